@@ -43,6 +43,7 @@ use prometheus_client::{
     encoding::{DescriptorEncoder, EncodeMetric},
     metrics::{
         counter::ConstCounter,
+        family::Family,
         gauge::{self, ConstGauge, Gauge},
         MetricType,
     },
@@ -85,6 +86,60 @@ pub fn register(reg: &mut Registry) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Describes how the running application was built
+///
+/// Fields that are unknown may be left empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// The application's version, e.g. from `CARGO_PKG_VERSION`
+    pub version: String,
+
+    /// The git commit from which the application was built
+    pub git_commit: String,
+
+    /// The version of rustc that compiled the application
+    pub rustc_version: String,
+}
+
+/// Registers a `build_info` gauge, with a constant value of 1, that is labeled
+/// with the application's build information.
+///
+/// This follows the common `*_build_info` convention so that other metrics may
+/// be correlated with the running version. Note that, unlike [`register`], this
+/// is typically called with the application's root registry.
+///
+/// ```
+/// let mut prom = prometheus_client::registry::Registry::default();
+/// kubert_prometheus_process::register_build_info(
+///     &mut prom,
+///     kubert_prometheus_process::BuildInfo {
+///         version: env!("CARGO_PKG_VERSION").to_string(),
+///         git_commit: option_env!("GIT_SHA").unwrap_or_default().to_string(),
+///         ..Default::default()
+///     },
+/// );
+/// ```
+pub fn register_build_info(reg: &mut Registry, info: BuildInfo) {
+    let BuildInfo {
+        version,
+        git_commit,
+        rustc_version,
+    } = info;
+    let build_info = Family::<Vec<(&'static str, String)>, Gauge>::default();
+    build_info
+        .get_or_create(&vec![
+            ("version", version),
+            ("git_commit", git_commit),
+            ("rustc_version", rustc_version),
+        ])
+        .set(1);
+    reg.register(
+        "build_info",
+        "Build information for the running application",
+        build_info,
+    );
+}
+
 #[derive(Debug)]
 struct ProcessCollector {
     start_time: Instant,
@@ -0,0 +1,80 @@
+//! Configures the sockets on which servers accept connections
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+/// Returns how long a server should wait before accepting another connection after `errors`
+/// consecutive accept errors
+///
+/// The delay doubles with each consecutive error, from 5ms up to 1s. Accept errors are usually
+/// caused by resource exhaustion (e.g. running out of file descriptors), so retrying immediately
+/// would only spin.
+pub(crate) fn error_delay(errors: u32) -> Duration {
+    Duration::from_millis(5 << errors.min(8)).min(Duration::from_secs(1))
+}
+
+/// Returns the address to which a server configured with `addr` binds
+///
+/// When an `interface` is named, the server binds to the interface's current address (preferring
+/// an address in the same family as `addr`) on `addr`'s port. Otherwise, `addr` is used as-is.
+pub(crate) fn bind_addr(addr: SocketAddr, interface: Option<&str>) -> std::io::Result<SocketAddr> {
+    let Some(interface) = interface else {
+        return Ok(addr);
+    };
+
+    let ips = if_addrs::get_if_addrs()?
+        .into_iter()
+        .filter(|iface| iface.name == interface)
+        .map(|iface| iface.ip())
+        .collect::<Vec<IpAddr>>();
+    let ip = ips
+        .iter()
+        .find(|ip| ip.is_ipv4() == addr.is_ipv4())
+        .or_else(|| ips.first())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("network interface {interface} has no addresses"),
+            )
+        })?;
+    Ok(SocketAddr::new(*ip, addr.port()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_delay_doubles() {
+        assert_eq!(error_delay(0), Duration::from_millis(5));
+        assert_eq!(error_delay(1), Duration::from_millis(10));
+        assert_eq!(error_delay(7), Duration::from_millis(640));
+        assert_eq!(error_delay(8), Duration::from_secs(1));
+        assert_eq!(error_delay(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn bind_addr_without_interface() {
+        let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+        assert_eq!(bind_addr(addr, None).expect("must resolve"), addr);
+    }
+
+    #[test]
+    fn bind_addr_unknown_interface() {
+        let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+        let error = bind_addr(addr, Some("kubert-test0")).expect_err("must not resolve");
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn bind_addr_loopback_interface() {
+        let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+        assert_eq!(
+            bind_addr(addr, Some("lo")).expect("must resolve"),
+            SocketAddr::from(([127, 0, 0, 1], 8080)),
+        );
+    }
+}
@@ -7,23 +7,27 @@ use std::{
     pin::Pin,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, RwLock, Weak,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tracing::{debug, info_span, Instrument};
 
 #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
-mod diagnostics;
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "runtime", feature = "runtime-diagnostics")))
+)]
+pub mod diagnostics;
 
-#[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
-pub(crate) use self::diagnostics::Diagnostics;
 #[cfg(all(
     feature = "runtime",
     feature = "runtime-diagnostics",
     feature = "lease"
 ))]
 pub(crate) use self::diagnostics::LeaseDiagnostics;
+#[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
+pub(crate) use self::diagnostics::{Diagnostics, WatchDiagnostics};
 
 /// An error binding an admin server.
 #[derive(Debug, thiserror::Error)]
@@ -37,9 +41,20 @@ type Response = hyper::Response<Body>;
 /// A handler for a request path.
 type HandlerFn = Box<dyn Fn(Request) -> Response + Send + Sync + 'static>;
 
+type ResponseFuture =
+    Pin<Box<dyn std::future::Future<Output = Result<Response, tokio::task::JoinError>> + Send>>;
+
+/// Marks a response that should not be compressed.
+#[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+#[derive(Clone, Copy, Debug)]
+struct Uncompressed;
+
 #[cfg(feature = "prometheus-client")]
 mod metrics;
 
+#[cfg(test)]
+mod tests;
+
 /// Command-line arguments used to configure an admin server
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "clap", derive(clap::Parser))]
@@ -48,34 +63,165 @@ pub struct AdminArgs {
     /// The admin server's address
     #[cfg_attr(feature = "clap", clap(long, default_value = "0.0.0.0:8080"))]
     pub admin_addr: SocketAddr,
+
+    /// Additional addresses on which the admin server accepts connections
+    #[cfg_attr(feature = "clap", clap(long = "admin-extra-addr"))]
+    pub admin_extra_addrs: Vec<SocketAddr>,
+
+    /// The status code returned by the `/ready` endpoint when the server is
+    /// not ready
+    #[cfg_attr(feature = "clap", clap(long, default_value = "500"))]
+    pub admin_not_ready_status: hyper::StatusCode,
+
+    /// The name of the network interface (e.g. `eth0`) to which the admin
+    /// server's sockets are bound
+    ///
+    /// See [`Builder::with_interface`].
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub admin_interface: Option<String>,
+
+    /// Whether `SO_REUSEADDR` is set on the admin server's sockets
+    ///
+    /// See [`Builder::with_reuse_address`].
+    #[cfg_attr(
+        feature = "clap",
+        clap(long, default_value_t = true, action = clap::ArgAction::Set)
+    )]
+    pub admin_reuse_address: bool,
+
+    /// The maximum number of pending connections queued by the admin server's
+    /// sockets
+    ///
+    /// See [`Builder::with_backlog`].
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub admin_backlog: Option<u32>,
+
+    /// The period after the admin server is spawned during which the `/ready`
+    /// endpoint reports that the server is starting (e.g. `30s`)
+    ///
+    /// See [`Builder::with_startup_grace`].
+    #[cfg_attr(
+        feature = "clap",
+        clap(long, value_parser = crate::duration::parse_duration)
+    )]
+    pub admin_startup_grace: Option<Duration>,
+
+    /// The maximum number of admin handlers that may execute concurrently
+    ///
+    /// See [`Builder::with_max_concurrent_handlers`].
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub admin_max_concurrent_handlers: Option<usize>,
+
+    /// Accept cleartext HTTP/2 connections on the admin server
+    ///
+    /// See [`Builder::with_http2`].
+    #[cfg(feature = "admin-http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "admin-http2")))]
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub admin_http2: bool,
 }
 
 /// Supports configuring an admin server
 #[cfg_attr(docsrs, doc(cfg(feature = "admin")))]
 pub struct Builder {
     addr: SocketAddr,
+    extra_addrs: Vec<SocketAddr>,
+    socket: SocketOptions,
     ready: Readiness,
+    not_ready_status: hyper::StatusCode,
+    startup_grace: Option<Duration>,
+    #[cfg(feature = "admin-http2")]
+    http2: bool,
+    live: Liveness,
     routes: AHashMap<String, HandlerFn>,
+    max_handlers: Option<usize>,
+    #[cfg(feature = "prometheus-client")]
+    prometheus: AHashMap<String, metrics::Prometheus>,
+    #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+    uncompressed: ahash::AHashSet<String>,
     #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
     diagnostics: Diagnostics,
 }
 
+/// Options applied to the admin server's listening sockets
+#[derive(Clone, Debug)]
+struct SocketOptions {
+    reuse_address: bool,
+    backlog: i32,
+    interface: Option<String>,
+}
+
 /// Supports spawning an admin server
 #[cfg_attr(docsrs, doc(cfg(feature = "admin")))]
 pub struct Bound {
     addr: SocketAddr,
-    ready: Readiness,
+    state: State,
+    startup_grace: Option<Duration>,
     listener: tokio::net::TcpListener,
-    server: hyper::server::conn::http1::Builder,
-    routes: AHashMap<String, HandlerFn>,
+    extra_listeners: Vec<tokio::net::TcpListener>,
+    server: ConnBuilder,
+    #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+    uncompressed: ahash::AHashSet<String>,
     #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
     diagnostics: Diagnostics,
 }
 
+/// State shared by each of the admin server's connections
+#[derive(Clone)]
+struct State {
+    ready: Readiness,
+    not_ready_status: hyper::StatusCode,
+    /// While set, a server that is not ready reports that it is starting.
+    starting_until: Option<Instant>,
+    live: Liveness,
+    routes: Arc<AHashMap<String, HandlerFn>>,
+    /// Limits the number of user-provided handlers that execute concurrently.
+    handlers: Option<Arc<tokio::sync::Semaphore>>,
+}
+
+#[cfg(not(feature = "admin-http2"))]
+type ConnBuilder = hyper::server::conn::http1::Builder;
+
+#[cfg(feature = "admin-http2")]
+type ConnBuilder = hyper_util::server::conn::auto::Builder<hyper_util::rt::TokioExecutor>;
+
 /// Controls how the admin server advertises readiness
 #[cfg_attr(docsrs, doc(cfg(feature = "admin")))]
-#[derive(Clone, Debug)]
-pub struct Readiness(Arc<AtomicBool>);
+#[derive(Clone)]
+pub struct Readiness {
+    ready: Arc<AtomicBool>,
+    pending: Arc<RwLock<Option<PendingFn>>>,
+    checks: Checks,
+    /// Counts changes to the readiness state
+    #[cfg(feature = "prometheus-client")]
+    transitions: prometheus_client::metrics::counter::Counter,
+}
+
+/// Names the components that are blocking readiness.
+type PendingFn = Box<dyn Fn() -> Vec<String> + Send + Sync + 'static>;
+
+/// Controls how the admin server advertises liveness
+///
+/// The server is considered live while all registered checks succeed.
+#[cfg_attr(docsrs, doc(cfg(feature = "admin")))]
+#[derive(Clone, Default)]
+pub struct Liveness(Checks);
+
+/// Removes a readiness or liveness check when dropped
+#[cfg_attr(docsrs, doc(cfg(feature = "admin")))]
+#[must_use = "the check is removed when the handle is dropped"]
+pub struct CheckHandle {
+    checks: Weak<RwLock<Vec<Arc<Check>>>>,
+    check: Weak<Check>,
+}
+
+/// A named readiness or liveness check.
+struct Check {
+    name: String,
+    check: Box<dyn Fn() -> bool + Send + Sync + 'static>,
+}
+
+type Checks = Arc<RwLock<Vec<Arc<Check>>>>;
 
 /// A handle to a running admin server
 #[cfg_attr(docsrs, doc(cfg(feature = "admin")))]
@@ -83,6 +229,7 @@ pub struct Readiness(Arc<AtomicBool>);
 pub struct Server {
     addr: SocketAddr,
     ready: Readiness,
+    live: Liveness,
     task: tokio::task::JoinHandle<Result<(), hyper::Error>>,
 }
 
@@ -92,6 +239,15 @@ impl Default for AdminArgs {
     fn default() -> Self {
         Self {
             admin_addr: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            admin_extra_addrs: Vec::new(),
+            admin_not_ready_status: hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            admin_interface: None,
+            admin_reuse_address: true,
+            admin_backlog: None,
+            admin_startup_grace: None,
+            admin_max_concurrent_handlers: None,
+            #[cfg(feature = "admin-http2")]
+            admin_http2: false,
         }
     }
 }
@@ -99,7 +255,40 @@ impl Default for AdminArgs {
 impl AdminArgs {
     /// Creates a new [`Builder`] frm the command-line arguments
     pub fn into_builder(self) -> Builder {
-        Builder::new(self.admin_addr)
+        let Self {
+            admin_addr,
+            admin_extra_addrs,
+            admin_not_ready_status,
+            admin_interface,
+            admin_reuse_address,
+            admin_backlog,
+            admin_startup_grace,
+            admin_max_concurrent_handlers,
+            #[cfg(feature = "admin-http2")]
+            admin_http2,
+        } = self;
+        let mut builder = admin_extra_addrs
+            .into_iter()
+            .fold(Builder::new(admin_addr), Builder::with_extra_addr)
+            .with_not_ready_status(admin_not_ready_status)
+            .with_reuse_address(admin_reuse_address);
+        #[cfg(feature = "admin-http2")]
+        {
+            builder = builder.with_http2(admin_http2);
+        }
+        if let Some(interface) = admin_interface {
+            builder = builder.with_interface(interface);
+        }
+        if let Some(backlog) = admin_backlog {
+            builder = builder.with_backlog(backlog);
+        }
+        if let Some(grace) = admin_startup_grace {
+            builder = builder.with_startup_grace(grace);
+        }
+        if let Some(max) = admin_max_concurrent_handlers {
+            builder = builder.with_max_concurrent_handlers(max);
+        }
+        builder
     }
 }
 
@@ -124,13 +313,73 @@ impl Builder {
     pub fn new(addr: SocketAddr) -> Self {
         Self {
             addr,
-            ready: Readiness(Arc::new(false.into())),
+            extra_addrs: Vec::new(),
+            socket: SocketOptions::default(),
+            ready: Readiness {
+                ready: Arc::new(false.into()),
+                pending: Default::default(),
+                checks: Default::default(),
+                #[cfg(feature = "prometheus-client")]
+                transitions: Default::default(),
+            },
+            not_ready_status: hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            startup_grace: None,
+            #[cfg(feature = "admin-http2")]
+            http2: false,
+            live: Liveness::default(),
             routes: Default::default(),
+            max_handlers: None,
+            #[cfg(feature = "prometheus-client")]
+            prometheus: Default::default(),
+            #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+            uncompressed: ["/live", "/ready"].into_iter().map(Into::into).collect(),
             #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
             diagnostics: Diagnostics::new(),
         }
     }
 
+    /// Adds an additional address on which the admin server accepts
+    /// connections
+    ///
+    /// This may be used, for instance, to serve both IPv4 and IPv6 clients.
+    pub fn with_extra_addr(mut self, addr: SocketAddr) -> Self {
+        self.extra_addrs.push(addr);
+        self
+    }
+
+    /// Controls whether `SO_REUSEADDR` is set on the admin server's sockets
+    ///
+    /// This allows the server to bind its port while connections from a
+    /// previous process are in the `TIME_WAIT` state, e.g. when restarting
+    /// quickly. It is enabled by default.
+    pub fn with_reuse_address(mut self, enabled: bool) -> Self {
+        self.socket.reuse_address = enabled;
+        self
+    }
+
+    /// Binds the admin server's sockets to the named network interface
+    ///
+    /// The interface's address is resolved when the server is bound, so the
+    /// server follows the interface's address across restarts. An address in
+    /// the same family as the configured address is preferred, and the
+    /// configured port is used. Binding fails if the interface does not exist
+    /// or has no addresses.
+    pub fn with_interface(mut self, interface: impl Into<String>) -> Self {
+        self.socket.interface = Some(interface.into());
+        self
+    }
+
+    /// Sets the maximum number of pending connections queued by the admin
+    /// server's sockets
+    ///
+    /// A larger backlog may help the server to handle bursts of connections.
+    /// By default, the backlog is 128. The kernel may cap the backlog (e.g.
+    /// with `net.core.somaxconn` on Linux).
+    pub fn with_backlog(mut self, backlog: u32) -> Self {
+        self.socket.backlog = backlog.try_into().unwrap_or(i32::MAX);
+        self
+    }
+
     /// Returns a readiness handle
     pub fn readiness(&self) -> Readiness {
         self.ready.clone()
@@ -141,14 +390,96 @@ impl Builder {
         self.ready.set(true);
     }
 
+    /// Sets the status code returned by the `/ready` endpoint when the server
+    /// is not ready
+    ///
+    /// By default, `500 Internal Server Error` is returned. Probes may prefer
+    /// `503 Service Unavailable` to indicate that the server is temporarily
+    /// unready.
+    pub fn with_not_ready_status(mut self, status: hyper::StatusCode) -> Self {
+        self.not_ready_status = status;
+        self
+    }
+
+    /// Reports that the server is starting, rather than not ready, for the
+    /// given period after the server is spawned
+    ///
+    /// While the server is starting and not yet ready, the `/ready` endpoint
+    /// returns `503 Service Unavailable` with a `starting` body. This allows
+    /// operators to distinguish a process that is still starting from one that
+    /// never became ready. By default, there is no startup grace period.
+    pub fn with_startup_grace(mut self, grace: Duration) -> Self {
+        self.startup_grace = Some(grace);
+        self
+    }
+
+    /// Limits the number of user-provided handlers that may execute
+    /// concurrently
+    ///
+    /// Handlers run on tokio's shared blocking thread pool. When this limit is
+    /// reached, additional requests to handler routes receive a `503 Service
+    /// Unavailable` response with a `handlers saturated` body and a
+    /// `Retry-After` header instead of waiting for a blocking thread. By
+    /// default, handler concurrency is not limited.
+    pub fn with_max_concurrent_handlers(mut self, max: usize) -> Self {
+        self.max_handlers = Some(max);
+        self
+    }
+
+    /// Configures whether the admin server accepts HTTP/2 connections
+    ///
+    /// When enabled, clients (e.g. metrics scrapers) may connect with
+    /// cleartext HTTP/2 using prior knowledge. HTTP/1 connections, like those
+    /// made by kubelet probes, continue to be served. By default, only HTTP/1
+    /// is served.
+    #[cfg(feature = "admin-http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "admin-http2")))]
+    pub fn with_http2(mut self, enabled: bool) -> Self {
+        self.http2 = enabled;
+        self
+    }
+
+    /// Returns a liveness handle
+    pub fn liveness(&self) -> Liveness {
+        self.live.clone()
+    }
+
     /// Use the provided prometheus Registry to export a `/metrics` endpoint
     /// on the admin server with process metrics. When the `tokio_unstable` cfg
     /// is set, tokio runtime metrics are also exported.
     ///
+    /// To export only the provided registry at `/metrics`, e.g. when process
+    /// metrics are registered elsewhere, use
+    /// `with_prometheus_handler("/metrics", registry)` instead.
+    ///
     /// This method is only available if the "prometheus-client" feature is enabled.
     #[cfg(feature = "prometheus-client")]
     #[cfg_attr(docsrs, doc(cfg(feature = "prometheus-client")))]
-    pub fn with_prometheus(self, mut registry: prometheus_client::registry::Registry) -> Self {
+    pub fn with_prometheus(self, registry: prometheus_client::registry::Registry) -> Self {
+        self.with_prometheus_at("/metrics", registry)
+    }
+
+    /// Use the provided prometheus Registry to export process metrics (and,
+    /// when the `tokio_unstable` cfg is set, tokio runtime metrics) at `path`.
+    ///
+    /// The registry also exports an `admin_readiness_transitions_total`
+    /// counter, which is incremented each time the server's readiness state
+    /// changes, so that controllers that flap between ready and not ready may
+    /// be detected.
+    ///
+    /// This may be used to serve process metrics separately from an
+    /// application's metrics, which may be exported via
+    /// [`Builder::with_prometheus_handler`]. Process metrics should only be
+    /// registered once.
+    ///
+    /// This method is only available if the "prometheus-client" feature is enabled.
+    #[cfg(feature = "prometheus-client")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "prometheus-client")))]
+    pub fn with_prometheus_at(
+        self,
+        path: impl ToString,
+        mut registry: prometheus_client::registry::Registry,
+    ) -> Self {
         #[cfg(not(tokio_unstable))]
         tracing::debug!("Tokio runtime metrics cannot be monitored without the tokio_unstable cfg");
         #[cfg(tokio_unstable)]
@@ -170,22 +501,42 @@ impl Builder {
             tracing::warn!(%error, "Process metrics cannot be monitored");
         }
 
-        self.with_prometheus_handler("/metrics", registry)
+        registry.sub_registry_with_prefix("admin").register(
+            "readiness_transitions",
+            "Count of changes to the admin server's readiness state",
+            self.ready.transitions.clone(),
+        );
+
+        self.with_prometheus_handler(path, registry)
     }
 
     /// Use the provided prometheus Registry to export an arbitrary metrics
     /// endpoint.
     ///
+    /// Unlike [`Builder::with_prometheus`], no process or tokio runtime
+    /// metrics are registered.
+    ///
+    /// Registries may be exported at several paths. If a registry has already
+    /// been exported at `path`, both registries are exported together.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if called with the path of a built-in endpoint (e.g.
+    /// `/ready` or `/live`).
+    ///
     /// This method is only available if the "prometheus-client" feature is enabled.
     #[cfg(feature = "prometheus-client")]
     #[cfg_attr(docsrs, doc(cfg(feature = "prometheus-client")))]
     pub fn with_prometheus_handler(
-        self,
+        mut self,
         path: impl ToString,
         registry: prometheus_client::registry::Registry,
     ) -> Self {
-        let prom = metrics::Prometheus::new(registry);
-        self.with_handler(path, move |req| prom.handle_metrics(req))
+        let path = path.to_string();
+        assert_overridable(&path);
+        self.routes.remove(&path);
+        self.prometheus.entry(path).or_default().push(registry);
+        self
     }
 
     /// Adds a request handler for `path` to the admin server.
@@ -196,42 +547,144 @@ impl Builder {
     ///
     /// # Panics
     ///
-    /// This method panics if called with the path `/ready` or `/live`, as these
-    /// paths would conflict with the built-in readiness and liveness endpoints.
+    /// This method panics if called with the path of a built-in endpoint (e.g.
+    /// `/ready` or `/live`), as these paths would conflict with the built-in
+    /// readiness, liveness, and diagnostics endpoints.
     pub fn with_handler(
         mut self,
         path: impl ToString,
         handler: impl Fn(Request) -> Response + Send + Sync + 'static,
     ) -> Self {
         let path = path.to_string();
-        assert_ne!(
-            path, "/ready",
-            "the built-in `/ready` handler cannot be overridden"
-        );
-        assert_ne!(
-            path, "/live",
-            "the built-in `/live` handler cannot be overridden"
-        );
+        assert_overridable(&path);
+        #[cfg(feature = "prometheus-client")]
+        self.prometheus.remove(&path);
         self.routes.insert(path, Box::new(handler));
         self
     }
 
+    /// Adds a `/version` endpoint that serves the provided fields as a JSON object.
+    ///
+    /// This is typically used to expose build metadata, e.g.:
+    ///
+    /// ```
+    /// let admin = kubert::admin::Builder::default().with_version_endpoint([
+    ///     ("version", env!("CARGO_PKG_VERSION")),
+    ///     ("gitCommit", option_env!("GIT_COMMIT").unwrap_or("unknown")),
+    /// ]);
+    /// ```
+    ///
+    /// Fields are serialized with their keys sorted. Any handler previously
+    /// registered for `/version` is replaced.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the same key is provided more than once.
+    pub fn with_version_endpoint<K, V>(self, fields: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: ToString,
+        V: ToString,
+    {
+        let mut map = serde_json::Map::new();
+        for (key, value) in fields {
+            let key = key.to_string();
+            let prior = map.insert(key.clone(), serde_json::Value::String(value.to_string()));
+            assert!(prior.is_none(), "duplicate version field: {key}");
+        }
+        let mut body = serde_json::to_vec(&map).expect("version fields must serialize");
+        body.push(b'\n');
+        let body = bytes::Bytes::from(body);
+
+        self.with_handler("/version", move |req| match *req.method() {
+            hyper::Method::GET | hyper::Method::HEAD => hyper::Response::builder()
+                .header(hyper::header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.clone()))
+                .unwrap(),
+            _ => hyper::Response::builder()
+                .status(hyper::StatusCode::METHOD_NOT_ALLOWED)
+                .header(hyper::header::ALLOW, "GET, HEAD")
+                .body(Body::default())
+                .unwrap(),
+        })
+    }
+
+    /// Configures whether responses for `path` may be compressed.
+    ///
+    /// By default, responses to all paths except `/live` and `/ready` may be
+    /// compressed when the client supports it. Probe responses are small, so
+    /// they do not benefit from compression.
+    ///
+    /// This method is only available if the "admin-brotli" or "admin-gzip"
+    /// features are enabled.
+    #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "admin-brotli", feature = "admin-gzip")))
+    )]
+    pub fn with_compression(mut self, path: impl ToString, enabled: bool) -> Self {
+        let path = path.to_string();
+        if enabled {
+            self.uncompressed.remove(&path);
+        } else {
+            self.uncompressed.insert(path);
+        }
+        self
+    }
+
     /// Binds the admin server without accepting connections
     pub fn bind(self) -> Result<Bound, BindError> {
         let Self {
             addr,
+            extra_addrs,
+            socket,
             ready,
-            routes,
+            not_ready_status,
+            startup_grace,
+            #[cfg(feature = "admin-http2")]
+            http2,
+            live,
+            #[allow(unused_mut)]
+            mut routes,
+            max_handlers,
+            #[cfg(feature = "prometheus-client")]
+            prometheus,
+            #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+            uncompressed,
             #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
             diagnostics,
         } = self;
 
-        let lis = std::net::TcpListener::bind(addr)?;
-        lis.set_nonblocking(true)?;
-        let listener = tokio::net::TcpListener::from_std(lis)?;
+        #[cfg(feature = "prometheus-client")]
+        for (path, prom) in prometheus {
+            routes.insert(path, Box::new(move |req| prom.handle_metrics(req)));
+        }
+
+        let listener = socket.bind(addr)?;
+        // Record the bound address, in case an ephemeral port was requested.
+        let addr = listener.local_addr()?;
+        let extra_listeners = extra_addrs
+            .into_iter()
+            .map(|addr| socket.bind(addr))
+            .collect::<Result<Vec<_>, _>>()?;
 
+        #[cfg(not(feature = "admin-http2"))]
         let mut server = hyper::server::conn::http1::Builder::new();
-        server
+        #[cfg(not(feature = "admin-http2"))]
+        let http1 = &mut server;
+
+        #[cfg(feature = "admin-http2")]
+        let mut server = {
+            let server = ConnBuilder::new(hyper_util::rt::TokioExecutor::new());
+            if http2 {
+                server
+            } else {
+                server.http1_only()
+            }
+        };
+        #[cfg(feature = "admin-http2")]
+        let mut http1 = server.http1();
+
+        http1
             // Allow weird clients (like netcat).
             .half_close(true)
             .timer(hyper_util::rt::TokioTimer::default())
@@ -240,12 +693,24 @@ impl Builder {
             // Use a small buffer, since we don't really transfer much data.
             .max_buf_size(8 * 1024);
 
+        let state = State {
+            ready,
+            not_ready_status,
+            starting_until: None,
+            live,
+            routes: Arc::new(routes),
+            handlers: max_handlers.map(|max| Arc::new(tokio::sync::Semaphore::new(max))),
+        };
+
         Ok(Bound {
             addr,
-            ready,
+            state,
+            startup_grace,
             server,
             listener,
-            routes,
+            extra_listeners,
+            #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+            uncompressed,
             #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
             diagnostics,
         })
@@ -255,48 +720,143 @@ impl Builder {
 impl fmt::Debug for Builder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut d = f.debug_struct("Builder");
-        d.field("addr", &self.addr).field("ready", &self.ready);
+        d.field("addr", &self.addr)
+            .field("extra_addrs", &self.extra_addrs)
+            .field("socket", &self.socket)
+            .field("ready", &self.ready)
+            .field("not_ready_status", &self.not_ready_status)
+            .field("startup_grace", &self.startup_grace)
+            .field("max_handlers", &self.max_handlers)
+            .field("live", &self.live);
+        #[cfg(feature = "admin-http2")]
+        d.field("http2", &self.http2);
 
         d.finish()
     }
 }
 
+fn assert_overridable(path: &str) {
+    assert_ne!(
+        path, "/ready",
+        "the built-in `/ready` handler cannot be overridden"
+    );
+    assert_ne!(
+        path, "/live",
+        "the built-in `/live` handler cannot be overridden"
+    );
+    #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
+    assert_ne!(
+        path, "/kubert.json",
+        "the built-in `/kubert.json` handler cannot be overridden"
+    );
+    #[cfg(all(
+        feature = "runtime",
+        feature = "runtime-diagnostics",
+        feature = "lease"
+    ))]
+    assert_ne!(
+        path, "/leases.json",
+        "the built-in `/leases.json` handler cannot be overridden"
+    );
+}
+
+// === impl SocketOptions ===
+
+impl Default for SocketOptions {
+    fn default() -> Self {
+        // Match the defaults used by `std::net::TcpListener::bind`.
+        Self {
+            reuse_address: true,
+            backlog: 128,
+            interface: None,
+        }
+    }
+}
+
+impl SocketOptions {
+    fn bind(&self, addr: SocketAddr) -> Result<tokio::net::TcpListener, BindError> {
+        use socket2::{Domain, Protocol, Socket, Type};
+
+        let addr = crate::accept::bind_addr(addr, self.interface.as_deref())?;
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        socket.set_reuse_address(self.reuse_address)?;
+        socket.bind(&addr.into())?;
+        socket.listen(self.backlog)?;
+        socket.set_nonblocking(true)?;
+        Ok(tokio::net::TcpListener::from_std(socket.into())?)
+    }
+}
+
 // === impl Bound ===
 
 impl Bound {
+    /// Returns the bound local address of the server
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
     /// Returns a readiness handle
     pub fn readiness(&self) -> Readiness {
-        self.ready.clone()
+        self.state.ready.clone()
     }
 
     /// Sets the initial readiness state to ready
     pub fn set_ready(&self) {
-        self.ready.set(true);
+        self.state.ready.set(true);
+    }
+
+    /// Returns a liveness handle
+    pub fn liveness(&self) -> Liveness {
+        self.state.live.clone()
     }
 
     /// Binds and runs the server on a background task, returning a handle
     pub fn spawn(self) -> Server {
         let Self {
-            ready,
+            state,
+            startup_grace,
             server,
             listener,
-            routes,
+            extra_listeners,
             addr,
+            #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+            uncompressed,
             #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
             diagnostics,
         } = self;
 
+        let state = State {
+            starting_until: startup_grace.map(|grace| Instant::now() + grace),
+            ..state
+        };
+        let ready = state.ready.clone();
+        let live = state.live.clone();
         let task = tokio::spawn({
-            let ready = ready.clone();
-            let routes = Arc::new(routes);
+            #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+            let uncompressed = Arc::new(uncompressed);
             #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
             let diagnostics = diagnostics.clone();
             async move {
+                // Back off while accepting fails consecutively (e.g. when the process has run
+                // out of file descriptors) so that the accept loop does not spin.
+                let mut accept_errors = 0;
+                let listeners = std::iter::once(listener)
+                    .chain(extra_listeners)
+                    .collect::<Vec<_>>();
+                let mut next_listener = 0;
                 loop {
-                    let (stream, client_addr) = match listener.accept().await {
-                        Ok(socket) => socket,
+                    let accept =
+                        future::poll_fn(|cx| poll_accept(&listeners, &mut next_listener, cx));
+                    let (stream, client_addr) = match accept.await {
+                        Ok(socket) => {
+                            accept_errors = 0;
+                            socket
+                        }
                         Err(error) => {
-                            tracing::warn!(%error, "Failed to accept connection");
+                            let delay = crate::accept::error_delay(accept_errors);
+                            accept_errors += 1;
+                            tracing::warn!(%error, ?delay, "Failed to accept connection");
+                            tokio::time::sleep(delay).await;
                             continue;
                         }
                     };
@@ -307,26 +867,44 @@ impl Bound {
 
                     let svc = {
                         use tower::ServiceExt;
-                        let ready = ready.clone();
-                        let routes = routes.clone();
+                        let state = state.clone();
+                        #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+                        let uncompressed = uncompressed.clone();
                         #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
                         let diagnostics = diagnostics.clone();
                         let svc = tower::service_fn(move |req: Request| {
-                            handle(
-                                &ready,
-                                &routes,
+                            #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+                            let compress = !uncompressed.contains(req.uri().path());
+                            let rsp = handle(
+                                &state,
                                 req,
                                 #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
                                 (client_addr, &diagnostics),
-                            )
+                            );
+                            #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+                            let rsp = mark_compression(compress, rsp);
+                            rsp
                         });
                         #[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
-                        let svc = tower_http::compression::Compression::new(svc);
+                        let svc = {
+                            use tower_http::compression::predicate::{DefaultPredicate, Predicate};
+                            tower_http::compression::Compression::new(svc)
+                                .compress_when(DefaultPredicate::new().and(
+                                |_: hyper::StatusCode,
+                                 _: hyper::Version,
+                                 _: &hyper::HeaderMap,
+                                 ext: &hyper::http::Extensions| {
+                                    ext.get::<Uncompressed>().is_none()
+                                },
+                            ))
+                        };
                         hyper::service::service_fn(move |req| svc.clone().oneshot(req))
                     };
 
                     let serve =
                         server.serve_connection(hyper_util::rt::TokioIo::new(stream), svc.clone());
+                    #[cfg(feature = "admin-http2")]
+                    let serve = serve.into_owned();
                     tokio::spawn(
                         async move {
                             debug!("Serving");
@@ -341,13 +919,37 @@ impl Bound {
             .instrument(info_span!("admin", port = %self.addr.port()))
         });
 
-        Server { task, addr, ready }
+        Server {
+            task,
+            addr,
+            ready,
+            live,
+        }
     }
 
     #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
     pub(crate) fn diagnostics(&self) -> &Diagnostics {
         &self.diagnostics
     }
+
+    /// Serves the contents of a reflector store at `path`
+    ///
+    /// # Panics
+    ///
+    /// This method panics if called with the path of a built-in endpoint or of
+    /// a configured handler, since stores would otherwise shadow them.
+    #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
+    pub(crate) fn register_store<T>(&self, path: String, store: kube_runtime::reflector::Store<T>)
+    where
+        T: kube_core::Resource + Clone + Send + Sync + 'static,
+        T::DynamicType: Clone + Eq + std::hash::Hash + Send + Sync,
+    {
+        assert!(
+            !self.state.routes.contains_key(&path),
+            "the `{path}` handler cannot be overridden"
+        );
+        self.diagnostics.register_store(path, store);
+    }
 }
 
 // === impl Readiness ===
@@ -355,12 +957,137 @@ impl Bound {
 impl Readiness {
     /// Gets the current readiness state
     pub fn get(&self) -> bool {
-        self.0.load(Ordering::Acquire)
+        self.ready.load(Ordering::Acquire)
     }
 
     /// Sets the readiness state
     pub fn set(&self, ready: bool) {
-        self.0.store(ready, Ordering::Release);
+        if self.ready.swap(ready, Ordering::AcqRel) != ready {
+            #[cfg(feature = "prometheus-client")]
+            self.transitions.inc();
+        }
+    }
+
+    /// Registers a function that names the components that are blocking readiness
+    ///
+    /// While the server is not ready, the names are listed in the body of `/ready` responses.
+    /// Only the most recently registered function is used.
+    pub fn report_pending(&self, pending: impl Fn() -> Vec<String> + Send + Sync + 'static) {
+        *self.pending.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(pending));
+    }
+
+    /// Returns the names of the components that are blocking readiness
+    fn pending(&self) -> Vec<String> {
+        self.pending
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|pending| pending())
+            .unwrap_or_default()
+    }
+
+    /// Registers a named check that is evaluated for each readiness probe
+    ///
+    /// The server is reported as not ready while the check returns false, even if the readiness
+    /// state is set. The check is removed when the returned handle is dropped.
+    pub fn add_check(
+        &self,
+        name: impl ToString,
+        check: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> CheckHandle {
+        Check::add(&self.checks, name.to_string(), Box::new(check))
+    }
+
+    /// Returns the names of all failing readiness checks
+    pub fn failing(&self) -> Vec<String> {
+        Check::failing(&self.checks)
+    }
+}
+
+impl fmt::Debug for Readiness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Readiness").field(&self.get()).finish()
+    }
+}
+
+// === impl Liveness ===
+
+impl Liveness {
+    /// Registers a named check that is evaluated for each liveness probe
+    ///
+    /// The server is reported as not live while the check returns false. The check is removed
+    /// when the returned handle is dropped.
+    pub fn add_check(
+        &self,
+        name: impl ToString,
+        check: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> CheckHandle {
+        Check::add(&self.0, name.to_string(), Box::new(check))
+    }
+
+    /// Returns the names of all failing checks
+    pub fn failing(&self) -> Vec<String> {
+        Check::failing(&self.0)
+    }
+}
+
+impl fmt::Debug for Liveness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let checks = self.0.read().unwrap_or_else(|e| e.into_inner());
+        f.debug_list()
+            .entries(checks.iter().map(|c| &c.name))
+            .finish()
+    }
+}
+
+// === impl Check ===
+
+impl Check {
+    fn add(
+        checks: &Checks,
+        name: String,
+        check: Box<dyn Fn() -> bool + Send + Sync + 'static>,
+    ) -> CheckHandle {
+        let check = Arc::new(Check { name, check });
+        let handle = CheckHandle {
+            checks: Arc::downgrade(checks),
+            check: Arc::downgrade(&check),
+        };
+        checks
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(check);
+        handle
+    }
+
+    fn failing(checks: &Checks) -> Vec<String> {
+        checks
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|c| !(c.check)())
+            .map(|c| c.name.clone())
+            .collect()
+    }
+}
+
+// === impl CheckHandle ===
+
+impl Drop for CheckHandle {
+    fn drop(&mut self) {
+        if let Some(checks) = self.checks.upgrade() {
+            checks
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .retain(|c| !std::ptr::eq(Arc::as_ptr(c), self.check.as_ptr()));
+        }
+    }
+}
+
+impl fmt::Debug for CheckHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.check.upgrade().map(|c| c.name.clone());
+        f.debug_struct("CheckHandle").field("name", &name).finish()
     }
 }
 
@@ -377,29 +1104,53 @@ impl Server {
         self.ready.clone()
     }
 
+    /// Returns a liveness handle
+    pub fn liveness(&self) -> Liveness {
+        self.live.clone()
+    }
+
     /// Returns the server tasks's join handle
     pub fn into_join_handle(self) -> tokio::task::JoinHandle<Result<(), hyper::Error>> {
         self.task
     }
 }
 
+/// Accepts a connection from the first of `listeners` that is ready, starting
+/// at `next`.
+///
+/// `next` is advanced past the listener that accepted a connection so that a
+/// busy listener cannot starve the others.
+fn poll_accept(
+    listeners: &[tokio::net::TcpListener],
+    next: &mut usize,
+    cx: &mut std::task::Context<'_>,
+) -> std::task::Poll<std::io::Result<(tokio::net::TcpStream, SocketAddr)>> {
+    for i in 0..listeners.len() {
+        let idx = (*next + i) % listeners.len();
+        if let std::task::Poll::Ready(res) = listeners[idx].poll_accept(cx) {
+            *next = (idx + 1) % listeners.len();
+            return std::task::Poll::Ready(res);
+        }
+    }
+    std::task::Poll::Pending
+}
+
 // === routes ===
 
 fn handle(
-    ready: &Readiness,
-    routes: &Arc<AHashMap<String, HandlerFn>>,
+    state: &State,
     req: Request,
     #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))] (client_addr, diagnostics): (
         std::net::SocketAddr,
         &Diagnostics,
     ),
-) -> Pin<Box<dyn std::future::Future<Output = Result<Response, tokio::task::JoinError>> + Send>> {
+) -> ResponseFuture {
     // Fast path for probe handlers.
     if req.uri().path() == "/live" {
-        return Box::pin(future::ok(handle_live(req)));
+        return Box::pin(future::ok(handle_live(&state.live, req)));
     }
     if req.uri().path() == "/ready" {
-        return Box::pin(future::ok(handle_ready(ready, req)));
+        return Box::pin(future::ok(handle_ready(state, req)));
     }
 
     #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
@@ -407,13 +1158,49 @@ fn handle(
         return Box::pin(future::ok(diagnostics.handle(client_addr, req)));
     }
 
-    if routes.contains_key(req.uri().path()) {
+    #[cfg(all(
+        feature = "runtime",
+        feature = "runtime-diagnostics",
+        feature = "lease"
+    ))]
+    if req.uri().path() == "/leases.json" {
+        return Box::pin(future::ok(diagnostics.handle_leases(client_addr, req)));
+    }
+
+    #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
+    if let Some(rsp) = diagnostics.handle_store(client_addr, &req) {
+        return Box::pin(future::ok(rsp));
+    }
+
+    if state.routes.contains_key(req.uri().path()) {
         // User-provided handlers--especially metrics collectors--may perform
         // blocking calls like stat. Prevent these tasks from blocking the
         // runtime.
-        let routes = routes.clone();
+        //
+        // When handler concurrency is limited, fail fast rather than queueing
+        // requests behind saturated handlers.
+        let permit = match state
+            .handlers
+            .as_ref()
+            .map(|h| h.clone().try_acquire_owned())
+        {
+            Some(Err(_)) => {
+                return Box::pin(future::ok(
+                    hyper::Response::builder()
+                        .status(hyper::StatusCode::SERVICE_UNAVAILABLE)
+                        .header(hyper::header::CONTENT_TYPE, "text/plain")
+                        .header(hyper::header::RETRY_AFTER, "1")
+                        .body("handlers saturated\n".into())
+                        .unwrap(),
+                ));
+            }
+            Some(Ok(permit)) => Some(permit),
+            None => None,
+        };
+        let routes = state.routes.clone();
         let path = req.uri().path().to_string();
         return Box::pin(tokio::task::spawn_blocking(move || {
+            let _permit = permit;
             let handler = routes.get(&path).expect("routes must contain path");
             handler(req)
         }));
@@ -427,13 +1214,41 @@ fn handle(
     ))
 }
 
-fn handle_live(req: Request) -> Response {
+#[cfg(any(feature = "admin-brotli", feature = "admin-gzip"))]
+fn mark_compression(compress: bool, rsp: ResponseFuture) -> ResponseFuture {
+    if compress {
+        return rsp;
+    }
+    Box::pin(async move {
+        let mut rsp = rsp.await?;
+        rsp.extensions_mut().insert(Uncompressed);
+        Ok(rsp)
+    })
+}
+
+fn handle_live(live: &Liveness, req: Request) -> Response {
     match *req.method() {
-        hyper::Method::GET | hyper::Method::HEAD => hyper::Response::builder()
-            .status(hyper::StatusCode::OK)
-            .header(hyper::header::CONTENT_TYPE, "text/plain")
-            .body("alive\n".into())
-            .unwrap(),
+        hyper::Method::GET | hyper::Method::HEAD => {
+            let failing = live.failing();
+            if failing.is_empty() {
+                return hyper::Response::builder()
+                    .status(hyper::StatusCode::OK)
+                    .header(hyper::header::CONTENT_TYPE, "text/plain")
+                    .body("alive\n".into())
+                    .unwrap();
+            }
+
+            let mut body = String::from("not alive\n");
+            for name in failing {
+                body.push_str(&name);
+                body.push('\n');
+            }
+            hyper::Response::builder()
+                .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                .header(hyper::header::CONTENT_TYPE, "text/plain")
+                .body(body.into())
+                .unwrap()
+        }
         _ => hyper::Response::builder()
             .status(hyper::StatusCode::METHOD_NOT_ALLOWED)
             .header(hyper::header::ALLOW, "GET, HEAD")
@@ -442,10 +1257,17 @@ fn handle_live(req: Request) -> Response {
     }
 }
 
-fn handle_ready(Readiness(ready): &Readiness, req: Request) -> Response {
+fn handle_ready(state: &State, req: Request) -> Response {
+    let State {
+        ready,
+        not_ready_status,
+        starting_until,
+        ..
+    } = state;
     match *req.method() {
         hyper::Method::GET | hyper::Method::HEAD => {
-            if ready.load(Ordering::Acquire) {
+            let failing = ready.failing();
+            if ready.get() && failing.is_empty() {
                 return hyper::Response::builder()
                     .status(hyper::StatusCode::OK)
                     .header(hyper::header::CONTENT_TYPE, "text/plain")
@@ -453,10 +1275,24 @@ fn handle_ready(Readiness(ready): &Readiness, req: Request) -> Response {
                     .unwrap();
             }
 
+            // Distinguish a server that is still starting from one that is not ready.
+            let starting = starting_until.is_some_and(|until| Instant::now() < until);
+            let (status, mut body) = if starting {
+                (
+                    hyper::StatusCode::SERVICE_UNAVAILABLE,
+                    String::from("starting\n"),
+                )
+            } else {
+                (*not_ready_status, String::from("not ready\n"))
+            };
+            for name in ready.pending().into_iter().chain(failing) {
+                body.push_str(&name);
+                body.push('\n');
+            }
             hyper::Response::builder()
-                .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                .status(status)
                 .header(hyper::header::CONTENT_TYPE, "text/plain")
-                .body("not ready\n".into())
+                .body(body.into())
                 .unwrap()
         }
         _ => hyper::Response::builder()
@@ -1,14 +1,24 @@
+//! Types describing the `/kubert.json` runtime diagnostics endpoint
+//!
+//! These types may be used to deserialize the endpoint's responses.
+
+use ahash::AHashMap;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use std::{net::SocketAddr, sync::Arc};
 
 #[cfg(feature = "lease")]
 mod lease;
+mod store;
 mod watch;
 
 #[cfg(feature = "lease")]
 pub(crate) use self::lease::LeaseDiagnostics;
-use self::watch::WatchDiagnostics;
+#[cfg(feature = "lease")]
+#[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
+pub use self::lease::{LeaseClaim, LeaseState, LeaseStats};
+pub(crate) use self::watch::WatchDiagnostics;
+pub use self::watch::{Resource, WatchError, WatchParams, WatchStats, WatchSummary};
 
 #[derive(Clone, Debug)]
 pub(crate) struct Diagnostics {
@@ -16,20 +26,44 @@ pub(crate) struct Diagnostics {
     watches: Arc<Mutex<Vec<watch::StateRef>>>,
     #[cfg(feature = "lease")]
     leases: Arc<Mutex<Vec<lease::StateRef>>>,
+    stores: Arc<RwLock<AHashMap<String, store::StoreRef>>>,
+}
+
+/// Query parameters supported by the diagnostics endpoint.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Query {
+    /// Whether each watch's resources should be enumerated.
+    resources: bool,
+    /// Only include watches of this kind.
+    kind: Option<String>,
+    /// Only include watches with this API URL.
+    api_url: Option<String>,
+    /// Only include leases used by this component.
+    component: Option<String>,
+    /// Whether the response should be serialized as compact, rather than pretty-printed, JSON.
+    compact: bool,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+/// A response from the `/kubert.json` endpoint
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct Summary {
-    initial_timestamp: Time,
-    current_timestamp: Time,
+#[non_exhaustive]
+pub struct Summary {
+    /// When the runtime was initialized
+    pub initial_timestamp: Time,
 
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    watches: Vec<watch::WatchSummary>,
+    /// When the summary was generated
+    pub current_timestamp: Time,
 
+    /// The runtime's watches
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watches: Vec<WatchSummary>,
+
+    /// The runtime's leases
     #[cfg(feature = "lease")]
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    leases: Vec<lease::LeaseState>,
+    #[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leases: Vec<LeaseState>,
 }
 
 // === impl Diagnostics ===
@@ -41,44 +75,53 @@ impl Diagnostics {
             watches: Default::default(),
             #[cfg(feature = "lease")]
             leases: Default::default(),
+            stores: Default::default(),
         }
     }
 
     pub(crate) fn register_watch<T>(
         &self,
+        name: Option<String>,
         api: &crate::runtime::Api<T>,
-        label_selector: Option<&str>,
+        config: &kube_runtime::watcher::Config,
+        dt: &T::DynamicType,
     ) -> WatchDiagnostics
     where
         T: kube_core::Resource,
-        T::DynamicType: Default,
     {
-        let wd = WatchDiagnostics::new(api.resource_url(), label_selector);
+        let kind = T::kind(dt);
+        let api_version = T::api_version(dt);
+        let wd = WatchDiagnostics::new(name, &kind, &api_version, api.resource_url(), config);
         self.watches.lock().push(wd.weak());
         wd
     }
 
-    pub(super) fn handle(&self, client_addr: SocketAddr, req: super::Request) -> super::Response {
-        if req.method() != hyper::Method::GET {
-            return hyper::Response::builder()
-                .status(hyper::StatusCode::METHOD_NOT_ALLOWED)
-                .header(hyper::header::ALLOW, "GET")
-                .body(super::Body::default())
-                .unwrap();
-        }
+    /// Serves the resources held by `store` at `path`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if called with the path of a built-in endpoint (e.g.
+    /// `/ready` or `/kubert.json`).
+    pub(crate) fn register_store<T>(&self, path: String, store: kube_runtime::reflector::Store<T>)
+    where
+        T: kube_core::Resource + Clone + Send + Sync + 'static,
+        T::DynamicType: Clone + Eq + std::hash::Hash + Send + Sync,
+    {
+        super::assert_overridable(&path);
+        self.stores
+            .write()
+            .insert(path, store::StoreRef::new(store));
+    }
 
-        if !client_addr.ip().is_loopback() {
-            tracing::info!(client.ip=%client_addr.ip(), "Rejecting non-loopback request for diagnostics");
-            return hyper::Response::builder()
-                .status(hyper::StatusCode::FORBIDDEN)
-                .body(super::Body::default())
-                .unwrap();
+    pub(super) fn handle(&self, client_addr: SocketAddr, req: super::Request) -> super::Response {
+        if let Some(rsp) = reject(client_addr, &req) {
+            return rsp;
         }
 
-        let with_resources = req.uri().query() == Some("resources");
-        let watches = self.summarize_watches(with_resources);
+        let query = Query::parse(req.uri().query().unwrap_or_default());
+        let watches = self.summarize_watches(&query);
         #[cfg(feature = "lease")]
-        let leases = self.summarize_leases();
+        let leases = self.summarize_leases(query.component.as_deref());
         let summary = Summary {
             initial_timestamp: Time(self.initial_time),
             current_timestamp: Time(chrono::Utc::now()),
@@ -87,24 +130,30 @@ impl Diagnostics {
             leases,
         };
 
-        let mut bytes = Vec::with_capacity(8 * 1024);
-        if let Err(error) = serde_json::to_writer_pretty(&mut bytes, &summary) {
-            tracing::error!(%error, "Failed to serialize runtime diagnostics");
-            return hyper::Response::builder()
-                .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
-                .body(super::Body::default())
-                .unwrap();
-        }
+        json_response(&summary, query.compact)
+    }
 
-        hyper::Response::builder()
-            .header(hyper::header::CONTENT_TYPE, "application/json")
-            .body(super::Body::from(bytes))
-            .unwrap()
+    /// Serves the resources of the store registered at the request's path, if
+    /// there is one.
+    ///
+    /// Like the full diagnostics endpoint, stores describe cluster resources,
+    /// so they are only served to loopback clients.
+    pub(super) fn handle_store(
+        &self,
+        client_addr: SocketAddr,
+        req: &super::Request,
+    ) -> Option<super::Response> {
+        let store = self.stores.read().get(req.uri().path()).cloned()?;
+        let query = Query::parse(req.uri().query().unwrap_or_default());
+        Some(
+            reject(client_addr, req)
+                .unwrap_or_else(|| json_response(&store.resources(), query.compact)),
+        )
     }
 
-    /// Collect the summaries of the remaining watches, with their resources
-    /// sorted by creation.
-    fn summarize_watches(&self, with_resources: bool) -> Vec<watch::WatchSummary> {
+    /// Collect the summaries of the remaining watches that match the query,
+    /// with their resources sorted by creation.
+    fn summarize_watches(&self, query: &Query) -> Vec<watch::WatchSummary> {
         let mut refs = self.watches.lock();
         // Clean up any dead weak refs, i.e. of watches that have been dropped.
         refs.retain(|w| w.upgrade().is_some());
@@ -112,21 +161,73 @@ impl Diagnostics {
             .filter_map(|wref| {
                 let watch = wref.upgrade()?;
                 let state = watch.read();
-                Some(state.summary(with_resources))
+                if !state.matches(query.kind.as_deref(), query.api_url.as_deref()) {
+                    return None;
+                }
+                Some(state.summary(query.resources))
             })
             .collect()
     }
 }
 
+// === impl Query ===
+
+impl Query {
+    /// Parses the query string of a diagnostics request, e.g.
+    /// `resources&kind=Pod`. Values are percent-decoded. Unknown parameters
+    /// are ignored.
+    fn parse(query: &str) -> Self {
+        let mut q = Self::default();
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            match (&*key, value) {
+                ("resources", _) => q.resources = true,
+                ("compact", _) => q.compact = true,
+                ("kind", kind) if !kind.is_empty() => q.kind = Some(kind.into_owned()),
+                ("api_url", url) if !url.is_empty() => q.api_url = Some(url.into_owned()),
+                ("component", c) if !c.is_empty() => q.component = Some(c.into_owned()),
+                _ => {}
+            }
+        }
+        q
+    }
+}
+
 #[cfg(feature = "lease")]
 impl Diagnostics {
-    pub(crate) fn register_lease(&self, params: &crate::LeaseParams) -> LeaseDiagnostics {
-        let ld = LeaseDiagnostics::new(params);
+    pub(crate) fn register_lease(
+        &self,
+        component: Option<String>,
+        params: &crate::LeaseParams,
+    ) -> LeaseDiagnostics {
+        let ld = LeaseDiagnostics::new(component, params);
         self.leases.lock().push(ld.weak());
         ld
     }
 
-    fn summarize_leases(&self) -> Vec<lease::LeaseState> {
+    /// Serves the state of each registered lease as a JSON array.
+    ///
+    /// Like the full diagnostics endpoint, lease states describe cluster
+    /// resources (including the identities of their holders), so they are only
+    /// served to loopback clients.
+    pub(super) fn handle_leases(
+        &self,
+        client_addr: SocketAddr,
+        req: super::Request,
+    ) -> super::Response {
+        if let Some(rsp) = reject(client_addr, &req) {
+            return rsp;
+        }
+
+        let query = Query::parse(req.uri().query().unwrap_or_default());
+        json_response(
+            &self.summarize_leases(query.component.as_deref()),
+            query.compact,
+        )
+    }
+
+    /// Collect the states of the remaining leases used by `component`, if
+    /// specified.
+    fn summarize_leases(&self, component: Option<&str>) -> Vec<lease::LeaseState> {
         let mut refs = self.leases.lock();
         // Clean up any dead weak refs, i.e. of leases that have been dropped.
         refs.retain(|w| w.upgrade().is_some());
@@ -134,8 +235,108 @@ impl Diagnostics {
             .filter_map(|wref| {
                 let lease = wref.upgrade()?;
                 let state = lease.read();
+                if component.is_some_and(|c| state.component.as_deref() != Some(c)) {
+                    return None;
+                }
                 Some(state.clone())
             })
             .collect()
     }
 }
+
+/// Returns an error response if the request is not a `GET` request from a
+/// loopback client.
+fn reject(client_addr: SocketAddr, req: &super::Request) -> Option<super::Response> {
+    if req.method() != hyper::Method::GET {
+        return Some(
+            hyper::Response::builder()
+                .status(hyper::StatusCode::METHOD_NOT_ALLOWED)
+                .header(hyper::header::ALLOW, "GET")
+                .body(super::Body::default())
+                .unwrap(),
+        );
+    }
+
+    if !client_addr.ip().is_loopback() {
+        tracing::info!(client.ip=%client_addr.ip(), "Rejecting non-loopback request for diagnostics");
+        return Some(
+            hyper::Response::builder()
+                .status(hyper::StatusCode::FORBIDDEN)
+                .body(super::Body::default())
+                .unwrap(),
+        );
+    }
+
+    None
+}
+
+fn json_response<T: serde::Serialize>(value: &T, compact: bool) -> super::Response {
+    let mut bytes = Vec::with_capacity(8 * 1024);
+    let res = if compact {
+        serde_json::to_writer(&mut bytes, value)
+    } else {
+        serde_json::to_writer_pretty(&mut bytes, value)
+    };
+    if let Err(error) = res {
+        tracing::error!(%error, "Failed to serialize runtime diagnostics");
+        return hyper::Response::builder()
+            .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+            .body(super::Body::default())
+            .unwrap();
+    }
+
+    hyper::Response::builder()
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(super::Body::from(bytes))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_query_flags() {
+        assert_eq!(Query::parse(""), Query::default());
+        assert_eq!(
+            Query::parse("resources&compact=1&unknown"),
+            Query {
+                resources: true,
+                compact: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_query_filters() {
+        assert_eq!(
+            Query::parse("kind=Pod&api_url=/api/v1/pods&component=controller&kind="),
+            Query {
+                kind: Some("Pod".to_string()),
+                api_url: Some("/api/v1/pods".to_string()),
+                component: Some("controller".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(Query::parse("kind=&api_url"), Query::default());
+    }
+
+    #[test]
+    fn parses_encoded_query_values() {
+        assert_eq!(
+            Query::parse("api_url=%2Fapi%2Fv1%2Fpods&component=my+controller"),
+            Query {
+                api_url: Some("/api/v1/pods".to_string()),
+                component: Some("my controller".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Query::parse("component=my%20controller")
+                .component
+                .as_deref(),
+            Some("my controller")
+        );
+    }
+}
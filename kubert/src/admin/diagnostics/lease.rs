@@ -9,42 +9,69 @@ pub(crate) struct LeaseDiagnostics(Arc<RwLock<LeaseState>>);
 
 pub(super) type StateRef = Weak<RwLock<LeaseState>>;
 
-#[derive(Clone, Debug, serde::Serialize)]
+/// Describes the state of a lease
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub(super) struct LeaseState {
-    name: String,
-    namespace: String,
-    claimant: String,
-    field_manager: Cow<'static, str>,
-    lease_duration_seconds: f64,
-    renew_grace_period_seconds: f64,
+#[non_exhaustive]
+pub struct LeaseState {
+    /// The name of the component using the lease, if one was provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    /// The name of the lease
+    pub name: String,
+    /// The namespace of the lease
+    pub namespace: String,
+    /// The identity of this process's claimant
+    pub claimant: String,
+    /// The field manager used to update the lease
+    pub field_manager: Cow<'static, str>,
+    /// The duration of a claim on the lease
+    pub lease_duration_seconds: f64,
+    /// The grace period before a claim's expiry at which it is renewed
+    pub renew_grace_period_seconds: f64,
+    /// Statistics about updates to the lease
     #[serde(flatten)]
-    stats: LeaseStats,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    resource_version: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    current: Option<Claim>,
+    pub stats: LeaseStats,
+    /// The lease's most recently observed resource version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_version: Option<String>,
+    /// The current claim on the lease, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<LeaseClaim>,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+/// Statistics about updates to a lease
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct LeaseStats {
-    updates: u64,
-    creation_timestamp: Time,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    last_update_timestamp: Option<Time>,
+#[non_exhaustive]
+pub struct LeaseStats {
+    /// The number of updates observed on the lease
+    pub updates: u64,
+    /// The number of times the lease has been claimed by a new holder
+    #[serde(default)]
+    pub transitions: u64,
+    /// When the lease was first observed
+    pub creation_timestamp: Time,
+    /// When the lease was last updated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_update_timestamp: Option<Time>,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
-struct Claim {
-    holder: String,
-    expiry: Time,
+/// Describes a claim on a lease
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[non_exhaustive]
+pub struct LeaseClaim {
+    /// The identity of the claim's holder
+    pub holder: String,
+    /// When the claim expires
+    pub expiry: Time,
 }
 
 // === impl LeaseDiagnostics ===
 
 impl LeaseDiagnostics {
     pub(super) fn new(
+        component: Option<String>,
         crate::LeaseParams {
             name,
             namespace,
@@ -56,19 +83,21 @@ impl LeaseDiagnostics {
     ) -> Self {
         let now = Time(chrono::Utc::now());
         Self(Arc::new(RwLock::new(LeaseState {
+            component,
             name: name.clone(),
             namespace: namespace.clone(),
             claimant: claimant.clone(),
             lease_duration_seconds: lease_duration.as_secs_f64(),
             renew_grace_period_seconds: renew_grace_period.as_secs_f64(),
             field_manager: field_manager.clone().unwrap_or(Cow::Borrowed(
-                crate::lease::LeaseManager::DEFAULT_FIELD_MANAGER,
+                <crate::lease::LeaseManager>::DEFAULT_FIELD_MANAGER,
             )),
             current: None,
             resource_version: None,
             stats: LeaseStats {
                 creation_timestamp: now,
                 updates: 0,
+                transitions: 0,
                 last_update_timestamp: None,
             },
         })))
@@ -91,10 +120,14 @@ impl LeaseDiagnostics {
             return;
         }
         let now = Time(chrono::Utc::now());
+        let holder = claim.as_deref().map(|c| &c.holder);
+        if holder.is_some() && holder != state.current.as_ref().map(|c| &c.holder) {
+            state.stats.transitions += 1;
+        }
         state.current = claim
             .as_deref()
             .cloned()
-            .map(|crate::lease::Claim { holder, expiry }| Claim {
+            .map(|crate::lease::Claim { holder, expiry }| LeaseClaim {
                 holder,
                 expiry: Time(expiry),
             });
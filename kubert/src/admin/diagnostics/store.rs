@@ -0,0 +1,38 @@
+use super::Resource;
+use kube_runtime::reflector::Store;
+use std::{hash::Hash, sync::Arc};
+
+/// Lists the resources held by a registered reflector [`Store`].
+#[derive(Clone)]
+pub(super) struct StoreRef(Arc<dyn Fn() -> Vec<Resource> + Send + Sync>);
+
+// === impl StoreRef ===
+
+impl StoreRef {
+    pub(super) fn new<T>(store: Store<T>) -> Self
+    where
+        T: kube_core::Resource + Clone + Send + Sync + 'static,
+        T::DynamicType: Clone + Eq + Hash + Send + Sync,
+    {
+        Self(Arc::new(move || {
+            store
+                .state()
+                .iter()
+                .map(|obj| Resource::from_meta(obj.meta()))
+                .collect()
+        }))
+    }
+
+    /// Returns the store's resources sorted by namespace and name.
+    pub(super) fn resources(&self) -> Vec<Resource> {
+        let mut resources = (self.0)();
+        resources.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+        resources
+    }
+}
+
+impl std::fmt::Debug for StoreRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreRef").finish_non_exhaustive()
+    }
+}
@@ -1,73 +1,178 @@
+use crate::admin::{CheckHandle, Liveness, Readiness};
 use ahash::AHashMap;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
 use kube_runtime::watcher;
-use parking_lot::RwLock;
-use std::sync::{Arc, Weak};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, OnceLock, Weak},
+};
 
-pub(crate) struct WatchDiagnostics(Arc<RwLock<WatchState>>);
+#[derive(Clone)]
+pub(crate) struct WatchDiagnostics {
+    state: Arc<RwLock<WatchState>>,
+    /// Liveness and readiness checks that are removed once the watch is dropped.
+    checks: Arc<Mutex<Vec<CheckHandle>>>,
+}
 
 pub(super) type StateRef = Weak<RwLock<WatchState>>;
 
 #[derive(Clone, Debug)]
 pub(super) struct WatchState {
+    name: Option<String>,
+    kind: String,
+    api_version: String,
     api_url: String,
     label_selector: String,
+    params: WatchParams,
     stats: WatchStats,
+    /// The time of the first error since the watch last produced an event.
+    failing_since: Option<chrono::DateTime<chrono::Utc>>,
+    /// The window over which recent errors are retained, if an error budget is configured.
+    error_window: Option<chrono::Duration>,
+    /// The times of the errors encountered within the error window.
+    recent_errors: VecDeque<chrono::DateTime<chrono::Utc>>,
     known: AHashMap<ObjRef, Resource>,
     resetting: AHashMap<ObjRef, Resource>,
+    /// The checksum of the known resources, computed lazily and cleared whenever they change.
+    checksum: OnceLock<Option<String>>,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+/// Describes the state of a watch
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub(super) struct WatchSummary {
-    api_url: String,
-    label_selector: String,
+#[non_exhaustive]
+pub struct WatchSummary {
+    /// The name used to distinguish the watch, if one was provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The kind of resource being watched
+    #[serde(default)]
+    pub kind: String,
+
+    /// The URL of the watched resource API
+    pub api_url: String,
+
+    /// The label selector used by the watch, if any
+    pub label_selector: String,
+
+    /// How the watch lists and watches resources
     #[serde(flatten)]
-    stats: WatchStats,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    checksum: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    resources: Option<Vec<Resource>>,
+    pub params: WatchParams,
+
+    /// Statistics about the watch's events
+    #[serde(flatten)]
+    pub stats: WatchStats,
+
+    /// A checksum of the watch's known resources, if there are any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+
+    /// The watch's known resources, sorted by creation, when requested with the `resources` query
+    /// parameter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<Vec<Resource>>,
+}
+
+/// Describes how the watch lists and watches resources.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct WatchParams {
+    /// The field selector used by the watch, if any
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub field_selector: String,
+
+    /// The page size used for initial lists, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u32>,
+
+    /// The list semantic, i.e. `MostRecent` or `Any`
+    pub list_semantic: String,
+
+    /// The initial list strategy, i.e. `ListWatch` or `StreamingList`
+    pub initial_list_strategy: String,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+/// Statistics about a watch's events
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct WatchStats {
-    creation_timestamp: Time,
+#[non_exhaustive]
+pub struct WatchStats {
+    /// When the watch was created
+    pub creation_timestamp: Time,
+
+    /// The number of errors encountered by the watch
+    pub errors: u64,
+    /// The most recent error encountered by the watch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<WatchError>,
+
+    /// The number of times the watch desynced from the API server (i.e. its
+    /// resource version was too old) and had to relist resources
+    #[serde(default)]
+    pub desyncs: u64,
+
+    /// The number of times the watch backed off after repeated errors
+    #[serde(default)]
+    pub backoffs: u64,
+    /// The total time the watch has spent backing off
+    #[serde(default)]
+    pub backoff_seconds_total: f64,
 
-    errors: u64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    last_error: Option<WatchError>,
+    /// The number of times the watch has (re)listed resources
+    pub resets: u64,
+    /// When the watch last (re)listed resources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_reset_timestamp: Option<Time>,
 
-    resets: u64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    last_reset_timestamp: Option<Time>,
+    /// The number of resource updates observed by the watch
+    pub applies: u64,
+    /// When the watch last observed a resource update
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_apply_timestamp: Option<Time>,
 
-    applies: u64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    last_apply_timestamp: Option<Time>,
+    /// The number of resource deletions observed by the watch
+    pub deletes: u64,
+    /// When the watch last observed a resource deletion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_delete_timestamp: Option<Time>,
 
-    deletes: u64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    last_delete_timestamp: Option<Time>,
+    /// The number of distinct resources observed by the watch over its
+    /// lifetime.
+    #[serde(default)]
+    pub total_seen: u64,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+/// Describes an error encountered by a watch
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct WatchError {
-    message: String,
-    timestamp: Time,
+#[non_exhaustive]
+pub struct WatchError {
+    /// The error message
+    pub message: String,
+    /// When the error occurred
+    pub timestamp: Time,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+/// Summarizes the metadata of a resource known to a watch
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct Resource {
-    creation_timestamp: Option<Time>,
-    uid: String,
-    name: String,
-    namespace: String,
-    generation: Option<i64>,
-    resource_version: String,
+#[non_exhaustive]
+pub struct Resource {
+    /// When the resource was created
+    pub creation_timestamp: Option<Time>,
+    /// The resource's UID
+    pub uid: String,
+    /// The resource's name
+    pub name: String,
+    /// The resource's namespace, if it is namespaced
+    pub namespace: String,
+    /// The resource's generation
+    pub generation: Option<i64>,
+    /// The resource's version
+    pub resource_version: String,
 }
 
 /// Map key for resources.
@@ -83,28 +188,67 @@ struct ObjRef {
 // === impl WatchDiagnostics ===
 
 impl WatchDiagnostics {
-    pub(super) fn new(api_url: &str, label_selector: Option<&str>) -> Self {
-        Self(Arc::new(RwLock::new(WatchState {
+    pub(super) fn new(
+        name: Option<String>,
+        kind: &str,
+        api_version: &str,
+        api_url: &str,
+        config: &watcher::Config,
+    ) -> Self {
+        let state = Arc::new(RwLock::new(WatchState {
+            name,
+            kind: kind.to_string(),
+            api_version: api_version.to_string(),
             api_url: api_url.to_string(),
-            label_selector: label_selector.unwrap_or_default().to_string(),
+            label_selector: config.label_selector.clone().unwrap_or_default(),
+            params: WatchParams {
+                field_selector: config.field_selector.clone().unwrap_or_default(),
+                page_size: match config.initial_list_strategy {
+                    watcher::InitialListStrategy::ListWatch => config.page_size,
+                    // Pagination is not used with streaming lists.
+                    watcher::InitialListStrategy::StreamingList => None,
+                },
+                list_semantic: match config.list_semantic {
+                    watcher::ListSemantic::MostRecent => "MostRecent",
+                    watcher::ListSemantic::Any => "Any",
+                }
+                .to_string(),
+                initial_list_strategy: match config.initial_list_strategy {
+                    watcher::InitialListStrategy::ListWatch => "ListWatch",
+                    watcher::InitialListStrategy::StreamingList => "StreamingList",
+                }
+                .to_string(),
+            },
             stats: WatchStats {
                 creation_timestamp: Time(chrono::Utc::now()),
                 errors: 0,
                 last_error: None,
+                desyncs: 0,
+                backoffs: 0,
+                backoff_seconds_total: 0.0,
                 resets: 0,
                 last_reset_timestamp: None,
                 applies: 0,
                 last_apply_timestamp: None,
                 deletes: 0,
                 last_delete_timestamp: None,
+                total_seen: 0,
             },
+            failing_since: None,
+            error_window: None,
+            recent_errors: VecDeque::new(),
             known: AHashMap::new(),
             resetting: AHashMap::new(),
-        })))
+            checksum: OnceLock::new(),
+        }));
+        Self {
+            state,
+            checks: Default::default(),
+        }
     }
 
     pub(super) fn weak(&self) -> Weak<RwLock<WatchState>> {
-        Arc::downgrade(&self.0)
+        Arc::downgrade(&self.state)
     }
 }
 
@@ -114,11 +258,23 @@ impl WatchDiagnostics {
     pub(crate) fn inspect<T>(&self, event: &watcher::Result<watcher::Event<T>>)
     where
         T: kube_client::Resource,
-        T::DynamicType: Default,
     {
+        let mut state = self.state.write();
+        let WatchState {
+            ref kind,
+            ref api_version,
+            ref mut known,
+            ref mut resetting,
+            ref mut stats,
+            ref mut failing_since,
+            ref mut checksum,
+            error_window,
+            ref mut recent_errors,
+            ..
+        } = *state;
         let to_key = |meta: &ObjectMeta| ObjRef {
-            kind: T::kind(&Default::default()).to_string(),
-            api_version: T::api_version(&Default::default()).to_string(),
+            kind: kind.clone(),
+            api_version: api_version.clone(),
             namespace: meta.namespace.clone(),
             name: meta.name.clone(),
             uid: meta.uid.clone(),
@@ -128,46 +284,62 @@ impl WatchDiagnostics {
         // example, all state for a cluster. We store only the metadata that we
         // can use to establish a comparison between multiple controller
         // instances and the kubernets API state.
-        let to_resource = |meta: &ObjectMeta| Resource {
-            creation_timestamp: meta.creation_timestamp.clone(),
-            name: meta.name.clone().unwrap_or_default(),
-            namespace: meta.namespace.clone().unwrap_or_default(),
-            resource_version: meta.resource_version.clone().unwrap_or_default(),
-            generation: meta.generation,
-            uid: meta.uid.clone().unwrap_or_default(),
-        };
+        let to_resource = Resource::from_meta;
 
         let now = Time(chrono::Utc::now());
-        let WatchState {
-            ref mut known,
-            ref mut resetting,
-            ref mut stats,
-            ..
-        } = *self.0.write();
+        if event.is_ok() {
+            *failing_since = None;
+        } else if failing_since.is_none() {
+            *failing_since = Some(now.0);
+        }
         match event {
             Ok(watcher::Event::Init) => {
                 resetting.clear();
             }
             Ok(watcher::Event::InitApply(res)) => {
-                resetting.insert(to_key(res.meta()), to_resource(res.meta()));
+                let key = to_key(res.meta());
+                if !known.contains_key(&key) && !resetting.contains_key(&key) {
+                    stats.total_seen += 1;
+                }
+                resetting.insert(key, to_resource(res.meta()));
             }
             Ok(watcher::Event::InitDone) => {
                 std::mem::swap(known, resetting);
+                checksum.take();
                 stats.resets += 1;
                 stats.last_reset_timestamp = Some(now);
             }
             Ok(watcher::Event::Apply(res)) => {
-                known.insert(to_key(res.meta()), to_resource(res.meta()));
+                // Resources are keyed by UID, so a resource that is not
+                // currently known has not been seen before (unless it was
+                // deleted and restored with the same UID).
+                if known
+                    .insert(to_key(res.meta()), to_resource(res.meta()))
+                    .is_none()
+                {
+                    stats.total_seen += 1;
+                }
+                checksum.take();
                 stats.applies += 1;
                 stats.last_apply_timestamp = Some(now);
             }
             Ok(watcher::Event::Delete(res)) => {
                 known.remove(&to_key(res.meta()));
+                checksum.take();
                 stats.deletes += 1;
                 stats.last_delete_timestamp = Some(now);
             }
             Err(error) => {
                 stats.errors += 1;
+                // The API server responds with 410 Gone when the watch's resource version is too
+                // old, after which the watcher relists all resources.
+                if matches!(error, watcher::Error::WatchError(rsp) if rsp.code == 410) {
+                    stats.desyncs += 1;
+                }
+                if let Some(window) = error_window {
+                    prune_errors(recent_errors, now.0, window);
+                    recent_errors.push_back(now.0);
+                }
                 stats.last_error = Some(WatchError {
                     message: error.to_string(),
                     timestamp: now,
@@ -175,29 +347,133 @@ impl WatchDiagnostics {
             }
         }
     }
+
+    /// Records that the watch backed off for `delay` after repeated errors.
+    pub(crate) fn backoff(&self, delay: std::time::Duration) {
+        let mut state = self.state.write();
+        state.stats.backoffs += 1;
+        state.stats.backoff_seconds_total += delay.as_secs_f64();
+    }
+
+    /// Adds a liveness check that fails while the watch has been failing
+    /// for longer than `threshold`.
+    ///
+    /// A watch is considered to be failing when its most recent error is
+    /// newer than `threshold` and it has not produced an event (i.e. an
+    /// apply, delete, or reset) since it started erroring. The check is
+    /// removed once the watch has been dropped.
+    pub(crate) fn add_liveness_check(
+        &self,
+        live: &Liveness,
+        name: impl ToString,
+        threshold: std::time::Duration,
+    ) {
+        let state = self.weak();
+        let check = live.add_check(name, move || match state.upgrade() {
+            Some(state) => !state.read().is_failing(chrono::Utc::now(), threshold),
+            None => true,
+        });
+        self.checks.lock().push(check);
+    }
+
+    /// Adds a readiness check that fails while the watch has encountered
+    /// more than `max_errors` errors within the most recent `window`.
+    ///
+    /// The check is removed once the watch has been dropped.
+    pub(crate) fn add_readiness_check(
+        &self,
+        ready: &Readiness,
+        name: impl ToString,
+        max_errors: usize,
+        window: std::time::Duration,
+    ) {
+        let window = chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX);
+        self.state.write().error_window = Some(window);
+        let state = self.weak();
+        let check = ready.add_check(name, move || match state.upgrade() {
+            Some(state) => {
+                let mut state = state.write();
+                prune_errors(&mut state.recent_errors, chrono::Utc::now(), window);
+                state.recent_errors.len() <= max_errors
+            }
+            None => true,
+        });
+        self.checks.lock().push(check);
+    }
+}
+
+/// Drops errors that occurred before the `window` preceding `now`.
+fn prune_errors(
+    errors: &mut VecDeque<chrono::DateTime<chrono::Utc>>,
+    now: chrono::DateTime<chrono::Utc>,
+    window: chrono::Duration,
+) {
+    while errors.front().is_some_and(|&t| now - t > window) {
+        errors.pop_front();
+    }
 }
 
 // === impl WatchState ===
 
 impl WatchState {
-    pub(super) fn summary(&self, with_resources: bool) -> WatchSummary {
+    fn is_failing(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        threshold: std::time::Duration,
+    ) -> bool {
+        let Ok(threshold) = chrono::Duration::from_std(threshold) else {
+            return false;
+        };
+        let Some(since) = self.failing_since else {
+            return false;
+        };
+        let recent = self
+            .stats
+            .last_error
+            .as_ref()
+            .is_some_and(|e| now - e.timestamp.0 <= threshold);
+        recent && now - since > threshold
+    }
+
+    /// Indicates whether the watch matches the given `kind` and `api_url` filters.
+    ///
+    /// Kinds are matched case-insensitively.
+    pub(super) fn matches(&self, kind: Option<&str>, api_url: Option<&str>) -> bool {
+        kind.map_or(true, |k| self.kind.eq_ignore_ascii_case(k))
+            && api_url.map_or(true, |u| self.api_url == u)
+    }
+
+    /// Returns the known resources sorted by creation.
+    fn sorted_resources(&self) -> Vec<Resource> {
         let mut resources = self.known.values().cloned().collect::<Vec<_>>();
         resources.sort_by_key(|meta| meta.creation_timestamp.as_ref().map(|Time(t)| *t));
+        resources
+    }
 
-        let checksum = if resources.is_empty() {
-            None
-        } else {
-            Some(checksum(&resources))
-        };
+    pub(super) fn summary(&self, with_resources: bool) -> WatchSummary {
+        let checksum = self
+            .checksum
+            .get_or_init(|| {
+                let resources = self.sorted_resources();
+                if resources.is_empty() {
+                    None
+                } else {
+                    Some(checksum(&resources))
+                }
+            })
+            .clone();
         let resources = if with_resources {
-            Some(resources)
+            Some(self.sorted_resources())
         } else {
             None
         };
 
         WatchSummary {
+            name: self.name.clone(),
+            kind: self.kind.clone(),
             api_url: self.api_url.clone(),
             label_selector: self.label_selector.clone(),
+            params: self.params.clone(),
             stats: self.stats.clone(),
             resources,
             checksum,
@@ -207,6 +483,19 @@ impl WatchState {
 
 // === impl Resource ===
 
+impl Resource {
+    pub(super) fn from_meta(meta: &ObjectMeta) -> Self {
+        Self {
+            creation_timestamp: meta.creation_timestamp.clone(),
+            name: meta.name.clone().unwrap_or_default(),
+            namespace: meta.namespace.clone().unwrap_or_default(),
+            resource_version: meta.resource_version.clone().unwrap_or_default(),
+            generation: meta.generation,
+            uid: meta.uid.clone().unwrap_or_default(),
+        }
+    }
+}
+
 impl std::hash::Hash for Resource {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.creation_timestamp
@@ -236,3 +525,75 @@ fn checksum<T: std::hash::Hash>(obj: &T) -> String {
     obj.hash(&mut hasher);
     format!("sha256:{:x}", hasher.0.finalize())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn watch_state() -> WatchState {
+        let wd = WatchDiagnostics::new(
+            None,
+            "Pod",
+            "v1",
+            "/api/v1/pods",
+            &watcher::Config::default(),
+        );
+        let state = wd.state.read().clone();
+        state
+    }
+
+    fn failing(
+        since: chrono::DateTime<chrono::Utc>,
+        last_error: chrono::DateTime<chrono::Utc>,
+    ) -> WatchState {
+        let mut state = watch_state();
+        state.failing_since = Some(since);
+        state.stats.last_error = Some(WatchError {
+            message: "boom".to_string(),
+            timestamp: Time(last_error),
+        });
+        state
+    }
+
+    #[test]
+    fn is_failing_after_threshold() {
+        let now = chrono::Utc::now();
+        let threshold = Duration::from_secs(60);
+        let secs = chrono::Duration::seconds;
+
+        assert!(!watch_state().is_failing(now, threshold));
+
+        // The watch must have been failing for longer than the threshold.
+        assert!(!failing(now - secs(60), now).is_failing(now, threshold));
+        assert!(failing(now - secs(61), now).is_failing(now, threshold));
+    }
+
+    #[test]
+    fn is_failing_requires_recent_error() {
+        let now = chrono::Utc::now();
+        let threshold = Duration::from_secs(60);
+        let secs = chrono::Duration::seconds;
+
+        // The most recent error must have occurred within the threshold.
+        assert!(failing(now - secs(120), now - secs(60)).is_failing(now, threshold));
+        assert!(!failing(now - secs(120), now - secs(61)).is_failing(now, threshold));
+    }
+
+    #[test]
+    fn removes_checks_when_dropped() {
+        let live = Liveness::default();
+        let wd = WatchDiagnostics::new(
+            None,
+            "Pod",
+            "v1",
+            "/api/v1/pods",
+            &watcher::Config::default(),
+        );
+        wd.add_liveness_check(&live, "watch pods", Duration::from_secs(60));
+        assert_eq!(format!("{live:?}"), r#"["watch pods"]"#);
+
+        drop(wd);
+        assert_eq!(format!("{live:?}"), "[]");
+    }
+}
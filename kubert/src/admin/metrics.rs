@@ -1,16 +1,16 @@
 use super::*;
 use hyper::header;
 
-#[derive(Clone, Debug)]
+/// Serves one or more registries from a single scrape path
+#[derive(Clone, Debug, Default)]
 pub(super) struct Prometheus {
-    registry: Arc<prometheus_client::registry::Registry>,
+    registries: Vec<Arc<prometheus_client::registry::Registry>>,
 }
 
 impl Prometheus {
-    pub(super) fn new(reg: prometheus_client::registry::Registry) -> Self {
-        Self {
-            registry: reg.into(),
-        }
+    /// Adds a registry to be exported along with any previously added registries
+    pub(super) fn push(&mut self, reg: prometheus_client::registry::Registry) {
+        self.registries.push(reg.into());
     }
 
     pub(super) fn handle_metrics(&self, req: Request) -> Response {
@@ -44,7 +44,10 @@ impl Prometheus {
 
     fn encode_body(&self) -> std::result::Result<super::Body, std::fmt::Error> {
         let mut buf = String::with_capacity(16 * 1024);
-        prometheus_client::encoding::text::encode(&mut buf, &self.registry)?;
+        for registry in &self.registries {
+            prometheus_client::encoding::text::encode_registry(&mut buf, registry)?;
+        }
+        prometheus_client::encoding::text::encode_eof(&mut buf)?;
         Ok(super::Body::new(buf.into()))
     }
 }
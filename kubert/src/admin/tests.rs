@@ -0,0 +1,124 @@
+use super::*;
+use std::sync::{mpsc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Issues a `GET` request for `path` and returns the raw HTTP/1 response.
+async fn get(addr: SocketAddr, path: &str) -> String {
+    let mut stream = tokio::net::TcpStream::connect(addr)
+        .await
+        .expect("must connect");
+    let req = format!("GET {path} HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n");
+    stream
+        .write_all(req.as_bytes())
+        .await
+        .expect("must write request");
+    let mut rsp = String::new();
+    stream
+        .read_to_string(&mut rsp)
+        .await
+        .expect("must read response");
+    rsp
+}
+
+#[tokio::test]
+async fn limits_concurrent_handlers() {
+    let (entered_tx, mut entered_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let release_rx = Mutex::new(release_rx);
+    let server = Builder::new(([127, 0, 0, 1], 0).into())
+        .with_max_concurrent_handlers(1)
+        .with_handler("/block", move |_| {
+            entered_tx.send(()).expect("test must be running");
+            release_rx
+                .lock()
+                .unwrap()
+                .recv()
+                .expect("test must release handler");
+            hyper::Response::new("done\n".into())
+        })
+        .bind()
+        .expect("must bind")
+        .spawn();
+    let addr = server.local_addr();
+
+    // Occupy the only handler permit.
+    let blocked = tokio::spawn(async move { get(addr, "/block").await });
+    entered_rx.recv().await.expect("handler must be entered");
+
+    let rsp = get(addr, "/block").await;
+    assert!(rsp.starts_with("HTTP/1.1 503"), "{rsp}");
+    assert!(rsp.contains("retry-after: 1\r\n"), "{rsp}");
+    assert!(rsp.ends_with("handlers saturated\n"), "{rsp}");
+
+    // Built-in endpoints are not limited.
+    let rsp = get(addr, "/live").await;
+    assert!(rsp.starts_with("HTTP/1.1 200"), "{rsp}");
+
+    release_tx.send(()).unwrap();
+    let rsp = blocked.await.unwrap();
+    assert!(rsp.starts_with("HTTP/1.1 200"), "{rsp}");
+    assert!(rsp.ends_with("done\n"), "{rsp}");
+
+    // Once the blocking task completes, its permit is released.
+    release_tx.send(()).unwrap();
+    let rsp = get(addr, "/block").await;
+    assert!(rsp.starts_with("HTTP/1.1 200"), "{rsp}");
+    entered_rx.recv().await.expect("handler must be entered");
+}
+
+#[tokio::test]
+async fn serves_extra_addrs() {
+    // Reserve an ephemeral port for the extra address.
+    let extra_addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .expect("must bind");
+    let server = Builder::new(([127, 0, 0, 1], 0).into())
+        .with_extra_addr(extra_addr)
+        .bind()
+        .expect("must bind")
+        .spawn();
+
+    let rsp = get(server.local_addr(), "/live").await;
+    assert!(rsp.starts_with("HTTP/1.1 200"), "{rsp}");
+    let rsp = get(extra_addr, "/live").await;
+    assert!(rsp.starts_with("HTTP/1.1 200"), "{rsp}");
+}
+
+#[tokio::test]
+async fn accepts_from_listeners_in_turn() {
+    let listeners = vec![
+        tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap(),
+    ];
+    let primary = listeners[0].local_addr().unwrap();
+    let extra = listeners[1].local_addr().unwrap();
+
+    // The primary listener has more pending connections than the extra one.
+    let mut clients = Vec::new();
+    for addr in [primary, primary, extra] {
+        clients.push(tokio::net::TcpStream::connect(addr).await.unwrap());
+    }
+
+    let mut next = 0;
+    let mut accepted = Vec::new();
+    for _ in 0..clients.len() {
+        let (stream, _) = future::poll_fn(|cx| poll_accept(&listeners, &mut next, cx))
+            .await
+            .expect("must accept");
+        accepted.push(stream.local_addr().unwrap());
+    }
+    assert_eq!(accepted, [primary, extra, primary]);
+}
+
+#[test]
+fn removes_dropped_checks() {
+    let live = Liveness::default();
+    let a = live.add_check("a", || false);
+    let b = live.add_check("b", || false);
+    assert_eq!(live.failing(), ["a", "b"]);
+
+    drop(a);
+    assert_eq!(live.failing(), ["b"]);
+    drop(b);
+    assert!(live.failing().is_empty());
+}
@@ -0,0 +1,134 @@
+//! Configures how failed operations are retried
+
+use std::time::Duration;
+
+/// Configures an exponential backoff with jitter
+///
+/// Delays start at a minimum duration and grow by 50% after each attempt, up to a maximum
+/// duration. Each delay is randomized by a jitter factor: e.g., with a factor of `0.5`, delays are
+/// between 50% and 150% of the nominal delay. Retries may optionally be abandoned after a maximum
+/// elapsed time.
+///
+/// Backoffs are used by the [`lease`](crate::lease) module when claiming leases and by
+/// [`LogAndSleep`](crate::errors::LogAndSleep) when a stream fails repeatedly.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    jitter: f64,
+    max_elapsed: Option<Duration>,
+}
+
+/// A sequence of delays produced by a [`Backoff`]
+///
+/// The elapsed time is measured from when the sequence is created or last reset.
+#[derive(Debug)]
+pub struct Delays(::backoff::ExponentialBackoff);
+
+// === impl Backoff ===
+
+impl Backoff {
+    const DEFAULT_JITTER: f64 = 0.5; // up to 50% of the backoff duration
+
+    /// Creates a backoff with delays between `min` and `max`
+    ///
+    /// By default, delays are randomized by up to 50% and retries are never abandoned.
+    pub fn exponential(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max,
+            jitter: Self::DEFAULT_JITTER,
+            max_elapsed: None,
+        }
+    }
+
+    /// Sets the factor, between 0 and 1, by which each delay is randomized
+    ///
+    /// A factor of 0 disables jitter.
+    pub fn with_jitter(mut self, factor: f64) -> Self {
+        self.jitter = factor.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the maximum time after which retries are abandoned
+    ///
+    /// If `None`, retries are never abandoned.
+    pub fn with_max_elapsed(mut self, max: Option<Duration>) -> Self {
+        self.max_elapsed = max;
+        self
+    }
+
+    /// Starts a new sequence of delays
+    pub fn delays(&self) -> Delays {
+        Delays(self.build())
+    }
+
+    pub(crate) fn build(&self) -> ::backoff::ExponentialBackoff {
+        ::backoff::ExponentialBackoffBuilder::default()
+            .with_initial_interval(self.min)
+            .with_max_interval(self.max)
+            .with_randomization_factor(self.jitter)
+            .with_max_elapsed_time(self.max_elapsed)
+            .build()
+    }
+}
+
+// === impl Delays ===
+
+impl Delays {
+    /// Returns the next delay, or `None` if the maximum elapsed time has been exceeded
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        ::backoff::backoff::Backoff::next_backoff(&mut self.0)
+    }
+
+    /// Restarts the sequence from the minimum delay and resets the elapsed time
+    pub fn reset(&mut self) {
+        ::backoff::backoff::Backoff::reset(&mut self.0);
+    }
+}
+
+impl Iterator for Delays {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        self.next_delay()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_to_max() {
+        let backoff = Backoff::exponential(Duration::from_millis(100), Duration::from_millis(300))
+            .with_jitter(0.0);
+        let mut delays = backoff.delays();
+        assert_eq!(delays.next_delay(), Some(Duration::from_millis(100)));
+        assert_eq!(delays.next_delay(), Some(Duration::from_millis(150)));
+        assert_eq!(delays.next_delay(), Some(Duration::from_millis(225)));
+        assert_eq!(delays.next_delay(), Some(Duration::from_millis(300)));
+        assert_eq!(delays.next_delay(), Some(Duration::from_millis(300)));
+
+        delays.reset();
+        assert_eq!(delays.next_delay(), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn jitters_delays() {
+        let backoff = Backoff::exponential(Duration::from_secs(1), Duration::from_secs(1));
+        for delay in backoff.delays().take(100) {
+            assert!(delay >= Duration::from_millis(500), "{delay:?}");
+            assert!(delay <= Duration::from_millis(1500), "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn abandons_after_max_elapsed() {
+        let backoff = Backoff::exponential(Duration::from_millis(1), Duration::from_millis(1))
+            .with_max_elapsed(Some(Duration::ZERO));
+        let mut delays = backoff.delays();
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(delays.next_delay(), None);
+    }
+}
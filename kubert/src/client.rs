@@ -1,8 +1,11 @@
 //! Utilities for configuring a [`kube_client::Client`] from the command line
 pub use kube_client::*;
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 use thiserror::Error;
 
+#[cfg(feature = "clap")]
+use crate::duration::parse_duration;
+
 /// Configures a Kubernetes client
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(docsrs, doc(cfg(feature = "client")))]
@@ -21,6 +24,9 @@ pub struct ClientArgs {
     pub user: Option<String>,
 
     /// The path to the kubeconfig file to use
+    ///
+    /// When set, the configuration is loaded from this file instead of the `$KUBECONFIG`
+    /// environment variable or `~/.kube/config`.
     #[cfg_attr(feature = "clap", clap(long))]
     pub kubeconfig: Option<PathBuf>,
 
@@ -31,6 +37,35 @@ pub struct ClientArgs {
     /// Group to impersonate for Kubernetes operations
     #[cfg_attr(feature = "clap", clap(long = "as-group"))]
     pub impersonate_group: Option<String>,
+
+    /// Submit all write requests as server-side dry-runs
+    ///
+    /// When set, `POST`, `PUT`, `PATCH`, and `DELETE` requests are sent with `dryRun=All` so that
+    /// they are validated but not persisted by the API server. Read requests are unaffected.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub dry_run: bool,
+
+    /// The timeout for establishing a connection to the API server (e.g. `10s` or `500ms`)
+    ///
+    /// When unset, the client's default connect timeout is used.
+    #[cfg_attr(
+        feature = "clap",
+        clap(long = "client-connect-timeout", value_parser = parse_duration)
+    )]
+    pub connect_timeout: Option<Duration>,
+
+    /// Request compressed responses from the API server
+    ///
+    /// When set, requests advertise the supported encodings with an `Accept-Encoding` header and
+    /// compressed responses are decompressed transparently. This can reduce the bandwidth used
+    /// by large list responses.
+    #[cfg(any(feature = "client-brotli", feature = "client-gzip"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "client-brotli", feature = "client-gzip")))
+    )]
+    #[cfg_attr(feature = "clap", clap(long = "client-decompression"))]
+    pub decompression: bool,
 }
 
 /// Indicates an error occurred while configuring the Kubernetes client
@@ -39,34 +74,80 @@ pub struct ClientArgs {
 #[non_exhaustive]
 pub enum ConfigError {
     /// Indicates that the kubeconfig file could not be read
-    #[error(transparent)]
+    #[error("failed to load kubeconfig: {0}")]
     Kubeconfig(#[from] config::KubeconfigError),
 
     /// Indicates that the in-cluster configuration could not be read
-    #[error(transparent)]
+    #[error("failed to load in-cluster configuration: {0}")]
     InCluster(#[from] config::InClusterError),
 
+    /// Indicates that neither a kubeconfig nor an in-cluster configuration could be loaded
+    #[error(
+        "failed to detect configuration: kubeconfig could not be loaded ({kubeconfig}) \
+         and in-cluster configuration could not be loaded ({in_cluster})"
+    )]
+    Detect {
+        /// The error encountered while loading the kubeconfig
+        kubeconfig: config::KubeconfigError,
+        /// The error encountered while loading the in-cluster configuration
+        #[source]
+        in_cluster: config::InClusterError,
+    },
+
+    /// Indicates that the client's TLS configuration could not be initialized
+    #[error("failed to configure client TLS: {0}")]
+    Tls(#[source] Error),
+
     /// Indicates that the client could not be initialized
-    #[error(transparent)]
-    Client(#[from] Error),
+    #[error("failed to initialize client: {0}")]
+    Client(#[source] Error),
 }
 
 impl ClientArgs {
     /// Initializes a Kubernetes client
     ///
-    /// This will respect the `$KUBECONFIG` environment variable, but otherwise default to
-    /// `~/.kube/config`. The _current-context_ is used unless `context` is set.
+    /// The kubeconfig is loaded from `kubeconfig`, if set. Otherwise, this will respect the
+    /// `$KUBECONFIG` environment variable, but otherwise default to `~/.kube/config`. The
+    /// _current-context_ is used unless `context` is set.
     ///
     /// This is basically equivalent to using `kube_client::Client::try_default`, except that it
     /// supports kubeconfig configuration from the command-line.
+    ///
+    /// # Errors
+    ///
+    /// When the kubeconfig is customized (e.g. with `kubeconfig` or `context`) and cannot be
+    /// loaded, [`ConfigError::Kubeconfig`] is returned. Otherwise, the in-cluster configuration is
+    /// used as a fallback, and [`ConfigError::Detect`] describes both failures if neither
+    /// configuration can be loaded. Failures to initialize the client's TLS configuration are
+    /// reported as [`ConfigError::Tls`] and other client failures as [`ConfigError::Client`].
     pub async fn try_client(self) -> Result<Client, ConfigError> {
-        let client = match self.load_local_config().await {
-            Ok(client) => client,
-            Err(e) if self.is_customized() => return Err(e),
-            Err(_) => Config::incluster()?,
+        let mut config = match self.load_local_config().await {
+            Ok(config) => config,
+            Err(e) if self.is_customized() => return Err(ConfigError::Kubeconfig(e)),
+            Err(kubeconfig) => Config::incluster().map_err(|in_cluster| ConfigError::Detect {
+                kubeconfig,
+                in_cluster,
+            })?,
         };
+        if let Some(timeout) = self.connect_timeout {
+            config.connect_timeout = Some(timeout);
+        }
+
+        let builder = kube_client::client::ClientBuilder::try_from(config)
+            .map_err(ConfigError::from_client_error)?;
+        let dry_run = self.dry_run.then(|| {
+            tracing::info!("Write requests will be submitted as dry-runs");
+            tower::util::MapRequestLayer::new(dry_run_request)
+        });
+        let builder = builder.with_layer(&tower::util::option_layer(dry_run));
 
-        client.try_into().map_err(Into::into)
+        #[cfg(any(feature = "client-brotli", feature = "client-gzip"))]
+        if self.decompression {
+            tracing::debug!("Requesting compressed responses");
+            let layer = tower_http::decompression::DecompressionLayer::new();
+            return Ok(builder.with_layer(&layer).build());
+        }
+        Ok(builder.build())
     }
 
     /// Indicates whether the command-line arguments attempt to customize the Kubernetes
@@ -84,7 +165,7 @@ impl ClientArgs {
     ///
     /// First, the `--kubeconfig` argument is used. If that is not set, the `$KUBECONFIG`
     /// environment variable is used. If that is not set, the `~/.kube/config` file is used.
-    async fn load_local_config(&self) -> Result<Config, ConfigError> {
+    async fn load_local_config(&self) -> Result<Config, config::KubeconfigError> {
         let options = config::KubeConfigOptions {
             context: self.context.clone(),
             cluster: self.cluster.clone(),
@@ -112,8 +193,144 @@ impl ClientArgs {
             }
         }
 
-        Config::from_custom_kubeconfig(kubeconfig, &options)
-            .await
-            .map_err(Into::into)
+        Config::from_custom_kubeconfig(kubeconfig, &options).await
+    }
+}
+
+/// Adds the `dryRun=All` query parameter to mutating requests.
+fn dry_run_request<B>(mut req: hyper::Request<B>) -> hyper::Request<B> {
+    use hyper::{http::uri, Method};
+
+    if !matches!(
+        *req.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    ) {
+        return req;
+    }
+
+    let path = req.uri().path();
+    let query = match req.uri().query() {
+        Some(q) if q.split('&').any(|p| p.starts_with("dryRun=")) => return req,
+        Some(q) if !q.is_empty() => format!("{q}&dryRun=All"),
+        _ => "dryRun=All".to_string(),
+    };
+    let mut parts = req.uri().clone().into_parts();
+    parts.path_and_query = match format!("{path}?{query}").parse::<uri::PathAndQuery>() {
+        Ok(pq) => Some(pq),
+        Err(error) => {
+            tracing::warn!(%error, "Failed to add dryRun query parameter");
+            return req;
+        }
+    };
+    match uri::Uri::from_parts(parts) {
+        Ok(uri) => *req.uri_mut() = uri,
+        Err(error) => tracing::warn!(%error, "Failed to add dryRun query parameter"),
+    }
+    req
+}
+
+// === impl ConfigError ===
+
+impl ConfigError {
+    fn from_client_error(error: Error) -> Self {
+        match error {
+            #[cfg(feature = "rustls-tls")]
+            Error::RustlsTls(_) => Self::Tls(error),
+            #[cfg(feature = "openssl-tls")]
+            Error::OpensslTls(_) => Self::Tls(error),
+            Error::TlsRequired => Self::Tls(error),
+            error => Self::Client(error),
+        }
+    }
+}
+
+impl From<Error> for ConfigError {
+    fn from(error: Error) -> Self {
+        Self::from_client_error(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dry_run_uri(method: hyper::Method, uri: &str) -> String {
+        let req = hyper::Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(())
+            .unwrap();
+        dry_run_request(req).uri().to_string()
+    }
+
+    #[test]
+    fn dry_run_mutating_requests() {
+        for method in [
+            hyper::Method::POST,
+            hyper::Method::PUT,
+            hyper::Method::PATCH,
+            hyper::Method::DELETE,
+        ] {
+            assert_eq!(
+                dry_run_uri(method.clone(), "/api/v1/namespaces/default/pods"),
+                "/api/v1/namespaces/default/pods?dryRun=All",
+            );
+            assert_eq!(
+                dry_run_uri(method.clone(), "/api/v1/pods/foo?fieldManager=kubert"),
+                "/api/v1/pods/foo?fieldManager=kubert&dryRun=All",
+            );
+            assert_eq!(
+                dry_run_uri(method, "/api/v1/pods/foo?dryRun=All"),
+                "/api/v1/pods/foo?dryRun=All",
+            );
+        }
+    }
+
+    #[test]
+    fn dry_run_ignores_reads() {
+        assert_eq!(
+            dry_run_uri(hyper::Method::GET, "/api/v1/pods?watch=true"),
+            "/api/v1/pods?watch=true",
+        );
+    }
+
+    #[test]
+    fn config_error_from_client_error() {
+        assert!(matches!(
+            ConfigError::from(Error::TlsRequired),
+            ConfigError::Tls(Error::TlsRequired)
+        ));
+        assert!(matches!(
+            ConfigError::from(Error::LinesCodecMaxLineLengthExceeded),
+            ConfigError::Client(Error::LinesCodecMaxLineLengthExceeded)
+        ));
+    }
+
+    #[test]
+    fn detect_error_describes_both_failures() {
+        let error = ConfigError::Detect {
+            kubeconfig: config::KubeconfigError::CurrentContextNotSet,
+            in_cluster: config::InClusterError::ReadEnvironmentVariable(
+                std::env::VarError::NotPresent,
+            ),
+        };
+        let msg = error.to_string();
+        assert!(msg.contains("failed to determine current context"), "{msg}");
+        assert!(msg.contains("incluster environment variable"), "{msg}");
+        assert!(std::error::Error::source(&error).is_some_and(|e| e.is::<config::InClusterError>()));
+    }
+
+    #[tokio::test]
+    async fn customized_kubeconfig_errors_are_not_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let args = ClientArgs {
+            kubeconfig: Some(dir.path().join("missing")),
+            ..Default::default()
+        };
+        match args.try_client().await {
+            Err(ConfigError::Kubeconfig(_)) => {}
+            Err(error) => panic!("unexpected error: {error}"),
+            Ok(_) => panic!("kubeconfig must not load"),
+        }
     }
 }
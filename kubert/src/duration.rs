@@ -0,0 +1,42 @@
+//! Parses durations from the command line
+
+use std::time::Duration;
+use thiserror::Error;
+
+/// Indicates that a duration could not be parsed from the command line
+#[derive(Debug, Error)]
+#[error("invalid duration: {0}")]
+pub(crate) struct InvalidDuration(String);
+
+/// Parses a duration with an optional `ms`, `s`, or `m` suffix. Unsuffixed values are
+/// interpreted as seconds.
+pub(crate) fn parse_duration(s: &str) -> Result<Duration, InvalidDuration> {
+    let s = s.trim();
+    let parse = |n: &str| n.parse::<u64>().map_err(|_| InvalidDuration(s.to_string()));
+    if let Some(ms) = s.strip_suffix("ms") {
+        return parse(ms).map(Duration::from_millis);
+    }
+    if let Some(secs) = s.strip_suffix('s') {
+        return parse(secs).map(Duration::from_secs);
+    }
+    if let Some(mins) = s.strip_suffix('m') {
+        return parse(mins).map(|m| Duration::from_secs(m.saturating_mul(60)));
+    }
+    parse(s).map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(10));
+        assert_eq!(parse_duration("10s").unwrap(), Duration::from_secs(10));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("1h").is_err());
+        assert!(parse_duration("-1s").is_err());
+    }
+}
@@ -3,11 +3,15 @@
 use futures_core::{Future, Stream, TryStream};
 use futures_util::ready;
 use std::{
+    fmt,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 use tokio::time;
-use tracing::info;
+use tracing::{info, warn};
+
+use crate::backoff::{Backoff, Delays};
 
 pin_project_lite::pin_project! {
     /// Wraps a [`Stream`], handling errors by logging them and applying a backoff
@@ -15,10 +19,20 @@ pin_project_lite::pin_project! {
     /// A delay is applied on consecutive errors--that is, after an error, the stream will be polled
     /// immediately and if that second poll fails, a delay is applied before polling the stream
     /// again until it succeeds.
-    #[derive(Debug)]
-    pub struct LogAndSleep<S> {
-        delay: time::Duration,
+    ///
+    /// Errors are logged with an `error_kind` field, as determined by the stream's [`Classify`]
+    /// implementation (see [`LogAndSleep::classify_with`]).
+    pub struct LogAndSleep<S, C = ()> {
+        delay: Delay,
         failed: bool,
+        on_backoff: Option<OnBackoff>,
+        classify: C,
+        fail_fast: bool,
+        on_fail_fast: Option<OnFailFast>,
+        consecutive_errors: usize,
+        max_consecutive_errors: Option<usize>,
+        on_exhausted: Option<OnExhausted>,
+        terminated: bool,
 
         #[pin]
         sleep: time::Sleep,
@@ -29,12 +43,81 @@ pin_project_lite::pin_project! {
     }
 }
 
+/// Classifies the errors of a stream so that they may be logged and handled by kind
+pub trait Classify<E> {
+    /// Returns the kind of the given error
+    fn classify(&self, error: &E) -> ErrorKind;
+}
+
+/// Describes the kind of error encountered by a stream
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The client's credentials were rejected (i.e. HTTP 401)
+    Unauthorized,
+
+    /// The client is not authorized to access the resource (i.e. HTTP 403)
+    Forbidden,
+
+    /// The resource does not exist (i.e. HTTP 404), e.g. because its CRD is not installed
+    NotFound,
+
+    /// The connection to the server failed or was interrupted
+    Network,
+
+    /// A response could not be decoded
+    Decode,
+
+    /// The error could not be classified
+    Other,
+}
+
+/// Classifies errors from [`kube_runtime::watcher()`] streams
+#[cfg(feature = "runtime")]
+#[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct WatchErrors;
+
+/// Determines how long a [`LogAndSleep`] waits after consecutive errors
+#[derive(Debug)]
+enum Delay {
+    Fixed(time::Duration),
+    Backoff(Delays),
+}
+
+type OnBackoff = Arc<dyn Fn(time::Duration) + Send + Sync + 'static>;
+
+type OnExhausted = Arc<dyn Fn() + Send + Sync + 'static>;
+
+type OnFailFast = Arc<dyn Fn(ErrorKind, &dyn fmt::Display) + Send + Sync + 'static>;
+
 impl<S> LogAndSleep<S> {
     /// Creates an error handling stream that uses a fixed delay on consecutive errors
     pub fn fixed_delay(delay: time::Duration, stream: S) -> Self {
+        Self::new(Delay::Fixed(delay), stream)
+    }
+
+    /// Creates an error handling stream that uses an exponential backoff on consecutive errors
+    ///
+    /// The backoff is reset when the stream succeeds. If the stream fails for longer than the
+    /// backoff's maximum elapsed time, the stream ends as if it had exhausted its maximum
+    /// consecutive errors (see [`LogAndSleep::on_exhausted`]).
+    pub fn backoff(backoff: &Backoff, stream: S) -> Self {
+        Self::new(Delay::Backoff(backoff.delays()), stream)
+    }
+
+    fn new(delay: Delay, stream: S) -> Self {
         Self {
             delay,
             failed: false,
+            on_backoff: None,
+            classify: (),
+            fail_fast: false,
+            on_fail_fast: None,
+            consecutive_errors: 0,
+            max_consecutive_errors: None,
+            on_exhausted: None,
+            terminated: false,
             sleep: time::sleep(time::Duration::ZERO),
             sleeping: false,
             stream,
@@ -42,15 +125,96 @@ impl<S> LogAndSleep<S> {
     }
 }
 
-impl<S> Stream for LogAndSleep<S>
+impl<S, C> LogAndSleep<S, C> {
+    /// Invokes the provided function with the delay each time the stream backs off
+    pub fn on_backoff(mut self, f: impl Fn(time::Duration) + Send + Sync + 'static) -> Self {
+        self.on_backoff = Some(Arc::new(f));
+        self
+    }
+
+    /// Classifies the stream's errors with the given [`Classify`] implementation
+    pub fn classify_with<C2>(self, classify: C2) -> LogAndSleep<S, C2> {
+        LogAndSleep {
+            delay: self.delay,
+            failed: self.failed,
+            on_backoff: self.on_backoff,
+            classify,
+            fail_fast: self.fail_fast,
+            on_fail_fast: self.on_fail_fast,
+            consecutive_errors: self.consecutive_errors,
+            max_consecutive_errors: self.max_consecutive_errors,
+            on_exhausted: self.on_exhausted,
+            terminated: self.terminated,
+            sleep: self.sleep,
+            sleeping: self.sleeping,
+            stream: self.stream,
+        }
+    }
+
+    /// Ends the stream, instead of retrying, when it encounters a permanent error
+    ///
+    /// See [`ErrorKind::is_permanent`] and [`LogAndSleep::on_fail_fast`].
+    pub fn fail_fast(mut self) -> Self {
+        self.fail_fast = true;
+        self
+    }
+
+    /// Invokes the provided function with the error that ended the stream when it fails fast
+    ///
+    /// This distinguishes a stream that ended due to a permanent error from one that completed
+    /// normally. See [`LogAndSleep::fail_fast`].
+    pub fn on_fail_fast(
+        mut self,
+        f: impl Fn(ErrorKind, &dyn fmt::Display) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_fail_fast = Some(Arc::new(f));
+        self
+    }
+
+    /// Ends the stream, instead of retrying, once it encounters `max` consecutive errors
+    pub fn max_consecutive_errors(mut self, max: usize) -> Self {
+        self.max_consecutive_errors = Some(max);
+        self
+    }
+
+    /// Invokes the provided function when the stream ends because it failed too many times
+    /// consecutively or for too long
+    ///
+    /// See [`LogAndSleep::max_consecutive_errors`] and [`LogAndSleep::backoff`].
+    pub fn on_exhausted(mut self, f: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_exhausted = Some(Arc::new(f));
+        self
+    }
+}
+
+impl<S: fmt::Debug, C> fmt::Debug for LogAndSleep<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogAndSleep")
+            .field("delay", &self.delay)
+            .field("failed", &self.failed)
+            .field("fail_fast", &self.fail_fast)
+            .field("consecutive_errors", &self.consecutive_errors)
+            .field("max_consecutive_errors", &self.max_consecutive_errors)
+            .field("sleep", &self.sleep)
+            .field("sleeping", &self.sleeping)
+            .field("stream", &self.stream)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, C> Stream for LogAndSleep<S, C>
 where
     S: TryStream,
     S::Error: std::fmt::Display,
+    C: Classify<S::Error>,
 {
     type Item = S::Ok;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut this = self.project();
+        if *this.terminated {
+            return Poll::Ready(None);
+        }
 
         loop {
             if *this.sleeping {
@@ -61,22 +225,65 @@ where
             match ready!(this.stream.as_mut().try_poll_next(cx)) {
                 None => {
                     *this.failed = false;
+                    *this.consecutive_errors = 0;
                     return Poll::Ready(None);
                 }
 
                 Some(Ok(item)) => {
                     *this.failed = false;
+                    *this.consecutive_errors = 0;
                     return Poll::Ready(Some(item));
                 }
 
                 Some(Err(error)) => {
-                    info!(%error, "stream failed");
+                    let kind = this.classify.classify(&error);
+                    if kind.is_permanent() {
+                        warn!(%error, error_kind = %kind, "stream failed");
+                        if *this.fail_fast {
+                            *this.terminated = true;
+                            if let Some(on_fail_fast) = this.on_fail_fast.as_ref() {
+                                on_fail_fast(kind, &error);
+                            }
+                            return Poll::Ready(None);
+                        }
+                    } else {
+                        info!(%error, error_kind = %kind, "stream failed");
+                    }
+
+                    *this.consecutive_errors += 1;
+                    if let Some(max) = *this.max_consecutive_errors {
+                        if *this.consecutive_errors >= max {
+                            warn!(errors = max, "stream failed too many times; giving up");
+                            *this.terminated = true;
+                            if let Some(on_exhausted) = this.on_exhausted.as_ref() {
+                                on_exhausted();
+                            }
+                            return Poll::Ready(None);
+                        }
+                    }
+
                     if *this.failed {
-                        *this.sleeping = true;
                         // If the stream had failed in its previous poll, then set a delay.
-                        this.sleep
-                            .as_mut()
-                            .reset(time::Instant::now() + *this.delay);
+                        let delay = match this.delay {
+                            Delay::Fixed(delay) => Some(*delay),
+                            Delay::Backoff(delays) => delays.next_delay(),
+                        };
+                        let Some(delay) = delay else {
+                            warn!("stream failed for too long; giving up");
+                            *this.terminated = true;
+                            if let Some(on_exhausted) = this.on_exhausted.as_ref() {
+                                on_exhausted();
+                            }
+                            return Poll::Ready(None);
+                        };
+                        *this.sleeping = true;
+                        this.sleep.as_mut().reset(time::Instant::now() + delay);
+                        if let Some(on_backoff) = this.on_backoff.as_ref() {
+                            on_backoff(delay);
+                        }
+                    } else if let Delay::Backoff(delays) = this.delay {
+                        // Start a new backoff for each series of consecutive errors.
+                        delays.reset();
                     }
                     *this.failed = true;
                 }
@@ -85,9 +292,94 @@ where
     }
 }
 
+// === impl ErrorKind ===
+
+impl ErrorKind {
+    /// Returns a stable name for the error kind, suitable for logging
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden => "forbidden",
+            Self::NotFound => "not_found",
+            Self::Network => "network",
+            Self::Decode => "decode",
+            Self::Other => "other",
+        }
+    }
+
+    /// Indicates whether the error is unlikely to be resolved by retrying
+    ///
+    /// Authorization failures and missing resources typically indicate misconfiguration (e.g. of
+    /// RBAC or CRDs), so they are logged at the `WARN` level.
+    pub fn is_permanent(&self) -> bool {
+        matches!(self, Self::Unauthorized | Self::Forbidden | Self::NotFound)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// === impl Classify ===
+
+/// Errors are not classified by default.
+impl<E> Classify<E> for () {
+    fn classify(&self, _: &E) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+// === impl WatchErrors ===
+
+#[cfg(feature = "runtime")]
+impl WatchErrors {
+    fn classify_client(error: &kube_client::Error) -> ErrorKind {
+        match error {
+            kube_client::Error::Api(rsp) => Self::classify_response(rsp),
+            kube_client::Error::HyperError(_) | kube_client::Error::Service(_) => {
+                ErrorKind::Network
+            }
+            kube_client::Error::SerdeError(_)
+            | kube_client::Error::FromUtf8(_)
+            | kube_client::Error::LinesCodecMaxLineLengthExceeded
+            | kube_client::Error::ReadEvents(_) => ErrorKind::Decode,
+            // Failing to obtain credentials (e.g. when an exec plugin fails to refresh a token)
+            // may be resolved by retrying. Only the API server's rejections are permanent.
+            kube_client::Error::Auth(_) => ErrorKind::Other,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    fn classify_response(rsp: &kube_core::ErrorResponse) -> ErrorKind {
+        match rsp.code {
+            401 => ErrorKind::Unauthorized,
+            403 => ErrorKind::Forbidden,
+            404 => ErrorKind::NotFound,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl Classify<kube_runtime::watcher::Error> for WatchErrors {
+    fn classify(&self, error: &kube_runtime::watcher::Error) -> ErrorKind {
+        use kube_runtime::watcher::Error;
+        match error {
+            Error::InitialListFailed(e) | Error::WatchStartFailed(e) | Error::WatchFailed(e) => {
+                Self::classify_client(e)
+            }
+            Error::WatchError(rsp) => Self::classify_response(rsp),
+            Error::NoResourceVersion => ErrorKind::Other,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::LogAndSleep;
+    use super::{Backoff, Classify, ErrorKind, LogAndSleep};
+    use std::sync::{atomic::Ordering, Arc};
     use tokio::time;
     use tokio_stream::wrappers::ReceiverStream;
     use tokio_test::{assert_pending, assert_ready_eq, task};
@@ -139,4 +431,161 @@ mod test {
         tokio::time::sleep(time::Duration::from_millis(1)).await;
         assert_ready_eq!(rx.poll_next(), Some("third"));
     }
+
+    #[tokio::test]
+    async fn backs_off_on_repeated_errors() {
+        time::pause();
+        let backoff = Backoff::exponential(DELAY, DELAY * 2).with_jitter(0.0);
+        let backoffs = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (tx, mut rx) = {
+            let (tx, rx) = tokio::sync::mpsc::channel(4);
+            let backoffs = backoffs.clone();
+            let rx = task::spawn(
+                LogAndSleep::backoff(&backoff, ReceiverStream::new(rx))
+                    .on_backoff(move |d| backoffs.lock().unwrap().push(d)),
+            );
+            (tx, rx)
+        };
+
+        tx.try_send(Err("first")).expect("stream not full");
+        tx.try_send(Err("second")).expect("stream not full");
+        tx.try_send(Err("third")).expect("stream not full");
+        tx.try_send(Ok("fourth")).expect("stream not full");
+        assert_pending!(rx.poll_next());
+        assert_eq!(*backoffs.lock().unwrap(), vec![DELAY]);
+
+        tokio::time::sleep(DELAY).await;
+        assert_pending!(rx.poll_next());
+        assert_eq!(*backoffs.lock().unwrap(), vec![DELAY, DELAY.mul_f64(1.5)]);
+
+        tokio::time::sleep(DELAY.mul_f64(1.5)).await;
+        assert_ready_eq!(rx.poll_next(), Some("fourth"));
+    }
+
+    #[tokio::test]
+    async fn observes_backoff() {
+        time::pause();
+        let backoffs = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (tx, mut rx) = {
+            let (tx, rx) = tokio::sync::mpsc::channel(3);
+            let backoffs = backoffs.clone();
+            let rx = task::spawn(
+                LogAndSleep::fixed_delay(DELAY, ReceiverStream::new(rx))
+                    .on_backoff(move |d| backoffs.lock().unwrap().push(d)),
+            );
+            (tx, rx)
+        };
+
+        tx.try_send(Err("first")).expect("stream not full");
+        assert_pending!(rx.poll_next());
+        assert!(backoffs.lock().unwrap().is_empty());
+
+        tx.try_send(Err("second")).expect("stream not full");
+        tx.try_send(Ok("third")).expect("stream not full");
+        assert_pending!(rx.poll_next());
+        assert_eq!(*backoffs.lock().unwrap(), vec![DELAY]);
+
+        tokio::time::sleep(DELAY).await;
+        assert_ready_eq!(rx.poll_next(), Some("third"));
+        assert_eq!(*backoffs.lock().unwrap(), vec![DELAY]);
+    }
+
+    #[tokio::test]
+    async fn fails_fast_on_permanent_errors() {
+        struct Permanent;
+        impl Classify<&'static str> for Permanent {
+            fn classify(&self, error: &&'static str) -> ErrorKind {
+                match *error {
+                    "forbidden" => ErrorKind::Forbidden,
+                    _ => ErrorKind::Network,
+                }
+            }
+        }
+
+        time::pause();
+        let failed = Arc::new(std::sync::Mutex::new(None));
+        let (tx, mut rx) = {
+            let (tx, rx) = tokio::sync::mpsc::channel(3);
+            let failed = failed.clone();
+            let rx = task::spawn(
+                LogAndSleep::fixed_delay(DELAY, ReceiverStream::new(rx))
+                    .classify_with(Permanent)
+                    .fail_fast()
+                    .on_fail_fast(move |kind, error| {
+                        *failed.lock().unwrap() = Some((kind, error.to_string()));
+                    }),
+            );
+            (tx, rx)
+        };
+
+        tx.try_send(Err("transient")).expect("stream not full");
+        tx.try_send(Ok("first")).expect("stream not full");
+        assert_ready_eq!(rx.poll_next(), Some("first"));
+        assert_eq!(*failed.lock().unwrap(), None);
+
+        tx.try_send(Err("forbidden")).expect("stream not full");
+        tx.try_send(Ok("second")).expect("stream not full");
+        assert_ready_eq!(rx.poll_next(), None);
+        assert_eq!(
+            *failed.lock().unwrap(),
+            Some((ErrorKind::Forbidden, "forbidden".to_string()))
+        );
+        assert_ready_eq!(rx.poll_next(), None);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_errors() {
+        time::pause();
+        let exhausted = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let (tx, mut rx) = {
+            let (tx, rx) = tokio::sync::mpsc::channel(4);
+            let exhausted = exhausted.clone();
+            let rx = task::spawn(
+                LogAndSleep::fixed_delay(DELAY, ReceiverStream::new(rx))
+                    .max_consecutive_errors(2)
+                    .on_exhausted(move || exhausted.store(true, Ordering::Relaxed)),
+            );
+            (tx, rx)
+        };
+
+        // Errors are only counted while they are consecutive.
+        tx.try_send(Err("first")).expect("stream not full");
+        tx.try_send(Ok("second")).expect("stream not full");
+        assert_ready_eq!(rx.poll_next(), Some("second"));
+
+        tx.try_send(Err("third")).expect("stream not full");
+        tx.try_send(Err("fourth")).expect("stream not full");
+        tx.try_send(Ok("fifth")).expect("stream not full");
+        assert_ready_eq!(rx.poll_next(), None);
+        assert!(exhausted.load(Ordering::Relaxed));
+        assert_ready_eq!(rx.poll_next(), None);
+    }
+
+    #[cfg(feature = "runtime")]
+    #[test]
+    fn classifies_watch_errors() {
+        use super::WatchErrors;
+        use kube_runtime::watcher::Error;
+
+        let api = |code| {
+            kube_client::Error::Api(kube_core::ErrorResponse {
+                status: "Failure".to_string(),
+                message: "failed".to_string(),
+                reason: "Failed".to_string(),
+                code,
+            })
+        };
+        let classify = |e| WatchErrors.classify(&Error::WatchFailed(e));
+        assert_eq!(classify(api(401)), ErrorKind::Unauthorized);
+        assert_eq!(classify(api(403)), ErrorKind::Forbidden);
+        assert_eq!(classify(api(404)), ErrorKind::NotFound);
+        assert_eq!(classify(api(500)), ErrorKind::Other);
+
+        // Failures to obtain credentials are not permanent.
+        let auth = classify(kube_client::Error::Auth(
+            kube_client::client::AuthError::ExecPluginFailed,
+        ));
+        assert_eq!(auth, ErrorKind::Other);
+        assert!(!auth.is_permanent());
+    }
 }
@@ -59,6 +59,51 @@ pub trait IndexNamespacedResource<T> {
     }
 }
 
+/// Processes updates to `T`-typed Kubernetes resources, rebuilding the index wholesale whenever
+/// the watch is restarted.
+pub trait IndexRestartedResource<T> {
+    /// Processes an update to a Kubernetes resource.
+    fn apply(&mut self, resource: T);
+
+    /// Observes the removal of a Kubernetes resource.
+    fn delete(&mut self, resource: T);
+
+    /// Replaces the contents of the index with the complete set of live resources.
+    ///
+    /// This is called once the watch's initial list (or a relist) completes. Any resources not
+    /// included in `resources` should be considered removed.
+    fn restart(&mut self, resources: Vec<T>);
+}
+
+/// A normalized operation on an index, derived from a watch [`Event`]
+///
+/// This may be used by controllers that maintain state outside of the [`RwLock`]-guarded indexes
+/// updated by [`namespaced`], [`cluster`], and [`restarted`] while preserving the same event
+/// semantics, e.g. `events.map(IndexOp::from)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndexOp<T> {
+    /// A resource was created or updated.
+    Apply(T),
+
+    /// A resource was removed.
+    Delete(T),
+
+    /// The watch is being (re)initialized.
+    ///
+    /// The resources observed until [`IndexOp::ResetDone`] comprise the complete set of live
+    /// resources.
+    ResetStart,
+
+    /// A live resource was observed while the watch is (re)initialized.
+    ResetItem(T),
+
+    /// The watch has been (re)initialized.
+    ///
+    /// Any resources that were not observed since [`IndexOp::ResetStart`] should be considered
+    /// removed.
+    ResetDone,
+}
+
 /// Updates a `T`-typed index from a watch on a `R`-typed namespaced Kubernetes resource.
 pub async fn namespaced<T, R>(
     index: Arc<RwLock<T>>,
@@ -180,6 +225,51 @@ pub async fn cluster<T, R>(
     }
 }
 
+/// Updates a `T`-typed index from a watch on a `R`-typed Kubernetes resource.
+///
+/// Unlike [`namespaced`] and [`cluster`], this does not track the keys of indexed resources.
+/// Instead, resources are buffered while the watch is (re)initialized and the index is rebuilt
+/// from the complete set via [`IndexRestartedResource::restart`].
+pub async fn restarted<T, R>(
+    index: Arc<RwLock<T>>,
+    events: impl futures_core::Stream<Item = Event<R>>,
+) where
+    T: IndexRestartedResource<R>,
+    R: std::fmt::Debug,
+{
+    tokio::pin!(events);
+
+    let mut reset = vec![];
+
+    while let Some(event) = events.next().await {
+        tracing::trace!(?event);
+        match IndexOp::from(event) {
+            IndexOp::Apply(resource) => index.write().apply(resource),
+            IndexOp::Delete(resource) => index.write().delete(resource),
+            IndexOp::ResetStart => reset.clear(),
+            IndexOp::ResetItem(resource) => reset.push(resource),
+            IndexOp::ResetDone => {
+                let resources = mem::take(&mut reset);
+                index.write().restart(resources);
+            }
+        }
+    }
+}
+
+// === impl IndexOp ===
+
+impl<T> From<Event<T>> for IndexOp<T> {
+    fn from(event: Event<T>) -> Self {
+        match event {
+            Event::Apply(resource) => Self::Apply(resource),
+            Event::Delete(resource) => Self::Delete(resource),
+            Event::Init => Self::ResetStart,
+            Event::InitApply(resource) => Self::ResetItem(resource),
+            Event::InitDone => Self::ResetDone,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -306,6 +396,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn restarted_rebuilds() {
+        let state = Arc::new(RwLock::new(RestartCache::default()));
+        let (tx, rx) = mpsc::channel(10);
+        let mut task = task::spawn(restarted(state.clone(), ReceiverStream::new(rx)));
+
+        let ns = |name: &str| corev1::Namespace {
+            metadata: metav1::ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        tx.try_send(kube::runtime::watcher::Event::Init).unwrap();
+        tx.try_send(kube::runtime::watcher::Event::InitApply(ns("ns-0")))
+            .unwrap();
+        tx.try_send(kube::runtime::watcher::Event::InitApply(ns("ns-1")))
+            .unwrap();
+        assert_pending!(task.poll());
+        assert_eq!(state.read().restarts, 0);
+        assert!(state.read().names.is_empty());
+
+        tx.try_send(kube::runtime::watcher::Event::InitDone)
+            .unwrap();
+        tx.try_send(kube::runtime::watcher::Event::Apply(ns("ns-2")))
+            .unwrap();
+        assert_pending!(task.poll());
+        assert_eq!(state.read().restarts, 1);
+        assert_eq!(
+            state.read().names,
+            vec!["ns-0".to_string(), "ns-1".to_string(), "ns-2".to_string()]
+                .into_iter()
+                .collect()
+        );
+
+        tx.try_send(kube::runtime::watcher::Event::Delete(ns("ns-0")))
+            .unwrap();
+        tx.try_send(kube::runtime::watcher::Event::Init).unwrap();
+        tx.try_send(kube::runtime::watcher::Event::InitApply(ns("ns-3")))
+            .unwrap();
+        tx.try_send(kube::runtime::watcher::Event::InitDone)
+            .unwrap();
+        assert_pending!(task.poll());
+        assert_eq!(state.read().restarts, 2);
+        assert_eq!(
+            state.read().names,
+            vec!["ns-3".to_string()].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn index_ops_from_events() {
+        let ops = [
+            Event::Init,
+            Event::InitApply("a"),
+            Event::InitDone,
+            Event::Apply("b"),
+            Event::Delete("a"),
+        ]
+        .into_iter()
+        .map(IndexOp::from)
+        .collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            [
+                IndexOp::ResetStart,
+                IndexOp::ResetItem("a"),
+                IndexOp::ResetDone,
+                IndexOp::Apply("b"),
+                IndexOp::Delete("a"),
+            ]
+        );
+    }
+
     struct ClusterCache(HashSet<String>);
 
     struct NsCache(HashMap<String, HashSet<String>>);
@@ -320,6 +485,27 @@ mod tests {
         }
     }
 
+    #[derive(Default)]
+    struct RestartCache {
+        names: HashSet<String>,
+        restarts: usize,
+    }
+
+    impl<T: Resource> IndexRestartedResource<T> for RestartCache {
+        fn apply(&mut self, resource: T) {
+            self.names.insert(resource.name_unchecked());
+        }
+
+        fn delete(&mut self, resource: T) {
+            self.names.remove(&resource.name_unchecked());
+        }
+
+        fn restart(&mut self, resources: Vec<T>) {
+            self.names = resources.iter().map(|r| r.name_unchecked()).collect();
+            self.restarts += 1;
+        }
+    }
+
     impl<T: Resource> IndexNamespacedResource<T> for NsCache {
         fn apply(&mut self, resource: T) {
             let namespace = resource
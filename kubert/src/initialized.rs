@@ -3,8 +3,9 @@
 use futures_core::{Future, Stream};
 use futures_util::ready;
 use std::{
+    collections::BTreeMap,
     pin::Pin,
-    sync::Arc,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
 };
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
@@ -17,12 +18,36 @@ use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 pub struct Initialized {
     semaphore: Arc<Semaphore>,
     issued: u32,
+    pending: Pending,
 }
 
 /// Signals a component has been initialized
 #[derive(Debug)]
 #[must_use]
-pub struct Handle(#[allow(dead_code)] OwnedSemaphorePermit);
+pub struct Handle {
+    #[allow(dead_code)]
+    permit: OwnedSemaphorePermit,
+    name: Option<Named>,
+}
+
+/// Lists the named [`Handle`]s that have not yet been released
+///
+/// This may be used to determine which components are blocking initialization.
+#[derive(Clone, Debug, Default)]
+pub struct Pending(Arc<Mutex<PendingNames>>);
+
+#[derive(Debug, Default)]
+struct PendingNames {
+    next_id: u64,
+    names: BTreeMap<u64, String>,
+}
+
+/// Removes a handle's name from the pending set when the handle is released
+#[derive(Debug)]
+struct Named {
+    id: Option<u64>,
+    pending: Pending,
+}
 
 pin_project_lite::pin_project! {
     /// A wrapper that releases a `Handle` when the underlying `Future` or `Stream` becomes ready
@@ -34,6 +59,31 @@ pin_project_lite::pin_project! {
     }
 }
 
+pin_project_lite::pin_project! {
+    /// A wrapper that releases a `Handle` when the underlying `Stream` produces an item
+    ///
+    /// If the stream completes before producing an item, the handle is never released, so
+    /// initialization never completes.
+    #[derive(Debug)]
+    pub struct ReleasesOnItem<S> {
+        #[pin]
+        inner: S,
+        handle: Option<Handle>,
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// A wrapper that releases a `Handle` once a predicate holds after the underlying `Stream`
+    /// produces an item
+    #[derive(Debug)]
+    pub struct ReleasesWhen<S, P> {
+        #[pin]
+        inner: S,
+        ready: P,
+        handle: Option<Handle>,
+    }
+}
+
 // === impl Initialized ===
 
 impl Default for Initialized {
@@ -41,6 +91,7 @@ impl Default for Initialized {
         Self {
             semaphore: Arc::new(Semaphore::new(0)),
             issued: 0,
+            pending: Pending::default(),
         }
     }
 }
@@ -55,7 +106,22 @@ impl Initialized {
             .try_acquire_owned()
             .expect("semaphore must issue permit");
         self.issued += 1;
-        Handle(permit)
+        Handle { permit, name: None }
+    }
+
+    /// Creates a new [`Handle`] for a named component to be dropped when the component has been
+    /// initialized
+    ///
+    /// Until the handle is released, its name is listed by [`Initialized::pending`].
+    pub fn add_named_handle(&mut self, name: impl ToString) -> Handle {
+        let mut handle = self.add_handle();
+        handle.name = Some(self.pending.insert(name.to_string()));
+        handle
+    }
+
+    /// Returns a handle that lists the names of handles that have not yet been released
+    pub fn pending(&self) -> Pending {
+        self.pending.clone()
     }
 
     /// Waits for all handles to be dropped
@@ -75,6 +141,79 @@ impl Handle {
     pub fn release_on_ready<T>(self, unready: T) -> ReleasesOnReady<T> {
         ReleasesOnReady::new(unready, self)
     }
+
+    /// Wraps a [`Stream`] in a [`ReleasesOnItem`] so that the handle is released once the stream
+    /// produces an item
+    ///
+    /// If the stream completes without producing an item, the handle is never released.
+    pub fn release_on_item<S: Stream>(self, unready: S) -> ReleasesOnItem<S> {
+        ReleasesOnItem {
+            inner: unready,
+            handle: Some(self),
+        }
+    }
+
+    /// Wraps a [`Stream`] in a [`ReleasesWhen`] so that the handle is released once `ready`
+    /// returns true after an item is produced
+    ///
+    /// The handle is also released when the stream completes.
+    pub fn release_when<S, P>(self, unready: S, ready: P) -> ReleasesWhen<S, P>
+    where
+        S: Stream,
+        P: FnMut() -> bool,
+    {
+        ReleasesWhen {
+            inner: unready,
+            ready,
+            handle: Some(self),
+        }
+    }
+
+    /// Consumes the handle without releasing it, so that initialization never completes
+    ///
+    /// The handle's name remains listed as pending.
+    pub(crate) fn forget(self) {
+        let Self { permit, name } = self;
+        permit.forget();
+        if let Some(mut name) = name {
+            name.id = None;
+        }
+    }
+}
+
+// === impl Pending ===
+
+impl Pending {
+    /// Returns the names of handles that have not yet been released, in the order they were
+    /// created
+    pub fn names(&self) -> Vec<String> {
+        self.lock().names.values().cloned().collect()
+    }
+
+    fn insert(&self, name: String) -> Named {
+        let mut pending = self.lock();
+        let id = pending.next_id;
+        pending.next_id += 1;
+        pending.names.insert(id, name);
+        Named {
+            id: Some(id),
+            pending: self.clone(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PendingNames> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// === impl Named ===
+
+impl Drop for Named {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.pending.lock().names.remove(&id);
+        }
+    }
 }
 
 // === impl ReleasesOnReady ===
@@ -110,6 +249,43 @@ impl<S: Stream> Stream for ReleasesOnReady<S> {
     }
 }
 
+// === impl ReleasesOnItem ===
+
+impl<S: Stream> Stream for ReleasesOnItem<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let mut this = self.project();
+        let next = ready!(this.inner.as_mut().poll_next(cx));
+        if let Some(handle) = this.handle.take() {
+            if next.is_none() {
+                // The stream completed before it was initialized.
+                handle.forget();
+            }
+        }
+        Poll::Ready(next)
+    }
+}
+
+// === impl ReleasesWhen ===
+
+impl<S, P> Stream for ReleasesWhen<S, P>
+where
+    S: Stream,
+    P: FnMut() -> bool,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let mut this = self.project();
+        let next = ready!(this.inner.as_mut().poll_next(cx));
+        if this.handle.is_some() && (next.is_none() || (this.ready)()) {
+            drop(this.handle.take());
+        }
+        Poll::Ready(next)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -171,4 +347,82 @@ mod test {
         assert_ready!(rx.poll_next());
         assert_ready!(init.poll());
     }
+
+    #[tokio::test]
+    async fn initializes_when_ready() {
+        let mut init = Initialized::default();
+        let (tx, mut rx) = {
+            let (tx, rx) = tokio::sync::mpsc::channel(2);
+            let mut count = 0;
+            let rx = task::spawn(init.add_handle().release_when(
+                ReceiverStream::new(rx),
+                move || {
+                    count += 1;
+                    count == 2
+                },
+            ));
+            (tx, rx)
+        };
+        let mut init = task::spawn(init.initialized());
+
+        assert_pending!(rx.poll_next());
+        assert_pending!(init.poll());
+        tx.try_send("hello").unwrap();
+        assert_ready!(rx.poll_next());
+        assert_pending!(init.poll());
+        tx.try_send("world").unwrap();
+        assert_ready!(rx.poll_next());
+        assert_ready!(init.poll());
+    }
+
+    #[tokio::test]
+    async fn lists_pending_names() {
+        let mut init = Initialized::default();
+        let pending = init.pending();
+        let handle0 = init.add_named_handle("a");
+        let handle1 = init.add_handle();
+        let handle2 = init.add_named_handle("b");
+        assert_eq!(pending.names(), vec!["a", "b"]);
+
+        drop(handle2);
+        assert_eq!(pending.names(), vec!["a"]);
+
+        let mut init = task::spawn(init.initialized());
+        drop(handle1);
+        assert_pending!(init.poll());
+        assert_eq!(pending.names(), vec!["a"]);
+
+        drop(handle0);
+        assert_ready!(init.poll());
+        assert!(pending.names().is_empty());
+    }
+
+    #[tokio::test]
+    async fn does_not_initialize_on_empty_stream() {
+        let mut init = Initialized::default();
+        let (tx, mut rx) = {
+            let (tx, rx) = tokio::sync::mpsc::channel::<&'static str>(2);
+            let rx = task::spawn(init.add_handle().release_on_item(ReceiverStream::new(rx)));
+            (tx, rx)
+        };
+        let mut init = task::spawn(init.initialized());
+
+        assert_pending!(rx.poll_next());
+        assert_pending!(init.poll());
+        drop(tx);
+        assert_ready!(rx.poll_next());
+        assert_pending!(init.poll());
+    }
+
+    #[tokio::test]
+    async fn does_not_initialize_on_forget() {
+        let mut init = Initialized::default();
+        let pending = init.pending();
+        let handle = init.add_named_handle("a");
+        let mut init = task::spawn(init.initialized());
+
+        handle.forget();
+        assert_pending!(init.poll());
+        assert_eq!(pending.names(), vec!["a"]);
+    }
 }
@@ -5,9 +5,10 @@
 //! that only one replica is trying to update resources at a time.
 //!
 //! [`LeaseManager`] interacts with a [`coordv1::Lease`] resource to ensure that
-//! only a single claimant owns the lease at a time.
+//! only a single claimant owns the lease at a time. Other resources, such as a
+//! custom resource, may be used instead by implementing [`LeaseLike`].
 
-use futures_util::TryFutureExt;
+use futures_util::{future, StreamExt, TryFutureExt};
 use k8s_openapi::{api::coordination::v1 as coordv1, apimachinery::pkg::apis::meta::v1 as metav1};
 use std::{borrow::Cow, sync::Arc};
 use tokio::time::{self, Duration};
@@ -16,12 +17,24 @@ use tokio::time::{self, Duration};
 use crate::admin::LeaseDiagnostics;
 
 /// Manages a Kubernetes `Lease`
+///
+/// By default, a [`coordv1::Lease`] is managed. Any other resource that
+/// implements [`LeaseLike`] may be used instead.
 #[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
-pub struct LeaseManager {
-    api: Api,
+pub struct LeaseManager<L = coordv1::Lease> {
+    api: Api<L>,
     name: String,
     field_manager: Cow<'static, str>,
-    state: tokio::sync::Mutex<State>,
+    force_conflicts: bool,
+    merge_patch: bool,
+    retain_resource: bool,
+    max_retry_elapsed: Option<Duration>,
+    initial_claim_timeout: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    state: tokio::sync::Mutex<State<L>>,
+    /// The current claim, cached so that it may be read without waiting for
+    /// API requests that hold the state lock.
+    held: tokio::sync::watch::Sender<Option<Arc<Claim>>>,
 
     #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
     diagnostics: Option<LeaseDiagnostics>,
@@ -63,6 +76,14 @@ pub struct ClaimParams {
     pub renew_grace_period: Duration,
 }
 
+/// Configuration used when observing a lease without claiming it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
+pub struct ObserveParams {
+    /// How often the lease is read from the API
+    pub poll_interval: Duration,
+}
+
 /// Describes the state of a lease
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
@@ -74,6 +95,65 @@ pub struct Claim {
     pub expiry: chrono::DateTime<chrono::Utc>,
 }
 
+/// A resource that stores a lease's state
+///
+/// [`LeaseManager`] reads and writes the fields of a [`coordv1::LeaseSpec`]
+/// (i.e. the holder identity, renew time, and lease duration) on the resource.
+/// This is implemented for [`coordv1::Lease`]. In environments where the
+/// `coordination.k8s.io` API group is unavailable, it may be implemented for a
+/// custom resource that stores the same fields.
+#[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
+pub trait LeaseLike:
+    kube_core::Resource<DynamicType = ()>
+    + serde::de::DeserializeOwned
+    + Clone
+    + std::fmt::Debug
+    + Send
+    + Sync
+    + 'static
+{
+    /// Returns the resource's lease fields, if it has any.
+    fn into_lease_spec(self) -> Option<coordv1::LeaseSpec>;
+
+    /// Builds a patch body that sets the provided lease fields.
+    ///
+    /// The fields are serialized like a [`coordv1::LeaseSpec`]. By default,
+    /// they are set on the resource's `spec`.
+    fn lease_spec_patch(fields: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({ "spec": fields })
+    }
+
+    /// Builds a patch that updates a subset of the resource's lease fields.
+    ///
+    /// Fields with a `null` value are cleared. By default, a JSON merge patch
+    /// is used, since custom resources do not support strategic merge patches.
+    fn update_patch(body: serde_json::Value) -> kube_client::api::Patch<serde_json::Value> {
+        kube_client::api::Patch::Merge(body)
+    }
+}
+
+/// A source of the current time, used to evaluate lease expiry
+///
+/// [`LeaseManager`] uses the [`SystemClock`] by default. A [`ManualClock`] may
+/// be used to exercise time-dependent behavior deterministically.
+#[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
+pub trait Clock: Send + Sync + 'static {
+    /// Returns the current time.
+    fn now(&self) -> chrono::DateTime<chrono::Utc>;
+}
+
+/// A [`Clock`] backed by the system clock
+#[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
+pub struct SystemClock(());
+
+/// A [`Clock`] that only advances when instructed to
+///
+/// Clones share the same underlying time.
+#[derive(Clone, Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
+pub struct ManualClock(Arc<std::sync::Mutex<chrono::DateTime<chrono::Utc>>>);
+
 /// Indicates an error interacting with the Lease API
 #[derive(Debug, thiserror::Error)]
 #[cfg_attr(docsrs, doc(cfg(feature = "lease")))]
@@ -93,12 +173,23 @@ pub enum Error {
     /// A Kubernetes API call timed out
     #[error("timed out")]
     Timeout,
+
+    /// The claim parameters are invalid
+    #[error("renew grace period {renew_grace_period:?} must be less than the lease duration {lease_duration:?}")]
+    InvalidParams {
+        /// The configured lease duration
+        lease_duration: Duration,
+        /// The configured renew grace period
+        renew_grace_period: Duration,
+    },
 }
 
 #[derive(Clone, Debug)]
-struct State {
+struct State<L> {
     meta: Meta,
     claim: Option<Arc<Claim>>,
+    /// The last resource read or written, if retained.
+    resource: Option<Arc<L>>,
 }
 
 #[derive(Clone, Debug)]
@@ -107,13 +198,25 @@ struct Meta {
     transitions: u16,
 }
 
-pub(crate) type Api = kube_client::Api<coordv1::Lease>;
+pub(crate) type Api<L = coordv1::Lease> = kube_client::Api<L>;
 
 pub(crate) type Spawned = (
     tokio::sync::watch::Receiver<Arc<Claim>>,
     tokio::task::JoinHandle<Result<(), Error>>,
 );
 
+// === impl LeaseLike ===
+
+impl LeaseLike for coordv1::Lease {
+    fn into_lease_spec(self) -> Option<coordv1::LeaseSpec> {
+        self.spec
+    }
+
+    fn update_patch(body: serde_json::Value) -> kube_client::api::Patch<serde_json::Value> {
+        kube_client::api::Patch::Strategic(body)
+    }
+}
+
 // === impl ClaimParams ===
 
 impl Default for ClaimParams {
@@ -125,33 +228,158 @@ impl Default for ClaimParams {
     }
 }
 
+impl ClaimParams {
+    /// Ensures that the renew grace period is less than the lease duration.
+    ///
+    /// Otherwise, a holder would attempt to renew the lease before it is ever
+    /// acquired.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.renew_grace_period >= self.lease_duration {
+            return Err(Error::InvalidParams {
+                lease_duration: self.lease_duration,
+                renew_grace_period: self.renew_grace_period,
+            });
+        }
+        Ok(())
+    }
+}
+
+// === impl ObserveParams ===
+
+impl Default for ObserveParams {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+// === impl SystemClock ===
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now()
+    }
+}
+
+// === impl ManualClock ===
+
+impl ManualClock {
+    /// Creates a clock that is fixed at the given time.
+    pub fn new(now: chrono::DateTime<chrono::Utc>) -> Self {
+        Self(Arc::new(std::sync::Mutex::new(now)))
+    }
+
+    /// Sets the clock's current time.
+    pub fn set(&self, now: chrono::DateTime<chrono::Utc>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    /// Advances the clock by the given duration.
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        *now += chrono::Duration::from_std(by).unwrap_or(chrono::Duration::MAX);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new(chrono::Utc::now())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
 // === impl Claim ===
 
 impl Claim {
     /// Returns true iff the claim is still valid according to the system clock
+    ///
+    /// Claims obtained from a [`LeaseManager`] with a custom [`Clock`] should
+    /// be evaluated with [`Claim::is_current_at`] instead.
     #[inline]
     pub fn is_current(&self) -> bool {
-        chrono::Utc::now() < self.expiry
+        self.is_current_at(chrono::Utc::now())
+    }
+
+    /// Returns true iff the claim is still valid at the given time
+    #[inline]
+    pub fn is_current_at(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        now < self.expiry
+    }
+
+    /// Returns the time remaining after the given time until the claim
+    /// expires, or zero if the claim has expired at that time
+    #[inline]
+    pub fn remaining_at(&self, now: chrono::DateTime<chrono::Utc>) -> Duration {
+        (self.expiry - now).to_std().unwrap_or(Duration::ZERO)
     }
 
     /// Returns true iff the claim is still valid for the provided claimant
+    /// according to the system clock
     #[inline]
     pub fn is_current_for(&self, claimant: &str) -> bool {
         self.holder == claimant && self.is_current()
     }
 
-    /// Waits for the claim to expire
+    /// Returns true iff both claims have the same holder and expire in the
+    /// same second
+    ///
+    /// An expiry computed locally carries sub-second precision that may be
+    /// lost when a lease's times are rounded by the API server, so claims that
+    /// describe the same lease state may differ when compared with `==`.
+    pub fn eq_ignoring_subsecond(&self, other: &Self) -> bool {
+        use chrono::SubsecRound;
+        self.holder == other.holder && self.expiry.trunc_subsecs(0) == other.expiry.trunc_subsecs(0)
+    }
+
+    /// Builds a [`coordv1::LeaseSpec`] describing the claim.
+    ///
+    /// The renew time is derived from the claim's expiry and the provided lease
+    /// duration. As when a lease is acquired, the acquire time is set to the
+    /// renew time. Lease transitions are not tracked by the claim and are
+    /// omitted.
+    pub fn to_lease_spec(&self, lease_duration: Duration) -> coordv1::LeaseSpec {
+        let lease_duration =
+            chrono::Duration::from_std(lease_duration).unwrap_or(chrono::Duration::MAX);
+        let renew_time = self
+            .expiry
+            .checked_sub_signed(lease_duration)
+            .unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC);
+        coordv1::LeaseSpec {
+            holder_identity: Some(self.holder.clone()),
+            acquire_time: Some(metav1::MicroTime(renew_time)),
+            renew_time: Some(metav1::MicroTime(renew_time)),
+            lease_duration_seconds: Some(
+                lease_duration.num_seconds().try_into().unwrap_or(i32::MAX),
+            ),
+            ..Default::default()
+        }
+    }
+
+    /// Waits for the claim to expire according to the system clock
     pub async fn expire(&self) {
         self.expire_with_grace(Duration::ZERO).await;
     }
 
     /// Waits until there is a grace period remaining before the claim expires
+    /// according to the system clock
     pub async fn expire_with_grace(&self, grace: Duration) {
-        if let Ok(remaining) = (self.expiry - chrono::Utc::now()).to_std() {
-            let sleep = remaining.saturating_sub(grace);
-            if !sleep.is_zero() {
-                tokio::time::sleep(sleep).await;
-            }
+        self.expire_with_grace_at(&SystemClock::default(), grace)
+            .await;
+    }
+
+    /// Waits until there is a grace period remaining before the claim expires
+    /// according to the given clock
+    async fn expire_with_grace_at(&self, clock: &dyn Clock, grace: Duration) {
+        let sleep = self.remaining_at(clock.now()).saturating_sub(grace);
+        if !sleep.is_zero() {
+            tokio::time::sleep(sleep).await;
         }
     }
 }
@@ -159,23 +387,78 @@ impl Claim {
 // === impl LeaseManager ===
 
 impl LeaseManager {
+    /// Initialize a lease's state from the Kubernetes API.
+    ///
+    /// The named lease resource must already have been created, or a 404 error
+    /// will be returned.
+    pub async fn init(api: Api, name: impl ToString) -> Result<Self, Error> {
+        Self::init_resource(api, name).await
+    }
+
+    /// Initialize a lease's state from the Kubernetes API, as described by
+    /// `params`.
+    ///
+    /// The lease is read from `params.namespace` (regardless of the client's
+    /// default namespace) so that the Lease resource always matches its
+    /// parameters. If `params.field_manager` is set, it is used when updating
+    /// the Lease.
+    ///
+    /// The named lease resource must already have been created, or a 404 error
+    /// will be returned.
+    pub async fn init_with_params(
+        client: kube_client::Client,
+        params: &LeaseParams,
+    ) -> Result<Self, Error> {
+        let api = Api::namespaced(client, &params.namespace);
+        let manager = Self::init(api, &params.name).await?;
+        Ok(match params.field_manager.clone() {
+            Some(fm) => manager.with_field_manager(fm),
+            None => manager,
+        })
+    }
+}
+
+impl<L: LeaseLike> LeaseManager<L> {
     pub(crate) const DEFAULT_FIELD_MANAGER: &'static str = "kubert";
     const DEFAULT_MIN_BACKOFF: Duration = Duration::from_millis(5);
-    const DEFAULT_BACKOFF_JITTER: f64 = 0.5; // up to 50% of the backoff duration
+    const DEFAULT_MAX_RETRY_ELAPSED: Duration = Duration::from_secs(15 * 60);
     const API_TIMEOUT: Duration = Duration::from_secs(10);
 
-    /// Initialize a lease's state from the Kubernetes API.
+    /// Initialize the state of a lease stored on any [`LeaseLike`] resource
+    /// (e.g. a custom resource) from the Kubernetes API.
+    ///
+    /// The named resource must already have been created, or a 404 error will
+    /// be returned.
+    pub async fn init_resource(api: Api<L>, name: impl ToString) -> Result<Self, Error> {
+        Self::init_with_clock(api, name, SystemClock::default()).await
+    }
+
+    /// Initialize a lease's state from the Kubernetes API, using the provided
+    /// [`Clock`] to evaluate lease expiry.
     ///
     /// The named lease resource must already have been created, or a 404 error
     /// will be returned.
-    pub async fn init(api: Api, name: impl ToString) -> Result<Self, Error> {
+    pub async fn init_with_clock(
+        api: Api<L>,
+        name: impl ToString,
+        clock: impl Clock,
+    ) -> Result<Self, Error> {
         let name = name.to_string();
-        let state = Self::get(api.clone(), &name).await?;
+        let clock = Arc::new(clock);
+        let state = Self::get(api.clone(), &name, &*clock, false).await?;
+        let (held, _) = tokio::sync::watch::channel(state.claim.clone());
         Ok(Self {
             api,
             name,
             field_manager: Self::DEFAULT_FIELD_MANAGER.into(),
+            force_conflicts: true,
+            merge_patch: false,
+            retain_resource: false,
+            max_retry_elapsed: Some(Self::DEFAULT_MAX_RETRY_ELAPSED),
+            initial_claim_timeout: None,
+            clock,
             state: tokio::sync::Mutex::new(state),
+            held,
             #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
             diagnostics: None,
         })
@@ -190,6 +473,79 @@ impl LeaseManager {
         self
     }
 
+    /// Controls whether field conflicts are forced when acquiring the Lease
+    ///
+    /// By default, conflicts are forced so that the claimant takes ownership of
+    /// the Lease's fields from other field managers. When disabled, a conflict
+    /// causes the Lease's state to be re-synced from the API instead. If the
+    /// Lease has not changed, the conflict error is returned.
+    ///
+    /// This is intended to be used immediately following initialization and
+    /// before `ensure_claimed` is invoked.
+    pub fn with_force_conflicts(mut self, force: bool) -> Self {
+        self.force_conflicts = force;
+        self
+    }
+
+    /// Controls whether JSON merge patches are used to renew and vacate the Lease
+    ///
+    /// By default, the patch type is determined by [`LeaseLike::update_patch`]
+    /// (i.e. a strategic merge patch for [`coordv1::Lease`]). When enabled, a
+    /// JSON merge patch (RFC 7386) is used instead, for API servers that do not
+    /// support strategic merge patches. Acquisitions always use server-side
+    /// apply.
+    ///
+    /// This is intended to be used immediately following initialization and
+    /// before `ensure_claimed` is invoked.
+    pub fn with_merge_patch(mut self, merge: bool) -> Self {
+        self.merge_patch = merge;
+        self
+    }
+
+    /// Retains the last Lease resource read from or written to the API
+    ///
+    /// When enabled, the resource returned by each read or update of the
+    /// Lease (e.g. by [`LeaseManager::sync`] or
+    /// [`LeaseManager::ensure_claimed`]) is available via
+    /// [`LeaseManager::resource`], so that callers may inspect fields that are
+    /// not described by the claim (e.g. annotations written by other actors)
+    /// without reading the Lease again.
+    ///
+    /// This is intended to be used immediately following initialization and
+    /// before `ensure_claimed` is invoked.
+    pub fn with_retained_resource(mut self, retain: bool) -> Self {
+        self.retain_resource = retain;
+        self
+    }
+
+    /// Limits how long a spawned task retries failed attempts to renew or
+    /// acquire the Lease
+    ///
+    /// When the limit is exceeded, the task returned by [`LeaseManager::spawn`]
+    /// completes with the last error. If `None`, the task retries indefinitely.
+    /// By default, retries are abandoned after 15 minutes.
+    ///
+    /// This is intended to be used immediately following initialization and
+    /// before `spawn` is invoked.
+    pub fn with_max_retry_elapsed(mut self, max: Option<Duration>) -> Self {
+        self.max_retry_elapsed = max;
+        self
+    }
+
+    /// Limits how long [`LeaseManager::spawn`] waits for the initial claim
+    ///
+    /// If the initial claim does not complete within the timeout, `spawn`
+    /// fails with an [`Error::Timeout`] error rather than blocking. By
+    /// default, each API request is bounded but the initial claim is not,
+    /// e.g. if it repeatedly conflicts with other writers.
+    ///
+    /// This is intended to be used immediately following initialization and
+    /// before `spawn` is invoked.
+    pub fn with_initial_claim_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.initial_claim_timeout = timeout;
+        self
+    }
+
     #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
     pub(crate) fn with_diagnostics(mut self, diagnostics: LeaseDiagnostics) -> Self {
         self.diagnostics = Some(diagnostics);
@@ -201,14 +557,38 @@ impl LeaseManager {
         self.state.lock().await.claim.clone()
     }
 
+    /// Returns the last Lease resource read from or written to the API
+    ///
+    /// Resources are only retained when enabled by
+    /// [`LeaseManager::with_retained_resource`], so `None` is returned until
+    /// the Lease is next read or updated.
+    pub async fn resource(&self) -> Option<Arc<L>> {
+        self.state.lock().await.resource.clone()
+    }
+
+    /// Returns true iff the lease is currently held by the provided claimant.
+    ///
+    /// Like [`LeaseManager::claimed`], this inspects the last known state of
+    /// the claim without updating it from the API. It does not wait for
+    /// in-flight API requests to complete.
+    pub fn is_held_by(&self, claimant: &str) -> bool {
+        self.held
+            .borrow()
+            .as_ref()
+            .is_some_and(|c| c.holder == claimant && c.is_current_at(self.clock.now()))
+    }
+
     /// Update the state of the claim from the API.
     pub async fn sync(&self) -> Result<Option<Arc<Claim>>, Error> {
         let mut state = self.state.lock().await;
-        *state = Self::get(self.api.clone(), &self.name).await?;
-        #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
-        if let Some(diagnostics) = self.diagnostics.as_ref() {
-            diagnostics.inspect(state.claim.clone(), state.meta.version.clone());
-        }
+        *state = Self::get(
+            self.api.clone(),
+            &self.name,
+            &*self.clock,
+            self.retain_resource,
+        )
+        .await?;
+        self.publish(&state);
         Ok(state.claim.clone())
     }
 
@@ -217,11 +597,15 @@ impl LeaseManager {
     /// If these is not currently held, it is claimed by the provided identity.
     /// If it is currently held by the provided claimant, it is renewed if it is
     /// within the renew grace period.
+    ///
+    /// An [`Error::InvalidParams`] error is returned if the claim parameters
+    /// are invalid. See [`ClaimParams::validate`].
     pub async fn ensure_claimed(
         &self,
         claimant: &str,
         params: &ClaimParams,
     ) -> Result<Arc<Claim>, Error> {
+        params.validate()?;
         let mut state = self.state.lock().await;
         loop {
             if let Some(claim) = state.claim.as_ref() {
@@ -231,56 +615,67 @@ impl LeaseManager {
                     let renew_at = claim.expiry
                         - chrono::Duration::from_std(params.renew_grace_period)
                             .unwrap_or_else(|_| chrono::Duration::zero());
-                    if chrono::Utc::now() < renew_at {
+                    if self.clock.now() < renew_at {
                         return Ok(claim.clone());
                     }
 
-                    let (claim, meta) = match self.renew(&state.meta, claimant, params).await {
-                        Ok(renew) => renew,
-
-                        Err(e) if Self::is_conflict(&e) => {
-                            // Another process updated the claim's resource version, so
-                            // re-sync the state and try again.
-                            *state = Self::get(self.api.clone(), &self.name).await?;
-                            #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
-                            if let Some(diagnostics) = self.diagnostics.as_ref() {
-                                diagnostics
-                                    .inspect(state.claim.clone(), state.meta.version.clone());
+                    let (claim, meta, resource) =
+                        match self.renew(&state.meta, claimant, params).await {
+                            Ok(renew) => renew,
+
+                            Err(e) if Self::is_conflict(&e) => {
+                                // Another process updated the claim's resource version, so
+                                // re-sync the state and try again.
+                                *state = Self::get(
+                                    self.api.clone(),
+                                    &self.name,
+                                    &*self.clock,
+                                    self.retain_resource,
+                                )
+                                .await?;
+                                self.publish(&state);
+                                continue;
                             }
-                            continue;
-                        }
 
-                        Err(e) => return Err(e),
-                    };
+                            Err(e) => return Err(e),
+                        };
 
                     *state = State {
                         claim: Some(claim.clone()),
                         meta,
+                        resource,
                     };
-                    #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
-                    if let Some(diagnostics) = self.diagnostics.as_ref() {
-                        diagnostics.inspect(state.claim.clone(), state.meta.version.clone());
-                    }
+                    self.publish(&state);
                     return Ok(claim);
                 }
 
                 // The claim is held by another claimant, return it.
-                if claim.is_current() {
+                if claim.is_current_at(self.clock.now()) {
                     return Ok(claim.clone());
                 }
             }
 
             // There's no current claim, so try to acquire it.
-            let (claim, meta) = match self.acquire(&state.meta, claimant, params).await {
+            let (claim, meta, resource) = match self.acquire(&state.meta, claimant, params).await {
                 Ok(acquire) => acquire,
 
                 Err(e) if Self::is_conflict(&e) => {
                     // Another process updated the claim's resource version, so
                     // re-sync the state and try again.
-                    *state = Self::get(self.api.clone(), &self.name).await?;
-                    #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
-                    if let Some(diagnostics) = self.diagnostics.as_ref() {
-                        diagnostics.inspect(state.claim.clone(), state.meta.version.clone());
+                    let version = std::mem::take(&mut state.meta.version);
+                    *state = Self::get(
+                        self.api.clone(),
+                        &self.name,
+                        &*self.clock,
+                        self.retain_resource,
+                    )
+                    .await?;
+                    self.publish(&state);
+                    // When conflicts are not forced, the conflict may be due to
+                    // another field manager owning the Lease's fields. If the
+                    // resource has not changed, retrying would conflict again.
+                    if !self.force_conflicts && state.meta.version == version {
+                        return Err(e);
                     }
                     continue;
                 }
@@ -291,11 +686,9 @@ impl LeaseManager {
             *state = State {
                 claim: Some(claim.clone()),
                 meta,
+                resource,
             };
-            #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
-            if let Some(diagnostics) = self.diagnostics.as_ref() {
-                diagnostics.inspect(state.claim.clone(), state.meta.version.clone());
-            }
+            self.publish(&state);
 
             return Ok(claim);
         }
@@ -312,43 +705,38 @@ impl LeaseManager {
             return Ok(false);
         };
 
-        if !claim.is_current() {
+        if claim.holder != claimant && claim.is_current_at(self.clock.now()) {
+            state.claim = Some(claim);
             return Ok(false);
         }
 
-        if claim.holder != claimant {
-            state.claim = Some(claim);
+        // The claim is cleared even if the Lease cannot be updated.
+        self.held.send_replace(None);
+        if !claim.is_current_at(self.clock.now()) {
             return Ok(false);
         }
 
-        #[cfg_attr(
-            not(all(feature = "runtime", feature = "runtime-diagnostics")),
-            allow(unused_variables)
-        )]
         let lease = self
-            .patch(&kube_client::api::Patch::Strategic(serde_json::json!({
-                "apiVersion": "coordination.k8s.io/v1",
-                "kind": "Lease",
-                "metadata": {
-                    "resourceVersion": state.meta.version,
-                },
-                "spec": {
+            .patch(&self.update_patch(Self::patch_body(
+                &state.meta.version,
+                serde_json::json!({
                     "acquireTime": Option::<()>::None,
                     "renewTime": Option::<()>::None,
                     "holderIdentity": Option::<()>::None,
                     "leaseDurationSeconds": Option::<()>::None,
-                    // leaseTransitions is preserved by strategic patch
-                },
-            })))
+                    // leaseTransitions is preserved by the patch
+                }),
+            )))
             .await?;
 
         #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
         if let Some(diagnostics) = self.diagnostics.as_ref() {
             diagnostics.inspect(
                 None,
-                lease.metadata.resource_version.clone().unwrap_or_default(),
+                lease.meta().resource_version.clone().unwrap_or_default(),
             );
         }
+        state.resource = self.retained(lease);
 
         Ok(true)
     }
@@ -363,18 +751,28 @@ impl LeaseManager {
     ///
     /// When all receivers are dropped, the task completes and the lease is
     /// vacated so that another process can claim it.
+    ///
+    /// If the lease cannot be claimed before the retry limit is exceeded (see
+    /// [`LeaseManager::with_max_retry_elapsed`]), the task fails with the last
+    /// error.
+    ///
+    /// If the initial claim does not complete before the timeout configured by
+    /// [`LeaseManager::with_initial_claim_timeout`], an [`Error::Timeout`]
+    /// error is returned.
     pub async fn spawn(
         self,
         claimant: impl ToString,
         params: ClaimParams,
     ) -> Result<Spawned, Error> {
         let claimant = claimant.to_string();
-        let mut claim = self.ensure_claimed(&claimant, &params).await?;
+        let claimed = self.ensure_claimed(&claimant, &params);
+        let mut claim = match self.initial_claim_timeout {
+            Some(timeout) => time::timeout(timeout, claimed)
+                .await
+                .map_err(|_| Error::Timeout)??,
+            None => claimed.await?,
+        };
         let (tx, rx) = tokio::sync::watch::channel(claim.clone());
-        let mut new_backoff = backoff::ExponentialBackoffBuilder::default();
-        new_backoff
-            .with_initial_interval(Self::DEFAULT_MIN_BACKOFF)
-            .with_randomization_factor(Self::DEFAULT_BACKOFF_JITTER);
 
         let task = tokio::spawn(async move {
             loop {
@@ -391,11 +789,14 @@ impl LeaseManager {
                 tokio::select! {
                     biased;
                     _ = tx.closed() => break,
-                    _ = claim.expire_with_grace(grace) => {}
+                    _ = claim.expire_with_grace_at(&*self.clock, grace) => {}
                 }
 
                 // Update the claim and broadcast it to all receivers.
-                let backoff = new_backoff.with_max_interval(grace).build();
+                let backoff =
+                    crate::backoff::Backoff::exponential(Self::DEFAULT_MIN_BACKOFF, grace)
+                        .with_max_elapsed(self.max_retry_elapsed)
+                        .build();
                 claim = backoff::future::retry(backoff, || {
                     self.ensure_claimed(&claimant, &params).map_err(|err| match err {
                         err @ Error::Api(kube_client::Error::Auth(_))
@@ -433,6 +834,64 @@ impl LeaseManager {
         Ok((rx, task))
     }
 
+    /// Observes the state of the lease without attempting to claim it.
+    ///
+    /// The returned stream first yields the lease's last known claim and then
+    /// yields the claim each time it changes, e.g. when another process
+    /// acquires or vacates the lease. `None` indicates that the lease is not
+    /// currently claimed.
+    ///
+    /// The lease is read from the API every `params.poll_interval` and when
+    /// the current claim expires. Errors reading the lease are logged and the
+    /// lease is read again at the next interval. If the lease cannot be read
+    /// when the current claim expires, the lease is considered unclaimed.
+    pub fn observe(
+        self,
+        params: ObserveParams,
+    ) -> impl futures_util::Stream<Item = Option<Arc<Claim>>> + Send + 'static {
+        futures_util::stream::unfold((self, None::<Option<Arc<Claim>>>), move |(lease, last)| async move {
+            let claim = match last {
+                // Start with the last known claim.
+                None => lease
+                    .claimed()
+                    .await
+                    .filter(|claim| claim.is_current_at(lease.clock.now())),
+
+                Some(ref current) => loop {
+                    // Wait for the poll interval to elapse or for the current
+                    // claim to expire, whichever comes first.
+                    let expiry = current
+                        .as_ref()
+                        .map(|claim| claim.remaining_at(lease.clock.now()));
+                    let expired = tokio::select! {
+                        _ = time::sleep(params.poll_interval) => false,
+                        _ = time::sleep(expiry.unwrap_or_default()), if expiry.is_some() => true,
+                    };
+
+                    match lease.sync().await {
+                        Ok(claim) => break claim,
+                        Err(error) if expired => {
+                            tracing::debug!(%error, "Failed to observe lease; considering it unclaimed");
+                            break None;
+                        }
+                        Err(error) => {
+                            tracing::debug!(%error, "Failed to observe lease");
+                        }
+                    }
+                },
+            };
+
+            Some((claim.clone(), (lease, Some(claim))))
+        })
+        .scan(None, |last, claim| {
+            // Only yield claims that differ from the last one yielded.
+            let changed = last.as_ref() != Some(&claim);
+            *last = Some(claim.clone());
+            future::ready(Some(changed.then_some(claim)))
+        })
+        .filter_map(future::ready)
+    }
+
     /// Acquire the lease (i.e. assuming the claimant IS NOT the current holder
     /// of the lease).
     ///
@@ -444,25 +903,21 @@ impl LeaseManager {
         meta: &Meta,
         claimant: &str,
         params: &ClaimParams,
-    ) -> Result<(Arc<Claim>, Meta), Error> {
+    ) -> Result<(Arc<Claim>, Meta, Option<Arc<L>>), Error> {
         let lease_duration =
             chrono::Duration::from_std(params.lease_duration).unwrap_or(chrono::Duration::MAX);
-        let now = chrono::Utc::now();
+        let now = self.clock.now();
         let lease = self
-            .patch(&kube_client::api::Patch::Apply(serde_json::json!({
-                "apiVersion": "coordination.k8s.io/v1",
-                "kind": "Lease",
-                "metadata": {
-                    "resourceVersion": meta.version,
-                },
-                "spec": {
+            .patch(&kube_client::api::Patch::Apply(Self::patch_body(
+                &meta.version,
+                serde_json::json!({
                     "acquireTime": metav1::MicroTime(now),
                     "renewTime": metav1::MicroTime(now),
                     "holderIdentity": claimant,
                     "leaseDurationSeconds": lease_duration.num_seconds(),
                     "leaseTransitions": meta.transitions + 1,
-                },
-            })))
+                }),
+            )))
             .await?;
 
         let claim = Claim {
@@ -471,41 +926,39 @@ impl LeaseManager {
         };
         let meta = Meta {
             version: lease
-                .metadata
+                .meta()
                 .resource_version
+                .clone()
                 .ok_or(Error::MissingResourceVersion)?,
             transitions: meta.transitions + 1,
         };
-        Ok((claim.into(), meta))
+        Ok((claim.into(), meta, self.retained(lease)))
     }
 
     /// Renew the lease (i.e. assuming the claimant IS the current holder of the
     /// lease).
     ///
-    /// A strategic merge is used so that only the `renewTime` field is updated
-    /// in most cases. The `leaseDurationSeconds` fields may also be updated if
+    /// A merge patch (see [`LeaseLike::update_patch`] and
+    /// [`LeaseManager::with_merge_patch`]) is used so that only the
+    /// `renewTime` field is updated in most cases. The `leaseDurationSeconds` fields may also be updated if
     /// the caller passed an updated value.
     async fn renew(
         &self,
         meta: &Meta,
         claimant: &str,
         params: &ClaimParams,
-    ) -> Result<(Arc<Claim>, Meta), Error> {
+    ) -> Result<(Arc<Claim>, Meta, Option<Arc<L>>), Error> {
         let lease_duration =
             chrono::Duration::from_std(params.lease_duration).unwrap_or(chrono::Duration::MAX);
-        let now = chrono::Utc::now();
+        let now = self.clock.now();
         let lease = self
-            .patch(&kube_client::api::Patch::Strategic(serde_json::json!({
-                "apiVersion": "coordination.k8s.io/v1",
-                "kind": "Lease",
-                "metadata": {
-                    "resourceVersion": meta.version,
-                },
-                "spec": {
+            .patch(&self.update_patch(Self::patch_body(
+                &meta.version,
+                serde_json::json!({
                     "renewTime": metav1::MicroTime(now),
                     "leaseDurationSeconds": lease_duration.num_seconds(),
-                },
-            })))
+                }),
+            )))
             .await?;
 
         let claim = Claim {
@@ -514,15 +967,50 @@ impl LeaseManager {
         };
         let meta = Meta {
             version: lease
-                .metadata
+                .meta()
                 .resource_version
+                .clone()
                 .ok_or(Error::MissingResourceVersion)?,
             transitions: meta.transitions,
         };
-        Ok((claim.into(), meta))
+        Ok((claim.into(), meta, self.retained(lease)))
+    }
+
+    /// Builds a patch body that sets the provided lease fields, conditioned on
+    /// the resource version.
+    fn patch_body(version: &str, fields: serde_json::Value) -> serde_json::Value {
+        let mut body = L::lease_spec_patch(fields);
+        body["apiVersion"] = L::api_version(&()).into();
+        body["kind"] = L::kind(&()).into();
+        body["metadata"] = serde_json::json!({ "resourceVersion": version });
+        body
+    }
+
+    /// Builds a patch that updates a subset of the lease fields, honoring
+    /// [`LeaseManager::with_merge_patch`].
+    fn update_patch(&self, body: serde_json::Value) -> kube_client::api::Patch<serde_json::Value> {
+        if self.merge_patch {
+            return kube_client::api::Patch::Merge(body);
+        }
+        L::update_patch(body)
+    }
+
+    /// Publishes the current claim to readers of the cached claim and to
+    /// diagnostics.
+    fn publish(&self, state: &State<L>) {
+        self.held.send_replace(state.claim.clone());
+        #[cfg(all(feature = "runtime", feature = "runtime-diagnostics"))]
+        if let Some(diagnostics) = self.diagnostics.as_ref() {
+            diagnostics.inspect(state.claim.clone(), state.meta.version.clone());
+        }
     }
 
-    async fn patch<P>(&self, patch: &kube_client::api::Patch<P>) -> Result<coordv1::Lease, Error>
+    /// Returns the resource if resources are retained.
+    fn retained(&self, lease: L) -> Option<Arc<L>> {
+        self.retain_resource.then(|| Arc::new(lease))
+    }
+
+    async fn patch<P>(&self, patch: &kube_client::api::Patch<P>) -> Result<L, Error>
     where
         P: serde::Serialize + std::fmt::Debug,
     {
@@ -530,9 +1018,9 @@ impl LeaseManager {
         let params = kube_client::api::PatchParams {
             field_manager: Some(self.field_manager.to_string()),
             // Force conflict resolution when using Server-side Apply (i.e., to
-            // acquire a lease). This is the recommended behavior for
-            // controllers. See: https://kubernetes.io/docs/reference/using-api/server-side-apply/#conflicts
-            force: matches!(patch, kube_client::api::Patch::Apply(_)),
+            // acquire a lease), unless disabled. This is the recommended
+            // behavior for controllers. See: https://kubernetes.io/docs/reference/using-api/server-side-apply/#conflicts
+            force: self.force_conflicts && matches!(patch, kube_client::api::Patch::Apply(_)),
             ..Default::default()
         };
         time::timeout(
@@ -544,16 +1032,20 @@ impl LeaseManager {
         .map_err(Into::into)
     }
 
-    async fn get(api: Api, name: &str) -> Result<State, Error> {
+    async fn get(
+        api: Api<L>,
+        name: &str,
+        clock: &dyn Clock,
+        retain: bool,
+    ) -> Result<State<L>, Error> {
         let lease = time::timeout(Self::API_TIMEOUT, api.get(name))
             .await
             .map_err(|_| Error::Timeout)??;
-        let spec = lease.spec.ok_or(Error::MissingSpec)?;
+        let resource = retain.then(|| Arc::new(lease.clone()));
+        let version = lease.meta().resource_version.clone();
+        let spec = lease.into_lease_spec().ok_or(Error::MissingSpec)?;
 
-        let version = lease
-            .metadata
-            .resource_version
-            .ok_or(Error::MissingResourceVersion)?;
+        let version = version.ok_or(Error::MissingResourceVersion)?;
         let transitions = spec.lease_transitions.unwrap_or(0).try_into().unwrap_or(0);
         let meta = Meta {
             version,
@@ -565,7 +1057,11 @@ impl LeaseManager {
                 match $e {
                     Some(e) => e,
                     None => {
-                        return Ok(State { meta, claim: None });
+                        return Ok(State {
+                            meta,
+                            claim: None,
+                            resource,
+                        });
                     }
                 }
             };
@@ -577,13 +1073,18 @@ impl LeaseManager {
         let lease_duration =
             chrono::Duration::seconds(or_unclaimed!(spec.lease_duration_seconds).into());
         let expiry = renew_time + lease_duration;
-        if expiry <= chrono::Utc::now() {
-            return Ok(State { meta, claim: None });
+        if expiry <= clock.now() {
+            return Ok(State {
+                meta,
+                claim: None,
+                resource,
+            });
         }
 
         Ok(State {
             meta,
             claim: Some(Arc::new(Claim { holder, expiry })),
+            resource,
         })
     }
 
@@ -595,3 +1096,585 @@ impl LeaseManager {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Builds an API client that responds to all requests with the lease (or
+    /// error status) returned by `handle`.
+    fn mock_api(
+        handle: impl Fn(
+                &hyper::Request<kube_client::client::Body>,
+            ) -> Result<coordv1::Lease, hyper::StatusCode>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Api {
+        Api::namespaced(mock_client(handle), "default")
+    }
+
+    /// Builds a client, with the `default` namespace, that responds to all
+    /// requests with the lease (or error status) returned by `handle`.
+    fn mock_client(
+        handle: impl Fn(
+                &hyper::Request<kube_client::client::Body>,
+            ) -> Result<coordv1::Lease, hyper::StatusCode>
+            + Send
+            + Sync
+            + 'static,
+    ) -> kube_client::Client {
+        let svc = tower::service_fn(move |req| {
+            let rsp = match handle(&req) {
+                Ok(lease) => {
+                    let body = serde_json::to_vec(&lease).expect("lease must serialize");
+                    hyper::Response::new(kube_client::client::Body::from(body))
+                }
+                Err(status) => hyper::Response::builder()
+                    .status(status)
+                    .body(kube_client::client::Body::empty())
+                    .expect("response must be valid"),
+            };
+            future::ok::<_, std::convert::Infallible>(rsp)
+        });
+        kube_client::Client::new(svc, "default")
+    }
+
+    fn mk_lease(
+        version: usize,
+        holder: &str,
+        renew_time: chrono::DateTime<chrono::Utc>,
+    ) -> coordv1::Lease {
+        coordv1::Lease {
+            metadata: metav1::ObjectMeta {
+                name: Some("lease".to_string()),
+                namespace: Some("default".to_string()),
+                resource_version: Some(version.to_string()),
+                ..Default::default()
+            },
+            spec: Some(coordv1::LeaseSpec {
+                holder_identity: Some(holder.to_string()),
+                renew_time: Some(metav1::MicroTime(renew_time)),
+                lease_duration_seconds: Some(30),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn claim_to_lease_spec() {
+        let expiry = chrono::DateTime::from_timestamp(1_700_000_030, 0).expect("valid timestamp");
+        let claim = Claim {
+            holder: "alice".to_string(),
+            expiry,
+        };
+        let spec = claim.to_lease_spec(Duration::from_secs(30));
+        let renew_time = expiry - chrono::Duration::seconds(30);
+        assert_eq!(spec.holder_identity.as_deref(), Some("alice"));
+        assert_eq!(spec.renew_time, Some(metav1::MicroTime(renew_time)));
+        assert_eq!(spec.acquire_time, Some(metav1::MicroTime(renew_time)));
+        assert_eq!(spec.lease_duration_seconds, Some(30));
+        assert_eq!(spec.lease_transitions, None);
+    }
+
+    #[test]
+    fn claim_remaining() {
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let claim = Claim {
+            holder: "alice".to_string(),
+            expiry: t0 + chrono::Duration::seconds(30),
+        };
+        assert_eq!(claim.remaining_at(t0), Duration::from_secs(30));
+        assert_eq!(
+            claim.remaining_at(t0 + chrono::Duration::milliseconds(29_500)),
+            Duration::from_millis(500)
+        );
+        assert_eq!(claim.remaining_at(claim.expiry), Duration::ZERO);
+        assert_eq!(
+            claim.remaining_at(t0 + chrono::Duration::seconds(60)),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn claim_expires_with_clock() {
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let clock = ManualClock::new(t0);
+        let claim = Claim {
+            holder: "alice".to_string(),
+            expiry: t0 + chrono::Duration::seconds(30),
+        };
+
+        // The claim expired long ago according to the system clock, but its
+        // expiry is evaluated with the provided clock.
+        let start = time::Instant::now();
+        claim
+            .expire_with_grace_at(&clock, Duration::from_secs(1))
+            .await;
+        assert_eq!(start.elapsed(), Duration::from_secs(29));
+    }
+
+    #[tokio::test]
+    async fn is_held_by() {
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let clock = ManualClock::new(t0);
+        let api = mock_api(move |_| Ok(mk_lease(1, "alice", t0)));
+        let lease = LeaseManager::init_with_clock(api, "lease", clock.clone())
+            .await
+            .expect("lease must initialize");
+
+        assert!(lease.is_held_by("alice"));
+        assert!(!lease.is_held_by("bob"));
+
+        // The cached claim is readable while the state is locked (e.g. by an
+        // in-flight API request).
+        let state = lease.state.lock().await;
+        assert!(lease.is_held_by("alice"));
+        drop(state);
+
+        clock.advance(Duration::from_secs(30));
+        assert!(!lease.is_held_by("alice"));
+    }
+
+    #[tokio::test]
+    async fn inits_with_params_namespace() {
+        let t0 = chrono::Utc::now();
+        let client = mock_client(move |req| {
+            assert_eq!(
+                req.uri().path(),
+                "/apis/coordination.k8s.io/v1/namespaces/kube-system/leases/lease"
+            );
+            Ok(mk_lease(1, "alice", t0))
+        });
+        let params = LeaseParams {
+            name: "lease".to_string(),
+            namespace: "kube-system".to_string(),
+            claimant: "alice".to_string(),
+            lease_duration: Duration::from_secs(30),
+            renew_grace_period: Duration::from_secs(1),
+            field_manager: Some("test".into()),
+        };
+        let lease = LeaseManager::init_with_params(client, &params)
+            .await
+            .expect("lease must initialize");
+        assert_eq!(lease.field_manager, "test");
+        assert!(lease.is_held_by("alice"));
+    }
+
+    #[tokio::test]
+    async fn renews_and_expires_with_clock() {
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let clock = ManualClock::new(t0);
+        let patches = Arc::new(AtomicUsize::new(0));
+        let api = mock_api({
+            let patches = patches.clone();
+            move |req| {
+                if req.method() == hyper::Method::PATCH {
+                    let n = patches.fetch_add(1, Ordering::SeqCst) + 1;
+                    return Ok(mk_lease(n + 1, "", t0));
+                }
+                Ok(mk_lease(1, "alice", t0))
+            }
+        });
+        let params = ClaimParams {
+            lease_duration: Duration::from_secs(30),
+            renew_grace_period: Duration::from_secs(1),
+        };
+        let lease = LeaseManager::init_with_clock(api, "lease", clock.clone())
+            .await
+            .expect("lease must initialize");
+
+        // The existing claim is held and does not need to be renewed.
+        let claim = lease
+            .ensure_claimed("alice", &params)
+            .await
+            .expect("lease must be claimed");
+        assert_eq!(claim.holder, "alice");
+        assert_eq!(claim.expiry, t0 + chrono::Duration::seconds(30));
+        assert_eq!(patches.load(Ordering::SeqCst), 0);
+
+        // Within the grace period, the claim is renewed.
+        clock.advance(Duration::from_millis(29_500));
+        let claim = lease
+            .ensure_claimed("alice", &params)
+            .await
+            .expect("lease must be renewed");
+        assert_eq!(claim.holder, "alice");
+        assert_eq!(claim.expiry, clock.now() + chrono::Duration::seconds(30));
+        assert_eq!(patches.load(Ordering::SeqCst), 1);
+
+        // Another claimant cannot acquire the claim while it is current.
+        let claim = lease
+            .ensure_claimed("bob", &params)
+            .await
+            .expect("lease must be claimed");
+        assert_eq!(claim.holder, "alice");
+        assert!(claim.is_current_at(clock.now()));
+        assert_eq!(patches.load(Ordering::SeqCst), 1);
+
+        // Once the claim expires, another claimant acquires it.
+        clock.advance(Duration::from_secs(31));
+        let claim = lease
+            .ensure_claimed("bob", &params)
+            .await
+            .expect("lease must be acquired");
+        assert_eq!(claim.holder, "bob");
+        assert_eq!(patches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn spawned_task_stops_retrying() {
+        let t0 = chrono::Utc::now() - chrono::Duration::seconds(60);
+        let patches = Arc::new(AtomicUsize::new(0));
+        let api = mock_api({
+            let patches = patches.clone();
+            move |req| {
+                if req.method() == hyper::Method::PATCH {
+                    // The initial claim succeeds, but all subsequent updates
+                    // fail.
+                    if patches.fetch_add(1, Ordering::SeqCst) == 0 {
+                        return Ok(mk_lease(2, "bob", chrono::Utc::now()));
+                    }
+                    return Err(hyper::StatusCode::INTERNAL_SERVER_ERROR);
+                }
+                Ok(mk_lease(1, "alice", t0))
+            }
+        });
+        let params = ClaimParams {
+            lease_duration: Duration::from_secs(1),
+            renew_grace_period: Duration::from_millis(900),
+        };
+        let lease = LeaseManager::init(api, "lease")
+            .await
+            .expect("lease must initialize")
+            .with_max_retry_elapsed(Some(Duration::from_millis(100)));
+
+        let (claims, task) = lease
+            .spawn("bob", params)
+            .await
+            .expect("lease must be claimed");
+        assert_eq!(claims.borrow().holder, "bob");
+
+        let res = tokio::time::timeout(Duration::from_secs(10), task)
+            .await
+            .expect("task must stop retrying")
+            .expect("task must not panic");
+        assert!(matches!(res, Err(Error::Api(_))), "{res:?}");
+        assert!(patches.load(Ordering::SeqCst) > 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spawn_times_out_initial_claim() {
+        let t0 = chrono::Utc::now() - chrono::Duration::seconds(60);
+        let svc = tower::service_fn(move |req: hyper::Request<kube_client::client::Body>| {
+            // Reads succeed, but claims never complete.
+            let get = req.method() == hyper::Method::GET;
+            async move {
+                if !get {
+                    future::pending::<()>().await;
+                }
+                let body = serde_json::to_vec(&mk_lease(1, "alice", t0)).unwrap();
+                Ok::<_, std::convert::Infallible>(hyper::Response::new(
+                    kube_client::client::Body::from(body),
+                ))
+            }
+        });
+        let api = Api::namespaced(kube_client::Client::new(svc, "default"), "default");
+        let lease = LeaseManager::init(api, "lease")
+            .await
+            .expect("lease must initialize")
+            .with_initial_claim_timeout(Some(Duration::from_secs(1)));
+
+        let start = time::Instant::now();
+        let res = lease.spawn("bob", ClaimParams::default()).await;
+        assert!(matches!(res, Err(Error::Timeout)), "{:?}", res.err());
+        // The initial claim times out before the API request does.
+        assert!(start.elapsed() < <LeaseManager>::API_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn observes_changes() {
+        let now = chrono::Utc::now();
+        let gets = Arc::new(AtomicUsize::new(0));
+        let api = mock_api({
+            let gets = gets.clone();
+            move |req| {
+                assert_eq!(
+                    req.method(),
+                    hyper::Method::GET,
+                    "lease must not be claimed"
+                );
+                match gets.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Ok(mk_lease(1, "alice", now)),
+                    _ => Ok(mk_lease(2, "bob", now)),
+                }
+            }
+        });
+        let lease = LeaseManager::init(api, "lease")
+            .await
+            .expect("lease must initialize");
+
+        let params = ObserveParams {
+            poll_interval: Duration::from_millis(1),
+        };
+        let claims = lease.observe(params).take(2).collect::<Vec<_>>();
+        let claims = tokio::time::timeout(Duration::from_secs(10), claims)
+            .await
+            .expect("claims must be observed");
+        let holders = claims
+            .iter()
+            .map(|c| c.as_ref().map(|c| c.holder.as_str()))
+            .collect::<Vec<_>>();
+        // Unchanged claims are not yielded.
+        assert_eq!(holders, [Some("alice"), Some("bob")]);
+        assert!(gets.load(Ordering::SeqCst) >= 3);
+    }
+
+    /// A custom resource that stores its lease fields like a `Lease`.
+    #[derive(Clone, Debug)]
+    struct TestLease(coordv1::Lease);
+
+    impl<'de> serde::Deserialize<'de> for TestLease {
+        fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+            coordv1::Lease::deserialize(de).map(Self)
+        }
+    }
+
+    impl kube_core::Resource for TestLease {
+        type DynamicType = ();
+        type Scope = k8s_openapi::NamespaceResourceScope;
+
+        fn kind(_: &()) -> Cow<'_, str> {
+            "TestLease".into()
+        }
+
+        fn group(_: &()) -> Cow<'_, str> {
+            "example.com".into()
+        }
+
+        fn version(_: &()) -> Cow<'_, str> {
+            "v1".into()
+        }
+
+        fn plural(_: &()) -> Cow<'_, str> {
+            "testleases".into()
+        }
+
+        fn meta(&self) -> &metav1::ObjectMeta {
+            &self.0.metadata
+        }
+
+        fn meta_mut(&mut self) -> &mut metav1::ObjectMeta {
+            &mut self.0.metadata
+        }
+    }
+
+    impl LeaseLike for TestLease {
+        fn into_lease_spec(self) -> Option<coordv1::LeaseSpec> {
+            self.0.spec
+        }
+    }
+
+    #[tokio::test]
+    async fn manages_custom_resource() {
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let clock = ManualClock::new(t0);
+        let patches = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let client = mock_client({
+            let patches = patches.clone();
+            move |req| {
+                assert_eq!(
+                    req.uri().path(),
+                    "/apis/example.com/v1/namespaces/default/testleases/lease"
+                );
+                if req.method() == hyper::Method::PATCH {
+                    let content_type = req.headers()[hyper::header::CONTENT_TYPE].clone();
+                    patches.lock().unwrap().push(content_type);
+                    return Ok(mk_lease(2, "alice", t0));
+                }
+                Ok(mk_lease(1, "alice", t0))
+            }
+        });
+        let api = Api::<TestLease>::default_namespaced(client);
+        let lease = LeaseManager::init_with_clock(api, "lease", clock.clone())
+            .await
+            .expect("lease must initialize");
+        let params = ClaimParams {
+            lease_duration: Duration::from_secs(30),
+            renew_grace_period: Duration::from_secs(1),
+        };
+
+        // Renewals and vacations use merge patches, since custom resources do
+        // not support strategic merge patches.
+        clock.advance(Duration::from_millis(29_500));
+        let claim = lease
+            .ensure_claimed("alice", &params)
+            .await
+            .expect("lease must be renewed");
+        assert_eq!(claim.holder, "alice");
+        assert!(lease.vacate("alice").await.expect("lease must be vacated"));
+        assert_eq!(
+            *patches.lock().unwrap(),
+            [
+                "application/merge-patch+json",
+                "application/merge-patch+json"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn uses_merge_patches() {
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let clock = ManualClock::new(t0);
+        let patches = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let api = mock_api({
+            let patches = patches.clone();
+            move |req| {
+                if req.method() == hyper::Method::PATCH {
+                    let content_type = req.headers()[hyper::header::CONTENT_TYPE].clone();
+                    patches.lock().unwrap().push(content_type);
+                    return Ok(mk_lease(2, "alice", t0));
+                }
+                Ok(mk_lease(1, "alice", t0))
+            }
+        });
+        let lease = LeaseManager::init_with_clock(api, "lease", clock.clone())
+            .await
+            .expect("lease must initialize")
+            .with_merge_patch(true);
+        let params = ClaimParams {
+            lease_duration: Duration::from_secs(30),
+            renew_grace_period: Duration::from_secs(1),
+        };
+
+        clock.advance(Duration::from_millis(29_500));
+        lease
+            .ensure_claimed("alice", &params)
+            .await
+            .expect("lease must be renewed");
+        assert!(lease.vacate("alice").await.expect("lease must be vacated"));
+        assert_eq!(
+            *patches.lock().unwrap(),
+            [
+                "application/merge-patch+json",
+                "application/merge-patch+json"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn retains_resources() {
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let clock = ManualClock::new(t0);
+        let api = mock_api(move |req| {
+            if req.method() == hyper::Method::PATCH {
+                return Ok(mk_lease(2, "alice", t0));
+            }
+            Ok(mk_lease(1, "alice", t0))
+        });
+        let lease = LeaseManager::init_with_clock(api, "lease", clock.clone())
+            .await
+            .expect("lease must initialize")
+            .with_retained_resource(true);
+        assert!(lease.resource().await.is_none());
+
+        lease.sync().await.expect("lease must sync");
+        let resource = lease.resource().await.expect("lease must be retained");
+        assert_eq!(resource.metadata.resource_version.as_deref(), Some("1"));
+
+        let params = ClaimParams {
+            lease_duration: Duration::from_secs(30),
+            renew_grace_period: Duration::from_secs(1),
+        };
+        clock.advance(Duration::from_millis(29_500));
+        lease
+            .ensure_claimed("alice", &params)
+            .await
+            .expect("lease must be renewed");
+        let resource = lease.resource().await.expect("lease must be retained");
+        assert_eq!(resource.metadata.resource_version.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn compares_claims_ignoring_subseconds() {
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let claim = Claim {
+            holder: "alice".to_string(),
+            expiry: t0 + chrono::Duration::milliseconds(250),
+        };
+        let rounded = Claim {
+            holder: "alice".to_string(),
+            expiry: t0,
+        };
+        assert_ne!(claim, rounded);
+        assert!(claim.eq_ignoring_subsecond(&rounded));
+
+        let later = Claim {
+            holder: "alice".to_string(),
+            expiry: t0 + chrono::Duration::seconds(1),
+        };
+        assert!(!claim.eq_ignoring_subsecond(&later));
+
+        let other = Claim {
+            holder: "bob".to_string(),
+            expiry: t0,
+        };
+        assert!(!claim.eq_ignoring_subsecond(&other));
+    }
+
+    #[test]
+    fn validates_claim_params() {
+        assert!(ClaimParams::default().validate().is_ok());
+
+        let params = ClaimParams {
+            lease_duration: Duration::from_secs(1),
+            renew_grace_period: Duration::from_secs(5),
+        };
+        assert!(matches!(
+            params.validate(),
+            Err(Error::InvalidParams { .. })
+        ));
+
+        let params = ClaimParams {
+            lease_duration: Duration::from_secs(5),
+            renew_grace_period: Duration::from_secs(5),
+        };
+        assert!(matches!(
+            params.validate(),
+            Err(Error::InvalidParams { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn returns_unforced_conflicts() {
+        let t0 = chrono::DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let clock = ManualClock::new(t0 + chrono::Duration::seconds(60));
+        let gets = Arc::new(AtomicUsize::new(0));
+        let api = mock_api({
+            let gets = gets.clone();
+            move |req| {
+                if req.method() == hyper::Method::PATCH {
+                    let query = req.uri().query().unwrap_or_default();
+                    assert!(!query.contains("force=true"), "{query}");
+                    return Err(hyper::StatusCode::CONFLICT);
+                }
+                gets.fetch_add(1, Ordering::SeqCst);
+                Ok(mk_lease(1, "alice", t0))
+            }
+        });
+        let lease = LeaseManager::init_with_clock(api, "lease", clock)
+            .await
+            .expect("lease must initialize")
+            .with_force_conflicts(false);
+
+        let err = lease
+            .ensure_claimed("bob", &ClaimParams::default())
+            .await
+            .expect_err("conflict must not be forced");
+        assert!(<LeaseManager>::is_conflict(&err), "{err}");
+        // The lease was re-synced once after the conflict.
+        assert_eq!(gets.load(Ordering::SeqCst), 2);
+    }
+}
@@ -12,12 +12,19 @@
 //! These features control which of `kubert`'s modules are enabled.
 //!
 //! - **admin**: Enabled the [`admin`] module.
+//! - **backoff**: Enables the [`backoff`] module.
 //! - **client**: Enables the [`client`] module.
-//! - **errors**: Enables the [`errors`] module.
+//! - **errors**: Enables the [`errors`] module. Enabling this feature flag also
+//!   enables the **backoff** feature.
 //! - **index**: Enables the [`index`] module.
 //! - **initialized**: Enables the [`initialized`] module.
-//! - **lease**: Enables the [`lease`] module.
+//! - **lease**: Enables the [`lease`] module. Enabling this feature flag also
+//!   enables the **backoff** feature.
 //! - **log**: Enables the [`log`] module.
+//! - **log-ansi**: Enables ANSI colors in plaintext logs. Colors may be
+//!   disabled at runtime via [`log::LogOptions`].
+//! - **log-journald**: Enables writing structured logs to the systemd journal
+//!   via `LogFormat::Journald`.
 //! - **requeue**: Enables the [`requeue`] module.
 //! - **runtime**: Enables the [`runtime`] module. Enabling this feature flag
 //!   also enables the **admin**, **client**, **initialized**, and **log**
@@ -70,6 +77,33 @@
 //!
 //!    curl 'http://localhost:8080/kubert.json?resources'
 //!
+//! Watches may be filtered by their resource kind (matched case-insensitively)
+//! or API URL with the `kind` and `api_url` query parameters, respectively.
+//!
+//!    curl 'http://localhost:8080/kubert.json?resources&kind=Pod'
+//!
+//! Responses are pretty-printed by default. Programmatic clients may request
+//! compact JSON with the `compact` query parameter.
+//!
+//!    curl 'http://localhost:8080/kubert.json?compact'
+//!
+//! Responses may be deserialized with the types in the [`admin::diagnostics`]
+//! module.
+//!
+//! When the **lease** feature is also enabled, the `/leases.json` endpoint
+//! serves only the state of each lease (including its current holder and
+//! expiry), so that leadership may be monitored independently of the full
+//! diagnostics. Like `/kubert.json`, this endpoint only serves loopback
+//! clients.
+//!
+//!    curl 'http://localhost:8080/leases.json'
+//!
+//! Leases spawned with [`runtime::Runtime::spawn_named_lease`] are labeled with their
+//! component name, and both endpoints may filter leases by component with the
+//! `component` query parameter.
+//!
+//!    curl 'http://localhost:8080/leases.json?component=leader'
+//!
 //! [`kube`]: https://github.com/kube-rs/kube-rs
 //! [Cargo features]: https://doc.rust-lang.org/cargo/reference/features.html
 //! [`clap`]: https://crates.io/crates/clap
@@ -83,14 +117,24 @@
 #![forbid(unsafe_code)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(any(feature = "admin", feature = "server"))]
+mod accept;
+
 #[cfg(feature = "admin")]
 #[cfg_attr(docsrs, doc(cfg(feature = "admin")))]
 pub mod admin;
 
+#[cfg(feature = "backoff")]
+#[cfg_attr(docsrs, doc(cfg(feature = "backoff")))]
+pub mod backoff;
+
 #[cfg(feature = "client")]
 #[cfg_attr(docsrs, doc(cfg(feature = "client")))]
 pub mod client;
 
+#[cfg(all(feature = "clap", any(feature = "admin", feature = "client")))]
+mod duration;
+
 #[cfg(feature = "errors")]
 #[cfg_attr(docsrs, doc(cfg(feature = "errors")))]
 pub mod errors;
@@ -140,7 +184,7 @@ pub use self::initialized::Initialized;
 pub use self::lease::{LeaseManager, LeaseParams};
 
 #[cfg(feature = "log")]
-pub use self::log::{LogFilter, LogFormat, LogInitError};
+pub use self::log::{LogFilter, LogFilterBuilder, LogFormat, LogInitError, LogOptions};
 
 #[cfg(feature = "runtime")]
 pub use self::runtime::Runtime;
@@ -1,15 +1,25 @@
 //! Configures the global default tracing subscriber
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
 use thiserror::Error;
-use tracing::{metadata::LevelFilter, span, subscriber::Interest, Metadata, Subscriber};
+use tracing::{
+    callsite::Identifier, metadata::LevelFilter, span, subscriber::Interest, Level, Metadata,
+    Subscriber,
+};
 use tracing_subscriber::{
-    filter::ParseError,
+    filter::{Directive, ParseError},
+    fmt::time::{FormatTime, SystemTime, Uptime},
     layer::{Context, Filter},
     EnvFilter, Layer,
 };
 
-pub use tracing_subscriber::util::TryInitError as LogInitError;
+pub use tracing_subscriber::{fmt::format::FmtSpan, util::TryInitError as LogInitError};
 
 /// Configures whether logs should be emitted in plaintext (the default) or as JSON-encoded
 /// messages
@@ -21,6 +31,51 @@ pub enum LogFormat {
 
     /// The JSON-encoded format
     Json,
+
+    /// Structured logs written to the systemd journal
+    ///
+    /// If the journal is not available, plaintext logs are written instead.
+    #[cfg(feature = "log-journald")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "log-journald")))]
+    Journald,
+}
+
+/// Configures how log messages are rendered
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(docsrs, doc(cfg(feature = "log")))]
+pub struct LogOptions {
+    ansi: Option<bool>,
+    timestamp: LogTimestamp,
+    sample_one_in: u64,
+    span_events: Option<FmtSpan>,
+}
+
+/// Configures how log timestamps are formatted
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "log")))]
+pub enum LogTimestamp {
+    /// RFC 3339 timestamps in UTC (the default)
+    #[default]
+    Rfc3339,
+
+    /// Compact timestamps indicating the time elapsed since logging was initialized
+    Uptime,
+}
+
+/// Emits only one in every `one_in` events for each callsite.
+///
+/// Warnings and errors are never sampled.
+#[derive(Debug)]
+struct Sampler {
+    one_in: u64,
+    counts: RwLock<HashMap<Identifier, AtomicU64>>,
+}
+
+/// Formats timestamps as configured by a [`LogTimestamp`].
+#[derive(Clone, Debug)]
+enum Timer {
+    Rfc3339(SystemTime),
+    Uptime(Uptime),
 }
 
 /// Configures the global default tracing filters.
@@ -30,9 +85,30 @@ pub enum LogFormat {
 #[cfg_attr(docsrs, doc(cfg(feature = "log")))]
 pub struct LogFilter(Arc<EnvFilter>);
 
+/// Builds a [`LogFilter`] from directives configured in code and the environment
+///
+/// Directives are applied in order: default directives, then directives from the environment
+/// (`RUST_LOG` by default), and then override directives. A later directive for the same target
+/// replaces an earlier one, so, for example, an override of `kube=debug` takes precedence over
+/// `kube=info` set in the environment.
+#[derive(Clone, Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "log")))]
+pub struct LogFilterBuilder {
+    env: Option<String>,
+    defaults: Vec<String>,
+    overrides: Vec<String>,
+}
+
 /// Indicates that an invalid log format was specified
 #[derive(Debug, Error)]
-#[error("invalid log level: {0} must be 'plain' or 'json'")]
+#[cfg_attr(
+    not(feature = "log-journald"),
+    error("invalid log format: {0} must be 'plain' or 'json'")
+)]
+#[cfg_attr(
+    feature = "log-journald",
+    error("invalid log format: {0} must be 'plain', 'json', or 'journald'")
+)]
 #[cfg_attr(docsrs, doc(cfg(feature = "log")))]
 pub struct InvalidLogFormat(String);
 
@@ -53,6 +129,14 @@ impl LogFilter {
     }
 }
 
+impl LogFilter {
+    /// Returns a [`LogFilterBuilder`] that reads directives from the `RUST_LOG` environment
+    /// variable
+    pub fn builder() -> LogFilterBuilder {
+        LogFilterBuilder::default()
+    }
+}
+
 impl std::str::FromStr for LogFilter {
     type Err = ParseError;
 
@@ -152,6 +236,217 @@ impl std::fmt::Display for LogFilter {
     }
 }
 
+// === impl LogFilterBuilder ===
+
+impl Default for LogFilterBuilder {
+    fn default() -> Self {
+        Self {
+            env: Some(EnvFilter::DEFAULT_ENV.to_string()),
+            defaults: Vec::new(),
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl LogFilterBuilder {
+    /// Reads directives from the given environment variable instead of `RUST_LOG`
+    pub fn with_env_var(mut self, var: impl ToString) -> Self {
+        self.env = Some(var.to_string());
+        self
+    }
+
+    /// Ignores directives from the environment
+    pub fn without_env(mut self) -> Self {
+        self.env = None;
+        self
+    }
+
+    /// Adds a directive that may be overridden by the environment
+    pub fn with_default_directive(mut self, directive: impl ToString) -> Self {
+        self.defaults.push(directive.to_string());
+        self
+    }
+
+    /// Adds a directive that overrides the environment
+    pub fn with_directive(mut self, directive: impl ToString) -> Self {
+        self.overrides.push(directive.to_string());
+        self
+    }
+
+    /// Builds a [`LogFilter`]
+    ///
+    /// Invalid directives in the environment are reported on stderr and ignored. If no
+    /// directives are configured, a default directive enabling the [`ERROR`] level is added.
+    ///
+    /// [`ERROR`]: tracing::Level::ERROR
+    pub fn build(self) -> Result<LogFilter, ParseError> {
+        let env = self
+            .env
+            .and_then(|var| std::env::var(var).ok())
+            .unwrap_or_default();
+        let env = split_directives(&env)
+            .into_iter()
+            .filter_map(|d| match d.parse::<Directive>() {
+                Ok(directive) => Some(directive),
+                Err(error) => {
+                    eprintln!("ignoring `{d}`: {error}");
+                    None
+                }
+            });
+
+        let parse = |dirs: &[String]| {
+            dirs.iter()
+                .flat_map(|d| split_directives(d))
+                .map(str::parse::<Directive>)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let defaults = parse(&self.defaults)?;
+        let overrides = parse(&self.overrides)?;
+
+        // Directives are added rather than parsed from a single string so that commas within
+        // field filters are preserved. Later directives replace earlier ones for the same target.
+        let mut directives = defaults
+            .into_iter()
+            .chain(env)
+            .chain(overrides)
+            .collect::<Vec<_>>();
+        if directives.is_empty() {
+            directives.push(LevelFilter::ERROR.into());
+        }
+        let filter = directives
+            .into_iter()
+            .fold(EnvFilter::default(), EnvFilter::add_directive);
+        Ok(LogFilter(filter.into()))
+    }
+}
+
+/// Splits a comma-separated list of directives, ignoring commas within span field filters (e.g.
+/// `target[span{a=1,b=2}]=debug`).
+fn split_directives(dirs: &str) -> Vec<&str> {
+    let mut directives = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in dirs.char_indices() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                directives.push(&dirs[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    directives.push(&dirs[start..]);
+    directives.retain(|d| !d.is_empty());
+    directives
+}
+
+// === impl LogOptions ===
+
+impl LogOptions {
+    /// Forcibly enables or disables ANSI colors in plaintext logs
+    ///
+    /// By default, colors are used when the **log-ansi** feature is enabled
+    /// (unless the `NO_COLOR` environment variable is set). Colors cannot be
+    /// enabled unless the **log-ansi** feature is enabled.
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.ansi = Some(ansi);
+        self
+    }
+
+    /// Configures how log timestamps are formatted
+    pub fn with_timestamp(mut self, timestamp: LogTimestamp) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Emits only one in every `one_in` `INFO`, `DEBUG`, and `TRACE` events from each callsite
+    ///
+    /// This reduces log volume for hot paths that emit the same message many times. Warnings and
+    /// errors are always emitted. Values of 0 or 1 disable sampling (the default).
+    pub fn with_sampling(mut self, one_in: u32) -> Self {
+        self.sample_one_in = one_in.into();
+        self
+    }
+
+    /// Configures which span lifecycle events are logged
+    ///
+    /// For example, [`FmtSpan::CLOSE`] logs each span's busy and idle durations when it closes.
+    /// By default, span lifecycle events are not logged.
+    pub fn with_span_events(mut self, events: FmtSpan) -> Self {
+        self.span_events = Some(events);
+        self
+    }
+
+    fn span_events(&self) -> FmtSpan {
+        self.span_events.clone().unwrap_or(FmtSpan::NONE)
+    }
+
+    fn sampler(&self) -> Sampler {
+        Sampler {
+            one_in: self.sample_one_in.max(1),
+            counts: Default::default(),
+        }
+    }
+
+    fn timer(&self) -> Timer {
+        match self.timestamp {
+            LogTimestamp::Rfc3339 => Timer::Rfc3339(SystemTime),
+            LogTimestamp::Uptime => Timer::Uptime(Uptime::default()),
+        }
+    }
+}
+
+// === impl Sampler ===
+
+impl Sampler {
+    fn is_sampled(&self, meta: &Metadata<'_>) -> bool {
+        self.one_in > 1 && meta.is_event() && *meta.level() > Level::WARN
+    }
+}
+
+impl<S> Filter<S> for Sampler {
+    fn enabled(&self, meta: &Metadata<'_>, _: &Context<'_, S>) -> bool {
+        if !self.is_sampled(meta) {
+            return true;
+        }
+
+        let id = meta.callsite();
+        let n = {
+            let counts = self.counts.read().unwrap_or_else(|e| e.into_inner());
+            counts.get(&id).map(|c| c.fetch_add(1, Ordering::Relaxed))
+        };
+        let n = n.unwrap_or_else(|| {
+            let mut counts = self.counts.write().unwrap_or_else(|e| e.into_inner());
+            counts
+                .entry(id)
+                .or_default()
+                .fetch_add(1, Ordering::Relaxed)
+        });
+        n % self.one_in == 0
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        if self.is_sampled(meta) {
+            // Evaluate each event so that it may be counted.
+            Interest::sometimes()
+        } else {
+            Interest::always()
+        }
+    }
+}
+
+// === impl Timer ===
+
+impl FormatTime for Timer {
+    fn format_time(&self, w: &mut tracing_subscriber::fmt::format::Writer<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rfc3339(t) => t.format_time(w),
+            Self::Uptime(t) => t.format_time(w),
+        }
+    }
+}
+
 // === impl LogFormat ===
 
 impl Default for LogFormat {
@@ -167,6 +462,8 @@ impl std::str::FromStr for LogFormat {
         match s {
             "json" => Ok(LogFormat::Json),
             "plain" => Ok(LogFormat::Plain),
+            #[cfg(feature = "log-journald")]
+            "journald" => Ok(LogFormat::Journald),
             s => Err(InvalidLogFormat(s.to_string())),
         }
     }
@@ -179,12 +476,31 @@ impl LogFormat {
     /// This method returns an error if a global default subscriber has already been set, or if a
     /// `log` logger has already been set.
     pub fn try_init(self, filter: LogFilter) -> Result<(), LogInitError> {
+        self.try_init_with(filter, LogOptions::default())
+    }
+
+    /// Attempts to configure the global default tracing subscriber in the current scope with the
+    /// given [`LogOptions`], returning an error if one is already set
+    ///
+    /// See [`LogFormat::try_init`] for details.
+    pub fn try_init_with(self, filter: LogFilter, options: LogOptions) -> Result<(), LogInitError> {
         use tracing_subscriber::prelude::*;
 
-        let registry = tracing_subscriber::registry().with(filter);
+        let registry = tracing_subscriber::registry().with(filter.clone());
 
         match self {
-            LogFormat::Plain => registry.with(tracing_subscriber::fmt::layer()).try_init()?,
+            LogFormat::Plain => {
+                let mut fmt = tracing_subscriber::fmt::layer()
+                    .with_timer(options.timer())
+                    .with_span_events(options.span_events());
+                if let Some(ansi) = options.ansi {
+                    // Colors cannot be enabled without the `ansi` feature.
+                    fmt = fmt.with_ansi(ansi && cfg!(feature = "log-ansi"));
+                }
+                registry
+                    .with(fmt.with_filter(options.sampler()))
+                    .try_init()?
+            }
 
             LogFormat::Json => {
                 let event_fmt = tracing_subscriber::fmt::format()
@@ -198,13 +514,107 @@ impl LogFormat {
 
                 // Use the JSON event formatter and the JSON field formatter.
                 let fmt = tracing_subscriber::fmt::layer()
-                    .event_format(event_fmt)
-                    .fmt_fields(tracing_subscriber::fmt::format::JsonFields::default());
+                    .event_format(event_fmt.with_timer(options.timer()))
+                    .fmt_fields(tracing_subscriber::fmt::format::JsonFields::default())
+                    .with_span_events(options.span_events());
 
-                registry.with(fmt).try_init()?
+                registry
+                    .with(fmt.with_filter(options.sampler()))
+                    .try_init()?
             }
+
+            #[cfg(feature = "log-journald")]
+            LogFormat::Journald => match tracing_journald::layer() {
+                Ok(journald) => registry
+                    .with(journald.with_filter(options.sampler()))
+                    .try_init()?,
+                Err(error) => {
+                    LogFormat::Plain.try_init_with(filter, options)?;
+                    tracing::warn!(%error, "Failed to connect to journald; using plaintext logs");
+                }
+            },
         };
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_default_directives() {
+        let filter = LogFilter::builder()
+            .without_env()
+            .with_default_directive("info")
+            .with_default_directive("kube=info")
+            .with_directive("kube=debug")
+            .build()
+            .expect("filter must be valid");
+        let directives = filter.to_string();
+        assert!(directives.contains("kube=debug"), "{directives}");
+        assert!(!directives.contains("kube=info"), "{directives}");
+    }
+
+    #[test]
+    fn samples_events() {
+        use tracing_subscriber::prelude::*;
+
+        #[derive(Clone, Default)]
+        struct Count(Arc<AtomicU64>);
+        impl<S: Subscriber> Layer<S> for Count {
+            fn on_event(&self, _: &tracing::Event<'_>, _: Context<'_, S>) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let count = Count::default();
+        let sampler = LogOptions::default().with_sampling(10).sampler();
+        let subscriber = tracing_subscriber::registry().with(count.clone().with_filter(sampler));
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..100 {
+                tracing::info!("sampled");
+                tracing::warn!("not sampled");
+            }
+        });
+        assert_eq!(count.0.load(Ordering::Relaxed), 10 + 100);
+    }
+
+    #[test]
+    fn rejects_invalid_directives() {
+        assert!(LogFilter::builder()
+            .without_env()
+            .with_directive("kube=notalevel")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn splits_field_filters() {
+        assert_eq!(
+            split_directives("info,t[s{a=1,b=2}]=debug,,kube=trace"),
+            ["info", "t[s{a=1,b=2}]=debug", "kube=trace"]
+        );
+    }
+
+    #[test]
+    fn parses_env_field_filters() {
+        const VAR: &str = "KUBERT_TEST_LOG_FIELD_FILTERS";
+        std::env::set_var(VAR, "t[s{a=1,b=2}]=debug,kube=notalevel,kube=trace");
+        let filter = LogFilter::builder()
+            .with_env_var(VAR)
+            .with_default_directive("info")
+            .build()
+            .expect("filter must be valid");
+        std::env::remove_var(VAR);
+
+        let directives = filter.to_string();
+        assert!(directives.contains("t[s{"), "{directives}");
+        assert!(directives.contains("a=1"), "{directives}");
+        assert!(directives.contains("b=2"), "{directives}");
+        assert!(directives.contains("kube=trace"), "{directives}");
+        assert!(!directives.contains("notalevel"), "{directives}");
+        assert!(directives.contains("info"), "{directives}");
+    }
+}
@@ -3,12 +3,16 @@
 
 use std::{
     collections::{hash_map, HashMap},
+    future::Future,
     hash::Hash,
     pin::Pin,
     task::{Context, Poll},
 };
 use tokio::{
-    sync::mpsc::{self, error::SendError},
+    sync::mpsc::{
+        self,
+        error::{SendError, TrySendError},
+    },
     time::{Duration, Instant},
 };
 use tokio_util::time::{delay_queue, DelayQueue};
@@ -29,12 +33,28 @@ where
 {
     rx: mpsc::Receiver<Op<T>>,
     rx_closed: bool,
+    close_when: Option<Pin<Box<dyn Future<Output = ()> + Send + Sync + 'static>>>,
     q: DelayQueue<T>,
     pending: HashMap<T, delay_queue::Key>,
 }
 
 /// Creates a bounded, delayed mpsc channel for requeuing controller updates.
 pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>)
+where
+    T: Eq + Hash,
+{
+    channel_with_queue_capacity(capacity, 0)
+}
+
+/// Creates a bounded, delayed mpsc channel for requeuing controller updates, reserving space for
+/// `queue_capacity` pending values.
+///
+/// This avoids reallocating the receiver's internal queue during a burst of requeues when the
+/// number of pending values is known to be large.
+pub fn channel_with_queue_capacity<T>(
+    capacity: usize,
+    queue_capacity: usize,
+) -> (Sender<T>, Receiver<T>)
 where
     T: Eq + Hash,
 {
@@ -42,20 +62,45 @@ where
     let rx = Receiver {
         rx,
         rx_closed: false,
-        q: DelayQueue::new(),
-        pending: HashMap::new(),
+        close_when: None,
+        q: DelayQueue::with_capacity(queue_capacity),
+        pending: HashMap::with_capacity(queue_capacity),
     };
     (Sender { tx }, rx)
 }
 
 enum Op<T> {
     Requeue(T, Instant),
+    RequeueMany(Vec<(T, Instant)>),
     Cancel(T),
     Clear,
 }
 
 // === impl Receiver ===
 
+impl<T> Receiver<T>
+where
+    T: Eq + Hash,
+{
+    /// Closes the receiver so that [`Sender`]s observe [`Sender::closed`] and further sends fail.
+    ///
+    /// Updates that were sent before the receiver was closed are still processed, and pending
+    /// values continue to be yielded as their delays elapse.
+    pub fn close(&mut self) {
+        self.rx.close();
+        self.close_when = None;
+    }
+
+    /// Closes the receiver once the given future completes.
+    ///
+    /// The future is polled as the receiver is polled, so the receiver must continue to be polled
+    /// for the close to take effect.
+    pub fn close_when(mut self, signal: impl Future<Output = ()> + Send + Sync + 'static) -> Self {
+        self.close_when = Some(Box::pin(signal));
+        self
+    }
+}
+
 impl<T> Receiver<T>
 where
     T: Clone + Eq + Hash,
@@ -66,6 +111,13 @@ where
     pub fn poll_requeued(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
         tracing::trace!(rx.closed = self.rx_closed, pending = self.pending.len());
 
+        if let Some(signal) = self.close_when.as_mut() {
+            if signal.as_mut().poll(cx).is_ready() {
+                tracing::debug!("Closing requeue channel");
+                self.close();
+            }
+        }
+
         // We process messages from the sender before looking at the delay queue so that
         // updates have a chance to reset/cancel pending updates.
         if !self.rx_closed {
@@ -78,30 +130,7 @@ where
                         break;
                     }
 
-                    Poll::Ready(Some(Op::Clear)) => {
-                        self.pending.clear();
-                        self.q.clear();
-                    }
-
-                    Poll::Ready(Some(Op::Cancel(obj))) => {
-                        if let Some(key) = self.pending.remove(&obj) {
-                            tracing::trace!(?key, "canceling");
-                            self.q.remove(&key);
-                        }
-                    }
-
-                    Poll::Ready(Some(Op::Requeue(k, at))) => match self.pending.entry(k) {
-                        hash_map::Entry::Occupied(ent) => {
-                            let key = ent.get();
-                            tracing::trace!(?key, "resetting");
-                            self.q.reset_at(key, at);
-                        }
-                        hash_map::Entry::Vacant(slot) => {
-                            let key = self.q.insert_at(slot.key().clone(), at);
-                            tracing::trace!(?key, "inserting");
-                            slot.insert(key);
-                        }
-                    },
+                    Poll::Ready(Some(op)) => self.apply(op),
                 }
             }
         }
@@ -121,6 +150,59 @@ where
             Poll::Pending
         }
     }
+
+    /// Removes and returns all pending values, regardless of their delay.
+    ///
+    /// Updates that have already been sent are processed first so that, for example, pending
+    /// values may be checkpointed before the process exits. Values are returned in an arbitrary
+    /// order.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> {
+        while let Ok(op) = self.rx.try_recv() {
+            self.apply(op);
+        }
+
+        self.q.clear();
+        std::mem::take(&mut self.pending).into_keys()
+    }
+
+    fn apply(&mut self, op: Op<T>) {
+        match op {
+            Op::Clear => {
+                self.pending.clear();
+                self.q.clear();
+            }
+
+            Op::Cancel(obj) => {
+                if let Some(key) = self.pending.remove(&obj) {
+                    tracing::trace!(?key, "canceling");
+                    self.q.remove(&key);
+                }
+            }
+
+            Op::Requeue(k, at) => self.requeue(k, at),
+
+            Op::RequeueMany(objs) => {
+                for (k, at) in objs {
+                    self.requeue(k, at);
+                }
+            }
+        }
+    }
+
+    fn requeue(&mut self, k: T, at: Instant) {
+        match self.pending.entry(k) {
+            hash_map::Entry::Occupied(ent) => {
+                let key = ent.get();
+                tracing::trace!(?key, "resetting");
+                self.q.reset_at(key, at);
+            }
+            hash_map::Entry::Vacant(slot) => {
+                let key = self.q.insert_at(slot.key().clone(), at);
+                tracing::trace!(?key, "inserting");
+                slot.insert(key);
+            }
+        }
+    }
 }
 
 // We never put `T` in a `Pin`...
@@ -137,11 +219,16 @@ impl<T: Clone + Eq + Hash> futures_core::Stream for Receiver<T> {
 // === impl Receiver ===
 
 impl<T> Sender<T> {
-    /// Waits for all receivers to be dropped.
+    /// Waits for the receiver to be dropped or closed (see [`Receiver::close`]).
     pub async fn closed(&self) {
         self.tx.closed().await
     }
 
+    /// Returns whether the receiver has been dropped or closed (see [`Receiver::close`]).
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+
     /// Cancels all pending work.
     pub async fn clear(&self) -> Result<(), SendError<()>> {
         self.tx
@@ -166,6 +253,52 @@ impl<T> Sender<T> {
         self.requeue_at(obj, Instant::now() + defer).await
     }
 
+    /// Attempts to schedule the given object to be rescheduled at the given time without waiting
+    /// for channel capacity.
+    ///
+    /// An error is returned if the channel is full or if the receiver has been dropped.
+    pub fn try_requeue_at(&self, obj: T, time: Instant) -> Result<(), TrySendError<T>> {
+        self.tx
+            .try_send(Op::Requeue(obj, time))
+            .map_err(|error| match error {
+                TrySendError::Full(Op::Requeue(obj, _)) => TrySendError::Full(obj),
+                TrySendError::Closed(Op::Requeue(obj, _)) => TrySendError::Closed(obj),
+                _ => unreachable!(),
+            })
+    }
+
+    /// Attempts to schedule the given object to be rescheduled after the `defer` time has passed
+    /// without waiting for channel capacity.
+    ///
+    /// This may be used in synchronous contexts, e.g. from within a `poll` function.
+    pub fn try_requeue(&self, obj: T, defer: Duration) -> Result<(), TrySendError<T>> {
+        self.try_requeue_at(obj, Instant::now() + defer)
+    }
+
+    /// Schedule each of the given objects to be rescheduled after its `defer` time has passed.
+    ///
+    /// All objects are sent to the receiver as a single batch.
+    pub async fn requeue_many(
+        &self,
+        objs: impl IntoIterator<Item = (T, Duration)>,
+    ) -> Result<(), SendError<Vec<T>>> {
+        let now = Instant::now();
+        let objs = objs
+            .into_iter()
+            .map(|(obj, defer)| (obj, now + defer))
+            .collect::<Vec<_>>();
+        if objs.is_empty() {
+            return Ok(());
+        }
+        self.tx
+            .send(Op::RequeueMany(objs))
+            .await
+            .map_err(|SendError(op)| match op {
+                Op::RequeueMany(objs) => SendError(objs.into_iter().map(|(obj, _)| obj).collect()),
+                _ => unreachable!(),
+            })
+    }
+
     /// Cancels pending updates for the given object.
     pub async fn cancel(&self, obj: T) -> Result<(), SendError<T>> {
         self.tx
@@ -333,4 +466,137 @@ mod tests {
         tx.clear().await.expect("must send cancel");
         assert_pending!(rx.poll_next());
     }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn requeues_many() {
+        let _tracing = init_tracing();
+        time::pause();
+        let (tx, mut rx) = spawn_channel(1);
+
+        let pod_a = ObjectRef::new("pod-a").within("default");
+        let pod_b = ObjectRef::new("pod-b").within("default");
+        tx.requeue_many([
+            (pod_a.clone(), Duration::from_secs(10)),
+            (pod_b.clone(), Duration::from_secs(20)),
+        ])
+        .await
+        .expect("must send");
+        assert_pending!(rx.poll_next());
+
+        sleep(Duration::from_millis(10001)).await;
+        assert_eq!(
+            assert_ready!(rx.poll_next()).expect("stream must not end"),
+            pod_a
+        );
+        assert_pending!(rx.poll_next());
+
+        sleep(Duration::from_secs(10)).await;
+        assert_eq!(
+            assert_ready!(rx.poll_next()).expect("stream must not end"),
+            pod_b
+        );
+        assert_pending!(rx.poll_next());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn try_requeues() {
+        let _tracing = init_tracing();
+        time::pause();
+        let (tx, mut rx) = spawn_channel(1);
+
+        let pod_a = ObjectRef::new("pod-a").within("default");
+        let pod_b = ObjectRef::new("pod-b").within("default");
+        tx.try_requeue(pod_a.clone(), Duration::from_secs(10))
+            .expect("must send");
+        match tx.try_requeue(pod_b.clone(), Duration::from_secs(10)) {
+            Err(TrySendError::Full(obj)) => assert_eq!(obj, pod_b),
+            res => panic!("unexpected result: {res:?}"),
+        }
+        assert_pending!(rx.poll_next());
+
+        sleep(Duration::from_millis(10001)).await;
+        assert_eq!(
+            assert_ready!(rx.poll_next()).expect("stream must not end"),
+            pod_a
+        );
+        assert_pending!(rx.poll_next());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn drains() {
+        let _tracing = init_tracing();
+        time::pause();
+        let (tx, mut rx) = spawn_channel(2);
+
+        let pod_a = ObjectRef::new("pod-a").within("default");
+        let pod_b = ObjectRef::new("pod-b").within("default");
+        tx.requeue(pod_a.clone(), Duration::from_secs(10))
+            .await
+            .expect("must send");
+        assert_pending!(rx.poll_next());
+        // Sent but not yet processed by the receiver.
+        tx.requeue(pod_b.clone(), Duration::from_secs(10))
+            .await
+            .expect("must send");
+
+        let mut drained = rx.enter(|_, rx| rx.get_mut().drain().collect::<Vec<_>>());
+        drained.sort_by_key(|obj| obj.name.clone());
+        assert_eq!(drained, vec![pod_a, pod_b]);
+
+        sleep(Duration::from_secs(11)).await;
+        assert_pending!(rx.poll_next());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn is_closed() {
+        let (tx, rx) = channel::<ObjectRef<Pod>>(1);
+        assert!(!tx.is_closed());
+        drop(rx);
+        assert!(tx.is_closed());
+
+        let (tx, mut rx) = channel::<ObjectRef<Pod>>(1);
+        rx.close();
+        assert!(tx.is_closed());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn close_when() {
+        time::pause();
+        let _tracing = init_tracing();
+        let (signal_tx, signal_rx) = tokio::sync::oneshot::channel::<()>();
+        let (tx, rx) = channel::<ObjectRef<Pod>>(2);
+        let mut rx = task::spawn(rx.close_when(async move {
+            let _ = signal_rx.await;
+        }));
+
+        let pod_a = ObjectRef::new("pod-a").within("default");
+        tx.requeue(pod_a.clone(), Duration::from_secs(10))
+            .await
+            .expect("must send");
+        assert_pending!(rx.poll_next());
+        assert!(!tx.is_closed());
+
+        // Once signaled, the sender observes the close but pending values are still yielded.
+        signal_tx.send(()).unwrap();
+        assert!(rx.is_woken());
+        assert_pending!(rx.poll_next());
+        assert!(tx.is_closed());
+        tx.requeue(ObjectRef::new("pod-b").within("default"), Duration::ZERO)
+            .await
+            .expect_err("must not send after close");
+
+        sleep(Duration::from_secs(11)).await;
+        assert_eq!(
+            assert_ready!(rx.poll_next()).expect("stream must not end"),
+            pod_a
+        );
+        assert!(assert_ready!(rx.poll_next()).is_none());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn reserves_queue_capacity() {
+        let (_tx, rx) = channel_with_queue_capacity::<ObjectRef<Pod>>(1, 100);
+        assert!(rx.q.capacity() >= 100);
+        assert!(rx.pending.capacity() >= 100);
+    }
 }
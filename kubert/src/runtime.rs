@@ -6,16 +6,17 @@ use crate::lease;
 use crate::server::{self, ServerArgs};
 use crate::{
     admin::{self, Readiness},
+    backoff::Backoff,
     client::{self, Client, ClientArgs},
     errors,
     initialized::{self, Initialized},
-    shutdown, LogFilter, LogFormat, LogInitError,
+    shutdown, LogFilter, LogFormat, LogInitError, LogOptions,
 };
 use futures_core::Stream;
-use kube_core::{NamespaceResourceScope, Resource};
+use kube_core::{NamespaceResourceScope, PartialObjectMeta, Resource};
 use kube_runtime::{reflector, watcher};
 use serde::de::DeserializeOwned;
-use std::{fmt::Debug, future::Future, hash::Hash, time::Duration};
+use std::{fmt::Debug, future::Future, hash::Hash, sync::Arc, time::Duration};
 #[cfg(feature = "server")]
 use tower::Service;
 
@@ -24,6 +25,8 @@ pub use reflector::Store;
 
 #[cfg(feature = "prometheus-client")]
 mod metrics;
+mod namespaces;
+mod panics;
 
 /// Configures a controller [`Runtime`]
 #[derive(Debug, Default)]
@@ -33,6 +36,13 @@ pub struct Builder<S = NoServer> {
     admin: admin::Builder,
     client: Option<ClientArgs>,
     error_delay: Option<Duration>,
+    error_backoff: Option<Backoff>,
+    watch_max_errors: Option<usize>,
+    watch_fail_fast: bool,
+    drain_delay: Option<Duration>,
+    without_signals: bool,
+    unready_on_panic: bool,
+    check_client: bool,
     log: Option<LogSettings>,
 
     #[cfg(feature = "server")]
@@ -42,6 +52,12 @@ pub struct Builder<S = NoServer> {
 
     #[cfg(feature = "prometheus-client")]
     metrics: Option<RuntimeMetrics>,
+
+    #[cfg(feature = "runtime-diagnostics")]
+    watch_liveness_timeout: Option<Duration>,
+
+    #[cfg(feature = "runtime-diagnostics")]
+    watch_error_budget: Option<(usize, Duration)>,
 }
 
 /// Provides infrastructure for running:
@@ -59,6 +75,11 @@ pub struct Runtime<S = NoServer> {
     admin: admin::Bound,
     client: Client,
     error_delay: Duration,
+    error_backoff: Option<Backoff>,
+    watch_max_errors: Option<usize>,
+    watch_fail_fast: bool,
+    drain_delay: Duration,
+    unready_on_panic: bool,
     initialized: Initialized,
     shutdown_rx: drain::Watch,
     shutdown: shutdown::Shutdown,
@@ -68,8 +89,18 @@ pub struct Runtime<S = NoServer> {
     #[cfg(not(feature = "server"))]
     server: std::marker::PhantomData<S>,
 
+    /// The local address of the HTTPS server, once it has been spawned
+    #[cfg(feature = "server")]
+    spawned_server_addr: Option<std::net::SocketAddr>,
+
     #[cfg(feature = "prometheus-client")]
     metrics: Option<RuntimeMetrics>,
+
+    #[cfg(feature = "runtime-diagnostics")]
+    watch_liveness_timeout: Option<Duration>,
+
+    #[cfg(feature = "runtime-diagnostics")]
+    watch_error_budget: Option<(usize, Duration)>,
 }
 
 /// Indicates that no HTTPS server is configured
@@ -82,6 +113,8 @@ pub struct NoServer(());
 #[derive(Debug)]
 pub struct RuntimeMetrics {
     watch: metrics::ResourceWatchMetrics,
+    #[cfg(feature = "server")]
+    server: server::ServerMetrics,
 }
 
 /// Indicates that the [`Builder`] could not configure a [`Runtime`]
@@ -100,6 +133,11 @@ pub enum BuildError {
     #[error(transparent)]
     Client(#[from] client::ConfigError),
 
+    /// Indicates that the Kubernetes API server could not be reached (or that the client's
+    /// credentials were rejected) when the client was checked.
+    #[error("failed to connect to the Kubernetes API server: {0}")]
+    ClientCheck(#[source] kube_client::Error),
+
     #[cfg(feature = "server")]
     /// Indicates that the HTTPS server could not be initialized
     #[error(transparent)]
@@ -114,6 +152,22 @@ pub enum BuildError {
 struct LogSettings {
     filter: LogFilter,
     format: LogFormat,
+    options: LogOptions,
+}
+
+/// Returns a [`watcher::Config`] suited to inexpensive scans of all objects of a kind
+///
+/// The initial list is paginated and may be served from the API server's watch cache (i.e. with
+/// `resourceVersion=0`), which avoids a quorum read from etcd. Note that the API server may ignore
+/// the page size when it serves a list from its cache.
+///
+/// This is used by [`Runtime::watch_metadata_all`].
+#[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+pub fn metadata_scan_config() -> watcher::Config {
+    const PAGE_SIZE: u32 = 500;
+    watcher::Config::default()
+        .any_semantic()
+        .page_size(PAGE_SIZE)
 }
 
 // === impl Builder ===
@@ -135,7 +189,18 @@ impl<S> Builder<S> {
 
     /// Configures the runtime to use the given logging configuration
     pub fn with_log(mut self, filter: LogFilter, format: LogFormat) -> Self {
-        self.log = Some(LogSettings { filter, format });
+        let options = self.log.take().map(|l| l.options).unwrap_or_default();
+        self.log = Some(LogSettings {
+            filter,
+            format,
+            options,
+        });
+        self
+    }
+
+    /// Configures the runtime to render logs with the given [`LogOptions`]
+    pub fn with_log_options(mut self, options: LogOptions) -> Self {
+        self.log.get_or_insert_with(LogSettings::default).options = options;
         self
     }
 
@@ -145,6 +210,90 @@ impl<S> Builder<S> {
         self
     }
 
+    /// Configures the runtime to use the given exponential [`Backoff`] when a stream fails
+    ///
+    /// This takes precedence over [`Builder::with_fixed_delay_on_error`]. If the backoff has a
+    /// maximum elapsed time, watches that fail for longer give up as described by
+    /// [`Builder::with_watch_max_consecutive_errors`].
+    pub fn with_backoff_on_error(mut self, backoff: Backoff) -> Self {
+        self.error_backoff = Some(backoff);
+        self
+    }
+
+    /// Configures the runtime to stop retrying a watch after it fails `max` times consecutively
+    ///
+    /// When a watch gives up, its stream ends and the admin server's readiness endpoint reports
+    /// that the runtime is not ready. If the watch had not yet produced an event, the runtime is
+    /// never considered initialized. This is useful for watches that are not expected to recover,
+    /// e.g. when the resource's CRD has been deleted.
+    ///
+    /// By default, watches are retried indefinitely.
+    pub fn with_watch_max_consecutive_errors(mut self, max: usize) -> Self {
+        self.watch_max_errors = Some(max);
+        self
+    }
+
+    /// Configures the runtime to stop retrying a watch when it fails with a permanent error
+    ///
+    /// Errors like authorization failures (e.g. due to misconfigured RBAC) and missing resources
+    /// (e.g. when a CRD is not installed) are unlikely to be resolved by retrying. When a watch
+    /// fails fast, its stream ends and the admin server's readiness endpoint reports that the
+    /// runtime is not ready. See [`errors::ErrorKind::is_permanent`].
+    ///
+    /// By default, watches are retried after permanent errors.
+    pub fn with_watch_fail_fast(mut self) -> Self {
+        self.watch_fail_fast = true;
+        self
+    }
+
+    /// Configures the runtime to wait for `delay` after shutdown is signaled before draining
+    ///
+    /// When shutdown is signaled, the admin server's readiness endpoint immediately reports that
+    /// the runtime is not ready. Watches and servers are only notified to drain after the delay
+    /// elapses, giving load balancers time to stop routing new traffic to the process.
+    ///
+    /// By default, draining starts immediately.
+    pub fn with_shutdown_drain_delay(mut self, delay: Duration) -> Self {
+        self.drain_delay = Some(delay);
+        self
+    }
+
+    /// Configures the runtime not to register `SIGINT` and `SIGTERM` handlers
+    ///
+    /// This is useful when the runtime is embedded in an application that handles signals
+    /// itself. Shutdown is instead initiated by the [`shutdown::Trigger`] returned by
+    /// [`Runtime::shutdown_trigger`].
+    pub fn without_signal_handling(mut self) -> Self {
+        self.without_signals = true;
+        self
+    }
+
+    /// Configures the runtime to stop advertising readiness when any thread or task panics
+    ///
+    /// When the runtime is started, a panic hook is installed that logs the panic and marks the
+    /// admin server as not ready, so that the process is restarted promptly if a critical task
+    /// dies. Readiness is not restored once a panic has occurred. The previously installed panic
+    /// hook is still invoked.
+    ///
+    /// Panic hooks are global to the process, so panics in unrelated threads also clear readiness.
+    /// The hook is installed once per process and only affects runtimes that are running.
+    pub fn with_unready_on_panic(mut self) -> Self {
+        self.unready_on_panic = true;
+        self
+    }
+
+    /// Configures the runtime to verify that the Kubernetes API server is reachable when it is
+    /// built
+    ///
+    /// Clients are constructed lazily, so connectivity and authentication problems are otherwise
+    /// only discovered when the first request is made. When enabled, the API server's version is
+    /// requested (i.e. `GET /version`) while building the runtime, and building fails with
+    /// [`BuildError::ClientCheck`] if the request fails.
+    pub fn with_client_check(mut self) -> Self {
+        self.check_client = true;
+        self
+    }
+
     /// Configures the runtime to record watch metrics with the given registry
     #[cfg(feature = "prometheus-client")]
     pub fn with_metrics(mut self, metrics: RuntimeMetrics) -> Self {
@@ -152,6 +301,40 @@ impl<S> Builder<S> {
         self
     }
 
+    /// Configures the runtime to report watches as not live when they have
+    /// been failing for longer than the given `timeout`
+    ///
+    /// A watch is considered to be failing when it has encountered errors
+    /// without producing an event for the `timeout` duration. While any watch
+    /// is failing, the admin server's liveness endpoint returns an error so
+    /// that a wedged controller may be restarted.
+    #[cfg(feature = "runtime-diagnostics")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "runtime", feature = "runtime-diagnostics")))
+    )]
+    pub fn with_watch_liveness_timeout(mut self, timeout: Duration) -> Self {
+        self.watch_liveness_timeout = Some(timeout);
+        self
+    }
+
+    /// Fails readiness while any watch exceeds an error budget
+    ///
+    /// A watch exceeds its budget when it encounters more than `max_errors`
+    /// errors within the most recent `window`. Unlike
+    /// [`Builder::with_watch_max_consecutive_errors`], the watch keeps running, and the
+    /// admin server's readiness endpoint recovers once the watch's error rate
+    /// falls back within the budget.
+    #[cfg(feature = "runtime-diagnostics")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "runtime", feature = "runtime-diagnostics")))
+    )]
+    pub fn with_watch_error_budget(mut self, max_errors: usize, window: Duration) -> Self {
+        self.watch_error_budget = Some((max_errors, window));
+        self
+    }
+
     #[inline]
     async fn build_inner<F>(
         self,
@@ -162,7 +345,18 @@ impl<S> Builder<S> {
     {
         self.log.unwrap_or_default().try_init()?;
         let client = mk_client(self.client.unwrap_or_default()).await?;
-        let (shutdown, shutdown_rx) = shutdown::sigint_or_sigterm()?;
+        if self.check_client {
+            let version = client
+                .apiserver_version()
+                .await
+                .map_err(BuildError::ClientCheck)?;
+            tracing::debug!(version = %version.git_version, "Connected to the Kubernetes API server");
+        }
+        let (shutdown, shutdown_rx) = if self.without_signals {
+            shutdown::without_signals()
+        } else {
+            shutdown::sigint_or_sigterm()?
+        };
         let admin = self.admin.bind()?;
         Ok(Runtime {
             client,
@@ -170,11 +364,22 @@ impl<S> Builder<S> {
             shutdown,
             admin,
             error_delay: self.error_delay.unwrap_or(Self::DEFAULT_ERROR_DELAY),
+            error_backoff: self.error_backoff,
+            watch_max_errors: self.watch_max_errors,
+            watch_fail_fast: self.watch_fail_fast,
+            drain_delay: self.drain_delay.unwrap_or_default(),
+            unready_on_panic: self.unready_on_panic,
             initialized: Initialized::default(),
             // Server must be built by `Builder::build`
             server: self.server,
+            #[cfg(feature = "server")]
+            spawned_server_addr: None,
             #[cfg(feature = "prometheus-client")]
             metrics: self.metrics,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_liveness_timeout: self.watch_liveness_timeout,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_error_budget: self.watch_error_budget,
         })
     }
 }
@@ -189,8 +394,19 @@ impl Builder<NoServer> {
             admin: self.admin,
             client: self.client,
             error_delay: self.error_delay,
+            error_backoff: self.error_backoff,
+            watch_max_errors: self.watch_max_errors,
+            watch_fail_fast: self.watch_fail_fast,
+            drain_delay: self.drain_delay,
+            without_signals: self.without_signals,
+            unready_on_panic: self.unready_on_panic,
+            check_client: self.check_client,
             log: self.log,
             metrics: self.metrics,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_liveness_timeout: self.watch_liveness_timeout,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_error_budget: self.watch_error_budget,
         }
     }
 
@@ -205,8 +421,19 @@ impl Builder<NoServer> {
             admin: self.admin,
             client: self.client,
             error_delay: self.error_delay,
+            error_backoff: self.error_backoff,
+            watch_max_errors: self.watch_max_errors,
+            watch_fail_fast: self.watch_fail_fast,
+            drain_delay: self.drain_delay,
+            without_signals: self.without_signals,
+            unready_on_panic: self.unready_on_panic,
+            check_client: self.check_client,
             log: self.log,
             metrics: self.metrics,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_liveness_timeout: self.watch_liveness_timeout,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_error_budget: self.watch_error_budget,
         }
     }
 }
@@ -265,12 +492,37 @@ impl<S> Runtime<S> {
         self.client.clone()
     }
 
+    /// Returns the fixed delay applied after a watch stream fails
+    ///
+    /// This is the value set by [`Builder::with_fixed_delay_on_error`], or the default delay
+    /// when none was configured. It is ignored by watches when an error backoff is configured.
+    #[inline]
+    pub fn error_delay(&self) -> Duration {
+        self.error_delay
+    }
+
     /// Creates a new initization handle used to block readiness
     #[inline]
     pub fn initialized_handle(&mut self) -> initialized::Handle {
         self.initialized.add_handle()
     }
 
+    /// Creates a new named initization handle used to block readiness
+    ///
+    /// While the handle is held, its name is listed in the admin server's `/ready` responses.
+    #[inline]
+    pub fn named_initialized_handle(&mut self, name: impl ToString) -> initialized::Handle {
+        self.initialized.add_named_handle(name)
+    }
+
+    /// Returns the bound local address of the admin server
+    ///
+    /// This is useful to discover the admin server's port when it is bound to an ephemeral
+    /// port (i.e. `:0`).
+    pub fn admin_addr(&self) -> std::net::SocketAddr {
+        self.admin.local_addr()
+    }
+
     /// Obtains a handle to he admin server's readiness state
     #[inline]
     pub fn readiness(&self) -> Readiness {
@@ -283,11 +535,48 @@ impl<S> Runtime<S> {
         self.shutdown_rx.clone()
     }
 
+    /// Obtains a handle that initiates shutdown without a signal
+    ///
+    /// When the runtime is built with [`Builder::without_signal_handling`], this is the only way
+    /// to shut the runtime down. Triggering a second time before shutdown completes aborts it.
+    #[inline]
+    pub fn shutdown_trigger(&self) -> shutdown::Trigger {
+        self.shutdown.trigger()
+    }
+
+    /// Obtains a handle that observes whether the runtime's shutdown completed gracefully or was
+    /// aborted
+    ///
+    /// This allows application tasks to, for example, skip cleanup work when shutdown is forced.
+    #[inline]
+    pub fn shutdown_outcome(&self) -> shutdown::OutcomeWatch {
+        self.shutdown.outcome()
+    }
+
     /// Wraps the given `Future` or `Stream` so that it completes when the runtime is shutdown
     pub fn cancel_on_shutdown<T>(&self, inner: T) -> shutdown::CancelOnShutdown<T> {
         shutdown::CancelOnShutdown::new(self.shutdown_rx.clone(), inner)
     }
 
+    /// Spawns a task that the runtime waits for when it is shutdown
+    ///
+    /// The task holds a shutdown handle until it completes, so graceful shutdown does not complete
+    /// until the task does. Long-running tasks should stop when shutdown is signaled (e.g. via
+    /// [`Runtime::cancel_on_shutdown`]); otherwise shutdown only completes when a second signal
+    /// aborts it.
+    pub fn spawn_task<F>(&self, task: F) -> tokio::task::JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let shutdown = self.shutdown_rx.clone();
+        tokio::spawn(async move {
+            let out = task.await;
+            drop(shutdown);
+            out
+        })
+    }
+
     #[cfg(feature = "requeue")]
     #[cfg_attr(docsrs, doc(cfg(all(features = "runtime", feature = "requeue"))))]
     /// Wraps the given `Future` or `Stream` so that it completes when the runtime is shutdown
@@ -306,6 +595,101 @@ impl<S> Runtime<S> {
         (tx, rx)
     }
 
+    #[cfg(feature = "requeue")]
+    #[cfg_attr(docsrs, doc(cfg(all(features = "runtime", feature = "requeue"))))]
+    /// Creates a requeue channel that is closed when the runtime is shutdown
+    ///
+    /// Like [`Runtime::requeue`], the receiver completes when the runtime is shutdown. Additionally,
+    /// the channel is closed so that producers observe [`Sender::closed`] and stop enqueuing work.
+    ///
+    /// [`Sender::closed`]: crate::requeue::Sender::closed
+    pub fn requeue_closed_on_shutdown<T>(
+        &self,
+        capacity: usize,
+    ) -> (
+        crate::requeue::Sender<T>,
+        shutdown::CancelOnShutdown<crate::requeue::Receiver<T>>,
+    )
+    where
+        T: Eq + std::hash::Hash,
+    {
+        let (tx, rx) = crate::requeue::channel(capacity);
+        let signaled = self.shutdown_rx.clone();
+        let rx = rx.close_when(async move {
+            let _ = signaled.signaled().await;
+        });
+        let rx = shutdown::CancelOnShutdown::new(self.shutdown_rx.clone(), rx);
+        (tx, rx)
+    }
+
+    #[cfg(feature = "runtime-diagnostics")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(features = "runtime", feature = "runtime-diagnostics")))
+    )]
+    /// Serves the contents of a reflector [`Store`] on the admin server at `path`
+    ///
+    /// Responses list each cached resource's name, namespace, UID, generation, and resource
+    /// version (see [`admin::diagnostics::Resource`]) as a JSON array sorted by namespace and
+    /// name. This may be compared against the live cluster state to debug cache divergence. Like
+    /// the `/kubert.json` endpoint, the contents are only served to loopback clients.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if called with the path of a built-in endpoint (e.g. `/ready` or
+    /// `/kubert.json`) or of a handler configured on the admin server.
+    pub fn register_store<T>(&self, path: impl ToString, store: &Store<T>)
+    where
+        T: Resource + Clone + Send + Sync + 'static,
+        T::DynamicType: Clone + Eq + Hash + Send + Sync,
+    {
+        self.admin.register_store(path.to_string(), store.clone());
+    }
+
+    /// Waits for a reflector [`Store`] to contain the object referenced by `key`
+    ///
+    /// This is useful for controllers that depend on a bootstrap resource (e.g. a configuration
+    /// resource) before reconciling. The runtime is not considered initialized until the object
+    /// is found. The store is checked once it has been populated by its watch and then
+    /// periodically until the object is found.
+    ///
+    /// If the object is not found within `timeout`, an error is returned and the runtime is never
+    /// considered initialized.
+    pub fn await_store_contains<T>(
+        &mut self,
+        store: Store<T>,
+        key: reflector::ObjectRef<T>,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Arc<T>, tokio::time::error::Elapsed>> + Send + 'static
+    where
+        T: Resource + Clone + Send + Sync + 'static,
+        T::DynamicType: Clone + Eq + Hash + Send + Sync,
+    {
+        const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+        let handle = self.named_initialized_handle(format!("store contains {key}"));
+        async move {
+            let found = tokio::time::timeout(timeout, async {
+                // If the store's writer is dropped, the store is never populated, so we wait for
+                // the timeout to elapse.
+                let _ = store.wait_until_ready().await;
+                loop {
+                    if let Some(obj) = store.get(&key) {
+                        return obj;
+                    }
+                    tracing::debug!(%key, "Waiting for store to contain object");
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            })
+            .await;
+            match found {
+                Ok(_) => drop(handle),
+                Err(_) => handle.forget(),
+            }
+            found
+        }
+    }
+
     #[cfg(feature = "lease")]
     #[cfg_attr(docsrs, doc(cfg(all(features = "runtime", feature = "lease"))))]
     /// Initializes and spawns a lease manager.
@@ -316,26 +700,47 @@ impl<S> Runtime<S> {
     pub async fn spawn_lease(
         &self,
         params: lease::LeaseParams,
+    ) -> Result<lease::Spawned, lease::Error> {
+        self.spawn_lease_with_component(None, params).await
+    }
+
+    #[cfg(feature = "lease")]
+    #[cfg_attr(docsrs, doc(cfg(all(features = "runtime", feature = "lease"))))]
+    /// Initializes and spawns a lease manager used by `component`
+    ///
+    /// The component name is used to distinguish leases in the `/kubert.json` and `/leases.json`
+    /// admin endpoints when the **runtime-diagnostics** feature is enabled. These endpoints may
+    /// be filtered by component with the `component` query parameter.
+    ///
+    /// See [`Runtime::spawn_lease`] for more details.
+    pub async fn spawn_named_lease(
+        &self,
+        component: impl ToString,
+        params: lease::LeaseParams,
+    ) -> Result<lease::Spawned, lease::Error> {
+        self.spawn_lease_with_component(Some(component.to_string()), params)
+            .await
+    }
+
+    #[cfg(feature = "lease")]
+    #[cfg_attr(not(feature = "runtime-diagnostics"), allow(unused_variables))]
+    async fn spawn_lease_with_component(
+        &self,
+        component: Option<String>,
+        params: lease::LeaseParams,
     ) -> Result<lease::Spawned, lease::Error> {
         #[cfg(feature = "runtime-diagnostics")]
-        let diagnostics = self.admin.diagnostics().register_lease(&params);
+        let diagnostics = self.admin.diagnostics().register_lease(component, &params);
+        let manager = lease::LeaseManager::init_with_params(self.client.clone(), &params).await?;
+        #[cfg(feature = "runtime-diagnostics")]
+        let manager = manager.with_diagnostics(diagnostics);
+
         let lease::LeaseParams {
-            name,
-            namespace,
-            field_manager,
             claimant,
             lease_duration,
             renew_grace_period,
+            ..
         } = params;
-
-        let api = lease::Api::namespaced(self.client.clone(), &namespace);
-        let manager = lease::LeaseManager::init(api, name).await?;
-        let manager = field_manager
-            .into_iter()
-            .fold(manager, |m, fm| m.with_field_manager(fm));
-        #[cfg(feature = "runtime-diagnostics")]
-        let manager = manager.with_diagnostics(diagnostics);
-
         let params = lease::ClaimParams {
             lease_duration,
             renew_grace_period,
@@ -343,6 +748,37 @@ impl<S> Runtime<S> {
         manager.spawn(claimant, params).await
     }
 
+    /// Creates an interval that ticks every `period`, e.g. to periodically resync a controller's
+    /// state independently of watch events
+    ///
+    /// The first tick completes immediately. If ticks are missed (e.g. because the consumer was
+    /// busy), the next tick is delayed so that ticks remain `period` apart. The interval stops
+    /// ticking when the runtime is shutdown.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `period` is zero.
+    pub fn interval(&self, period: Duration) -> shutdown::Interval {
+        self.interval_with_jitter(period, 0.0)
+    }
+
+    /// Creates an interval that ticks every `period`, offsetting the first tick by a random
+    /// fraction of the period
+    ///
+    /// The jitter factor, between 0 and 1, bounds the offset: e.g., with a factor of `0.1`, the
+    /// first tick completes within 10% of `period`. Subsequent ticks remain `period` apart. This
+    /// avoids synchronizing the ticks of many controller replicas.
+    ///
+    /// See [`Runtime::interval`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `period` is zero.
+    pub fn interval_with_jitter(&self, period: Duration, jitter: f64) -> shutdown::Interval {
+        assert!(!period.is_zero(), "interval period must be non-zero");
+        shutdown::Interval::new(self.shutdown_rx.clone(), jittered_interval(period, jitter))
+    }
+
     /// Creates a watch with the given [`Api`]
     ///
     /// If the underlying stream encounters errors, the request is retried (potentially after a
@@ -352,6 +788,12 @@ impl<S> Runtime<S> {
     /// event.
     ///
     /// The return stream terminates when the runtime receives a shutdown signal.
+    ///
+    /// The initial list is configured by the `watcher_config`. For example, large clusters may
+    /// benefit from smaller pages (via [`watcher::Config::page_size`]) and from serving lists from
+    /// the API server's cache (via [`watcher::Config::any_semantic`]). When the
+    /// **runtime-diagnostics** feature is enabled, these parameters are reported by the
+    /// `/kubert.json` admin endpoint.
     pub fn watch<T>(
         &mut self,
         api: Api<T>,
@@ -361,10 +803,105 @@ impl<S> Runtime<S> {
         T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
         T::DynamicType: Default,
     {
-        let watch = self.watch_inner(api, watcher_config);
-        let successful = errors::LogAndSleep::fixed_delay(self.error_delay, watch);
-        let initialized = self.initialized.add_handle().release_on_ready(successful);
-        shutdown::CancelOnShutdown::new(self.shutdown_rx.clone(), initialized)
+        self.watch_with_name(None, api, &Default::default(), watcher_config)
+    }
+
+    /// Creates a watch with the given [`Api`], identified by `name`
+    ///
+    /// The name is used to distinguish watches (e.g. of the same kind for different purposes) in
+    /// the `/kubert.json` admin endpoint when the **runtime-diagnostics** feature is enabled.
+    ///
+    /// See [`Runtime::watch`] for more details.
+    pub fn watch_named<T>(
+        &mut self,
+        name: impl ToString,
+        api: Api<T>,
+        watcher_config: watcher::Config,
+    ) -> impl Stream<Item = watcher::Event<T>>
+    where
+        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
+        T::DynamicType: Default,
+    {
+        self.watch_with_name(
+            Some(name.to_string()),
+            api,
+            &Default::default(),
+            watcher_config,
+        )
+    }
+
+    /// Creates a watch with the given [`Api`] for resources whose types are only known at runtime
+    ///
+    /// This supports, for example, watching [`DynamicObject`](kube_core::DynamicObject)s for
+    /// custom resources found via the discovery API, where `dyntype` is the resource's
+    /// [`ApiResource`](kube_core::ApiResource). The watch is retried, instrumented, and
+    /// terminated on shutdown like any other watch.
+    ///
+    /// See [`Runtime::watch`] for more details.
+    pub fn watch_dynamic<T>(
+        &mut self,
+        api: Api<T>,
+        dyntype: T::DynamicType,
+        watcher_config: watcher::Config,
+    ) -> impl Stream<Item = watcher::Event<T>>
+    where
+        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
+    {
+        self.watch_with_name(None, api, &dyntype, watcher_config)
+    }
+
+    fn watch_with_name<T>(
+        &mut self,
+        name: Option<String>,
+        api: Api<T>,
+        dt: &T::DynamicType,
+        watcher_config: watcher::Config,
+    ) -> impl Stream<Item = watcher::Event<T>>
+    where
+        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
+    {
+        let label = watch_name(name.as_deref(), api.resource_url());
+        let initialized =
+            self.watch_initialized(name.as_deref(), api, dt, watcher_config, watcher::watcher);
+        shutdown::CancelOnShutdown::new(self.shutdown_rx.clone(), initialized).with_name(label)
+    }
+
+    /// Creates a watch that retries on errors and that must produce an event before the runtime
+    /// is considered initialized.
+    #[cfg_attr(
+        not(any(feature = "runtime-diagnostics", feature = "prometheus-client")),
+        allow(unused_variables)
+    )]
+    fn watch_initialized<K, T, W>(
+        &mut self,
+        name: Option<&str>,
+        api: Api<K>,
+        dt: &K::DynamicType,
+        watcher_config: watcher::Config,
+        mk_watch: impl FnOnce(Api<K>, watcher::Config) -> W,
+    ) -> impl Stream<Item = watcher::Event<T>>
+    where
+        K: Resource,
+        T: Resource<DynamicType = K::DynamicType> + Send + 'static,
+        W: Stream<Item = watcher::Result<watcher::Event<T>>> + Send + 'static,
+    {
+        #[cfg(feature = "runtime-diagnostics")]
+        let diagnostics = self.register_watch(name, &api, &watcher_config, dt);
+
+        let api_url = api.resource_url().to_string();
+        let watch = self.watch_inner(
+            mk_watch(api, watcher_config),
+            #[cfg(feature = "prometheus-client")]
+            dt,
+            #[cfg(feature = "runtime-diagnostics")]
+            diagnostics.clone(),
+        );
+        let successful = self.log_and_sleep(watch);
+        #[cfg(feature = "runtime-diagnostics")]
+        let successful = successful.on_backoff(move |delay| diagnostics.backoff(delay));
+        self.initialized
+            .add_named_handle(watch_name(name, &api_url))
+            .release_on_item(successful)
     }
 
     /// Creates a cluster-level watch on the default Kubernetes client
@@ -400,6 +937,48 @@ impl<S> Runtime<S> {
         self.watch(api, watcher_config)
     }
 
+    /// Creates a watch with the given [`Api`] that only receives the metadata of each object
+    ///
+    /// Object specs and statuses are never transferred or deserialized, which reduces memory
+    /// usage when only names, labels, annotations, etc. are needed for kinds with large objects
+    /// (e.g. `Pod`s or `Event`s).
+    ///
+    /// See [`Runtime::watch`] for more details.
+    pub fn watch_metadata<T>(
+        &mut self,
+        api: Api<T>,
+        watcher_config: watcher::Config,
+    ) -> impl Stream<Item = watcher::Event<PartialObjectMeta<T>>>
+    where
+        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
+        T::DynamicType: Default,
+    {
+        let label = watch_name(None, api.resource_url());
+        let initialized = self.watch_initialized(
+            None,
+            api,
+            &Default::default(),
+            watcher_config,
+            watcher::metadata_watcher,
+        );
+        shutdown::CancelOnShutdown::new(self.shutdown_rx.clone(), initialized).with_name(label)
+    }
+
+    /// Creates a cluster-level, metadata-only watch on the default Kubernetes client, configured
+    /// by [`metadata_scan_config`]
+    ///
+    /// See [`Runtime::watch_metadata`] for more details.
+    #[inline]
+    pub fn watch_metadata_all<T>(
+        &mut self,
+    ) -> impl Stream<Item = watcher::Event<PartialObjectMeta<T>>>
+    where
+        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
+        T::DynamicType: Default,
+    {
+        self.watch_metadata(Api::all(self.client()), metadata_scan_config())
+    }
+
     /// Creates a cached watch with the given [`Api`]
     ///
     /// The returned [`Store`] is updated as the returned stream is polled. If the underlying stream
@@ -418,18 +997,131 @@ impl<S> Runtime<S> {
         T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
         T::DynamicType: Clone + Default + Eq + Hash + Clone,
     {
-        let writer = reflector::store::Writer::<T>::default();
+        self.cache_with_name(None, api, Default::default(), watcher_config)
+    }
+
+    /// Creates a cached watch with the given [`Api`], identified by `name`
+    ///
+    /// The name is used to distinguish watches (e.g. of the same kind for different purposes) in
+    /// the `/kubert.json` admin endpoint when the **runtime-diagnostics** feature is enabled.
+    ///
+    /// See [`Runtime::cache`] for more details.
+    pub fn cache_named<T>(
+        &mut self,
+        name: impl ToString,
+        api: Api<T>,
+        watcher_config: watcher::Config,
+    ) -> (Store<T>, impl Stream<Item = watcher::Event<T>>)
+    where
+        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
+        T::DynamicType: Clone + Default + Eq + Hash + Clone,
+    {
+        self.cache_with_name(
+            Some(name.to_string()),
+            api,
+            Default::default(),
+            watcher_config,
+        )
+    }
+
+    /// Creates a cached watch with the given [`Api`] for resources whose types are only known at
+    /// runtime
+    ///
+    /// See [`Runtime::watch_dynamic`] and [`Runtime::cache`] for more details.
+    pub fn cache_dynamic<T>(
+        &mut self,
+        api: Api<T>,
+        dyntype: T::DynamicType,
+        watcher_config: watcher::Config,
+    ) -> (Store<T>, impl Stream<Item = watcher::Event<T>>)
+    where
+        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
+        T::DynamicType: Clone + Eq + Hash,
+    {
+        self.cache_with_name(None, api, dyntype, watcher_config)
+    }
+
+    fn cache_with_name<T>(
+        &mut self,
+        name: Option<String>,
+        api: Api<T>,
+        dt: T::DynamicType,
+        watcher_config: watcher::Config,
+    ) -> (Store<T>, impl Stream<Item = watcher::Event<T>>)
+    where
+        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
+        T::DynamicType: Clone + Eq + Hash,
+    {
+        let writer = reflector::store::Writer::<T>::new(dt.clone());
         let store = writer.as_reader();
 
-        let watch = self.watch_inner(api, watcher_config);
+        #[cfg(feature = "runtime-diagnostics")]
+        let diagnostics = self.register_watch(name.as_deref(), &api, &watcher_config, &dt);
+
+        let api_url = api.resource_url().to_string();
+        let watch = self.watch_inner(
+            watcher::watcher(api, watcher_config),
+            #[cfg(feature = "prometheus-client")]
+            &dt,
+            #[cfg(feature = "runtime-diagnostics")]
+            diagnostics.clone(),
+        );
         let cached = reflector::reflector(writer, watch);
-        let successful = errors::LogAndSleep::fixed_delay(self.error_delay, cached);
-        let initialized = self.initialized.add_handle().release_on_ready(successful);
-        let graceful = shutdown::CancelOnShutdown::new(self.shutdown_rx.clone(), initialized);
+        #[cfg(feature = "prometheus-client")]
+        let cached = metrics::ResourceWatchMetrics::instrument_store(
+            self.metrics.as_ref().map(|m| m.watch.clone()),
+            // Unnamed stores are identified by their resource URL.
+            name.as_deref().unwrap_or(&api_url),
+            &dt,
+            store.clone(),
+            cached,
+        );
+        let successful = self.log_and_sleep(cached);
+        #[cfg(feature = "runtime-diagnostics")]
+        let successful = successful.on_backoff(move |delay| diagnostics.backoff(delay));
+        let label = watch_name(name.as_deref(), &api_url);
+        let initialized = self
+            .initialized
+            .add_named_handle(label.clone())
+            .release_on_item(successful);
+        let graceful =
+            shutdown::CancelOnShutdown::new(self.shutdown_rx.clone(), initialized).with_name(label);
 
         (store, graceful)
     }
 
+    /// Creates a cached watch with the given [`Api`] that is not considered initialized until
+    /// `ready` holds for the returned [`Store`]
+    ///
+    /// The predicate is evaluated each time the returned stream produces an event. This is in
+    /// addition to the requirement that the watch returns at least one event, so that, for
+    /// example, the admin server's readiness endpoint only succeeds once the store contains a
+    /// specific object.
+    ///
+    /// See [`Runtime::cache`] for more details.
+    pub fn cache_ready_when<T, P>(
+        &mut self,
+        api: Api<T>,
+        watcher_config: watcher::Config,
+        ready: P,
+    ) -> (Store<T>, impl Stream<Item = watcher::Event<T>>)
+    where
+        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
+        T::DynamicType: Clone + Default + Eq + Hash + Clone,
+        P: Fn(&Store<T>) -> bool,
+    {
+        let name = format!("{} readiness", watch_name(None, api.resource_url()));
+        let (store, events) = self.cache(api, watcher_config);
+        let events = self
+            .initialized
+            .add_named_handle(name)
+            .release_when(events, {
+                let store = store.clone();
+                move || ready(&store)
+            });
+        (store, events)
+    }
+
     /// Creates a cached cluster-level watch on the default Kubernetes client
     ///
     /// See [`Runtime::cache`] for more details.
@@ -463,29 +1155,167 @@ impl<S> Runtime<S> {
         self.cache(api, watcher_config)
     }
 
-    fn watch_inner<T>(
+    /// Creates a cached watch that aggregates objects from several namespaces into a single
+    /// [`Store`]
+    ///
+    /// This is useful for building a view of resources across namespaces when the controller is
+    /// not authorized to watch resources at the cluster level. Each namespace is watched
+    /// independently on the default Kubernetes client and the resulting events are merged into
+    /// the returned stream. When a namespace's watch is (re)initialized, only the cached objects
+    /// in that namespace are replaced. The store becomes ready once every namespace has completed
+    /// its initial list.
+    ///
+    /// The runtime is not considered initialized until each namespace's watch returns at least
+    /// one event. When the **runtime-diagnostics** feature is enabled, each namespace's watch is
+    /// reported by the `/kubert.json` admin endpoint.
+    ///
+    /// See [`Runtime::cache`] for more details.
+    pub fn cache_namespaces<T>(
         &mut self,
-        api: Api<T>,
+        namespaces: impl IntoIterator<Item = impl ToString>,
         watcher_config: watcher::Config,
-    ) -> impl Stream<Item = watcher::Result<watcher::Event<T>>>
+    ) -> (Store<T>, impl Stream<Item = watcher::Event<T>>)
     where
-        T: Resource + DeserializeOwned + Clone + Debug + Send + 'static,
-        T::DynamicType: Default,
+        T: Resource<Scope = NamespaceResourceScope>,
+        T: DeserializeOwned + Clone + Debug + Send + 'static,
+        T::DynamicType: Clone + Default + Eq + Hash + Clone,
     {
-        #[cfg(feature = "runtime-diagnostics")]
-        let diagnostics = self
-            .admin
-            .diagnostics()
-            .register_watch(&api, watcher_config.label_selector.as_deref());
+        use futures_util::StreamExt;
+
+        let writer = reflector::store::Writer::<T>::default();
+        let store = writer.as_reader();
+
+        let namespaces = namespaces
+            .into_iter()
+            .map(|ns| ns.to_string())
+            .collect::<Vec<_>>();
+        let watches = namespaces
+            .iter()
+            .enumerate()
+            .map(|(idx, ns)| {
+                let api = Api::namespaced(self.client(), ns);
+                let watch = self.watch_initialized(
+                    None,
+                    api,
+                    &Default::default(),
+                    watcher_config.clone(),
+                    watcher::watcher,
+                );
+                Box::pin(watch.map(move |event| (idx, event)))
+            })
+            .collect::<Vec<_>>();
+
+        let name = format!(
+            "{} in namespaces {}",
+            T::kind(&Default::default()),
+            namespaces.join(",")
+        );
+        let label = format!("watch {name}");
+        let mut writer = namespaces::NamespacesWriter::new(writer, namespaces);
+        let cached = futures_util::stream::select_all(watches).map(move |(idx, event)| {
+            writer.apply(idx, &event);
+            event
+        });
+        #[cfg(feature = "prometheus-client")]
+        let cached = metrics::ResourceWatchMetrics::instrument_store(
+            self.metrics.as_ref().map(|m| m.watch.clone()),
+            &name,
+            &Default::default(),
+            store.clone(),
+            cached,
+        );
+        let graceful =
+            shutdown::CancelOnShutdown::new(self.shutdown_rx.clone(), cached).with_name(label);
+
+        (store, graceful)
+    }
 
-        let watch = watcher::watcher(api, watcher_config);
+    #[cfg(feature = "runtime-diagnostics")]
+    fn register_watch<T>(
+        &self,
+        name: Option<&str>,
+        api: &Api<T>,
+        watcher_config: &watcher::Config,
+        dt: &T::DynamicType,
+    ) -> admin::WatchDiagnostics
+    where
+        T: Resource,
+    {
+        let check_name = match name {
+            Some(name) => format!("watch {name} ({})", api.resource_url()),
+            None => format!("watch {}", api.resource_url()),
+        };
+        let diagnostics = self.admin.diagnostics().register_watch(
+            name.map(str::to_string),
+            api,
+            watcher_config,
+            dt,
+        );
+        if let Some(timeout) = self.watch_liveness_timeout {
+            diagnostics.add_liveness_check(&self.admin.liveness(), &check_name, timeout);
+        }
+        if let Some((max_errors, window)) = self.watch_error_budget {
+            diagnostics.add_readiness_check(
+                &self.admin.readiness(),
+                &check_name,
+                max_errors,
+                window,
+            );
+        }
+        diagnostics
+    }
+
+    /// Handles a watch's errors by logging them and retrying after a delay
+    ///
+    /// If the watch fails too many times consecutively (or for longer than the backoff's maximum
+    /// elapsed time), or if it fails fast on a permanent error, the stream ends and the admin
+    /// server is marked as not ready.
+    fn log_and_sleep<W>(&self, stream: W) -> errors::LogAndSleep<W, errors::WatchErrors> {
+        let mut successful = match self.error_backoff.as_ref() {
+            Some(backoff) => errors::LogAndSleep::backoff(backoff, stream),
+            None => errors::LogAndSleep::fixed_delay(self.error_delay, stream),
+        }
+        .classify_with(errors::WatchErrors);
+        if self.watch_fail_fast {
+            let ready = self.admin.readiness();
+            successful = successful.fail_fast().on_fail_fast(move |kind, error| {
+                tracing::error!(
+                    %error,
+                    error_kind = %kind,
+                    "Watch failed permanently; marking the runtime as not ready"
+                );
+                ready.set(false);
+            });
+        }
+        let Some(max) = self.watch_max_errors else {
+            return successful;
+        };
+        let ready = self.admin.readiness();
+        successful
+            .max_consecutive_errors(max)
+            .on_exhausted(move || {
+                tracing::error!("Watch failed too many times; marking the runtime as not ready");
+                ready.set(false);
+            })
+    }
 
+    /// Instruments a watch with the runtime's diagnostics and metrics
+    fn watch_inner<T>(
+        &mut self,
+        watch: impl Stream<Item = watcher::Result<watcher::Event<T>>> + Send,
+        #[cfg(feature = "prometheus-client")] dt: &T::DynamicType,
+        #[cfg(feature = "runtime-diagnostics")] diagnostics: admin::WatchDiagnostics,
+    ) -> impl Stream<Item = watcher::Result<watcher::Event<T>>>
+    where
+        T: Resource + Send,
+    {
         #[cfg(feature = "runtime-diagnostics")]
         let watch = futures_util::StreamExt::inspect(watch, move |ev| diagnostics.inspect(ev));
 
         #[cfg(feature = "prometheus-client")]
         let watch = metrics::ResourceWatchMetrics::instrument_watch(
             self.metrics.as_ref().map(|m| m.watch.clone()),
+            dt,
             watch,
         );
 
@@ -500,28 +1330,65 @@ impl<S> Runtime<S> {
         let server = bind(self.server).await?;
         Ok(Runtime {
             server,
+            spawned_server_addr: self.spawned_server_addr,
             admin: self.admin,
             client: self.client,
             error_delay: self.error_delay,
+            error_backoff: self.error_backoff,
+            watch_max_errors: self.watch_max_errors,
+            watch_fail_fast: self.watch_fail_fast,
+            drain_delay: self.drain_delay,
+            unready_on_panic: self.unready_on_panic,
             initialized: self.initialized,
             shutdown_rx: self.shutdown_rx,
             shutdown: self.shutdown,
             metrics: self.metrics,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_liveness_timeout: self.watch_liveness_timeout,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_error_budget: self.watch_error_budget,
         })
     }
 
+    /// Returns a function that configures a bound server to record the runtime's metrics
+    #[cfg(feature = "server")]
+    fn instrument_server(&self) -> impl FnOnce(server::Bound) -> server::Bound {
+        #[cfg(feature = "prometheus-client")]
+        let metrics = self.metrics.as_ref().map(|m| m.server.clone());
+        move |srv| {
+            #[cfg(feature = "prometheus-client")]
+            if let Some(metrics) = metrics {
+                return srv.with_metrics(metrics);
+            }
+            srv
+        }
+    }
+
     #[cfg(feature = "server")]
-    fn spawn_server_inner(self, spawn: impl FnOnce(S)) -> Runtime<NoServer> {
-        spawn(self.server);
+    fn spawn_server_inner(
+        self,
+        spawn: impl FnOnce(S) -> Option<server::SpawnedServer>,
+    ) -> Runtime<NoServer> {
+        let spawned = spawn(self.server);
         Runtime {
             server: NoServer(()),
+            spawned_server_addr: spawned.as_ref().map(|s| s.local_addr()),
             admin: self.admin,
             client: self.client,
             error_delay: self.error_delay,
+            error_backoff: self.error_backoff,
+            watch_max_errors: self.watch_max_errors,
+            watch_fail_fast: self.watch_fail_fast,
+            drain_delay: self.drain_delay,
+            unready_on_panic: self.unready_on_panic,
             initialized: self.initialized,
             shutdown_rx: self.shutdown_rx,
             shutdown: self.shutdown,
             metrics: self.metrics,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_liveness_timeout: self.watch_liveness_timeout,
+            #[cfg(feature = "runtime-diagnostics")]
+            watch_error_budget: self.watch_error_budget,
         }
     }
 }
@@ -533,9 +1400,22 @@ impl Runtime<server::Bound> {
         self.server.local_addr()
     }
 
+    /// Customizes how the HTTPS server serves each connection
+    ///
+    /// See [`server::Bound::with_conn_builder`].
+    pub fn with_server_conn_builder(
+        mut self,
+        configure: impl Fn(&mut server::ConnBuilder) + Send + Sync + 'static,
+    ) -> Self {
+        self.server = self.server.with_conn_builder(configure);
+        self
+    }
+
     /// Spawns the HTTPS server with the given `service`. A runtime handle without the bound server
     /// configuration is returned.
     ///
+    /// The server's local address remains available via [`Runtime::spawned_server_addr`].
+    ///
     /// The server shuts down gracefully when the runtime is shutdown.
     pub fn spawn_server<S, B>(self, service: S) -> Runtime<NoServer>
     where
@@ -550,9 +1430,27 @@ impl Runtime<server::Bound> {
         B::Error: std::error::Error + Send + Sync,
     {
         let shutdown = self.shutdown_rx.clone();
-        self.spawn_server_inner(move |s| {
-            s.spawn(service, shutdown);
-        })
+        let instrument = self.instrument_server();
+        self.spawn_server_inner(move |s| Some(instrument(s).spawn(service, shutdown)))
+    }
+
+    /// Spawns the HTTPS server with the given `service` and then runs the runtime until it is
+    /// shutdown
+    ///
+    /// This is equivalent to calling [`Runtime::spawn_server`] followed by [`Runtime::run`].
+    pub async fn run_with_server<S, B>(self, service: S) -> Result<(), shutdown::Aborted>
+    where
+        S: Service<hyper::Request<hyper::body::Incoming>, Response = hyper::Response<B>>
+            + Clone
+            + Send
+            + 'static,
+        S::Error: std::error::Error + Send + Sync,
+        S::Future: Send,
+        B: hyper::body::Body + Send + 'static,
+        B::Data: Send,
+        B::Error: std::error::Error + Send + Sync,
+    {
+        self.spawn_server(service).run().await
     }
 }
 
@@ -563,9 +1461,22 @@ impl Runtime<Option<server::Bound>> {
         self.server.as_ref().map(|s| s.local_addr())
     }
 
+    /// Customizes how the HTTPS server, if bound, serves each connection
+    ///
+    /// See [`server::Bound::with_conn_builder`].
+    pub fn with_server_conn_builder(
+        mut self,
+        configure: impl Fn(&mut server::ConnBuilder) + Send + Sync + 'static,
+    ) -> Self {
+        self.server = self.server.map(|s| s.with_conn_builder(configure));
+        self
+    }
+
     /// Spawns the HTTPS server, if bound, with the given `service`. A runtime handle without the
     /// bound server configuration is returned.
     ///
+    /// The server's local address remains available via [`Runtime::spawned_server_addr`].
+    ///
     /// The server shuts down gracefully when the runtime is shutdown.
     pub fn spawn_server<S, B, F>(self, mk: F) -> Runtime<NoServer>
     where
@@ -581,15 +1492,44 @@ impl Runtime<Option<server::Bound>> {
         B::Error: std::error::Error + Send + Sync,
     {
         let shutdown = self.shutdown_rx.clone();
+        let instrument = self.instrument_server();
         self.spawn_server_inner(move |s| match s {
-            Some(s) => {
-                s.spawn(mk(), shutdown);
-            }
+            Some(s) => Some(instrument(s).spawn(mk(), shutdown)),
             None => {
                 tracing::debug!("No server is configured");
+                None
             }
         })
     }
+
+    /// Spawns the HTTPS server, if bound, with the given `service` and then runs the runtime until
+    /// it is shutdown
+    ///
+    /// This is equivalent to calling [`Runtime::spawn_server`] followed by [`Runtime::run`].
+    pub async fn run_with_server<S, B, F>(self, mk: F) -> Result<(), shutdown::Aborted>
+    where
+        F: FnOnce() -> S,
+        S: Service<hyper::Request<hyper::body::Incoming>, Response = hyper::Response<B>>
+            + Clone
+            + Send
+            + 'static,
+        S::Error: std::error::Error + Send + Sync,
+        S::Future: Send,
+        B: hyper::body::Body + Send + 'static,
+        B::Data: Send,
+        B::Error: std::error::Error + Send + Sync,
+    {
+        self.spawn_server(mk).run().await
+    }
+}
+
+#[cfg(feature = "server")]
+impl Runtime<NoServer> {
+    /// Returns the bound local address of the HTTPS server, if one was spawned
+    /// by `spawn_server`
+    pub fn spawned_server_addr(&self) -> Option<std::net::SocketAddr> {
+        self.spawned_server_addr
+    }
 }
 
 impl Runtime<NoServer> {
@@ -605,33 +1545,88 @@ impl Runtime<NoServer> {
     ///
     /// The admin server's readiness endpoint returns success only once all watches (and other
     /// initalized components) have become ready and then returns an error after shutdown is
-    /// initiated.
+    /// initiated. Draining may be delayed after shutdown is initiated (see
+    /// [`Builder::with_shutdown_drain_delay`]).
     pub async fn run(self) -> Result<(), shutdown::Aborted> {
+        let (_admin, run) = self.start();
+        run.await
+    }
+
+    /// Spawns the admin server, returning its handle and a future that runs the runtime until
+    /// it is shutdown
+    ///
+    /// This behaves like [`Runtime::run`], except that the admin [`Server`](admin::Server) is
+    /// returned so that callers may observe its local address or await its task.
+    pub fn start(
+        self,
+    ) -> (
+        admin::Server,
+        impl Future<Output = Result<(), shutdown::Aborted>> + Send + 'static,
+    ) {
         let Self {
             admin,
             initialized,
             shutdown,
             shutdown_rx,
+            drain_delay,
+            unready_on_panic,
             ..
         } = self;
 
-        let admin = admin.spawn();
+        let server = admin.spawn();
+
+        // The runtime stops observing panics once it completes.
+        let panics = unready_on_panic.then(|| panics::register(server.readiness()));
+
+        // List the initialization handles that are still held when the runtime is not ready.
+        let pending = initialized.pending();
+        server.readiness().report_pending(move || pending.names());
 
         // Set the admin readiness to succeed once all initilization handles have been released.
-        let ready = admin.readiness();
-        tokio::spawn(async move {
-            initialized.initialized().await;
-            ready.set(true);
-            tracing::debug!("initialized");
+        let ready = server.readiness();
+        let init = tokio::spawn({
+            let ready = ready.clone();
+            let panics = panics.clone();
+            async move {
+                initialized.initialized().await;
+                if panics.as_ref().is_some_and(panics::Registration::panicked) {
+                    tracing::debug!("initialized after a panic; not ready");
+                } else {
+                    ready.set(true);
+                    tracing::debug!("initialized");
+                }
 
-            drop(shutdown_rx.signaled().await);
+                // Hold the shutdown watch until this task is aborted when shutdown is signaled.
+                drop(shutdown_rx.signaled().await);
+            }
+        });
+
+        // When a shutdown signal is received, stop advertising readiness before draining so that
+        // load balancers may stop sending new traffic.
+        let before_drain = async move {
+            init.abort();
             ready.set(false);
             tracing::debug!("shutdown");
-        });
+            if !drain_delay.is_zero() {
+                tracing::debug!(delay = ?drain_delay, "Waiting to drain");
+                tokio::time::sleep(drain_delay).await;
+            }
+        };
 
-        shutdown.signaled().await?;
+        let run = async move {
+            let _panics = panics;
+            shutdown.signaled_with(before_drain).await?;
+            Ok(())
+        };
+        (server, run)
+    }
+}
 
-        Ok(())
+/// Describes a watch in logs and in the list of initialization handles that block readiness
+fn watch_name(name: Option<&str>, resource_url: &str) -> String {
+    match name {
+        Some(name) => format!("watch {name}"),
+        None => format!("watch {resource_url}"),
     }
 }
 
@@ -642,13 +1637,14 @@ impl Default for LogSettings {
         Self {
             filter: LogFilter::from_default_env(),
             format: LogFormat::default(),
+            options: LogOptions::default(),
         }
     }
 }
 
 impl LogSettings {
     fn try_init(self) -> Result<(), LogInitError> {
-        self.format.try_init(self.filter)
+        self.format.try_init_with(self.filter, self.options)
     }
 }
 
@@ -660,6 +1656,53 @@ impl RuntimeMetrics {
     pub fn register(registry: &mut prometheus_client::registry::Registry) -> Self {
         let watch =
             metrics::ResourceWatchMetrics::register(registry.sub_registry_with_prefix("watch"));
-        Self { watch }
+        #[cfg(feature = "server")]
+        let server = server::ServerMetrics::register(registry.sub_registry_with_prefix("server"));
+        Self {
+            watch,
+            #[cfg(feature = "server")]
+            server,
+        }
+    }
+}
+
+/// Creates an interval that ticks every `period`, with its first tick offset by a delay drawn
+/// uniformly from `[0, period * jitter)`.
+fn jittered_interval(period: Duration, jitter: f64) -> tokio::time::Interval {
+    let offset = period.mul_f64(jitter.clamp(0.0, 1.0) * fastrand::f64());
+    let start = tokio::time::Instant::now() + offset;
+    let mut interval = tokio::time::interval_at(start, period);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    interval
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn interval_ticks_within_jitter() {
+        let period = Duration::from_secs(10);
+        for _ in 0..100 {
+            let start = tokio::time::Instant::now();
+            let mut interval = jittered_interval(period, 0.1);
+            let first = interval.tick().await;
+            assert!(
+                first - start < Duration::from_secs(1),
+                "{:?}",
+                first - start
+            );
+
+            // Subsequent ticks are not jittered.
+            assert_eq!(interval.tick().await - first, period);
+            assert_eq!(interval.tick().await - first, period * 2);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn interval_without_jitter_ticks_immediately() {
+        let start = tokio::time::Instant::now();
+        let mut interval = jittered_interval(Duration::from_secs(10), 0.0);
+        assert_eq!(interval.tick().await, start);
     }
 }
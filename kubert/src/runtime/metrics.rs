@@ -1,19 +1,32 @@
 use futures_core::Stream;
 use futures_util::StreamExt;
 use kube_core::Resource;
-use kube_runtime::watcher;
+use kube_runtime::{reflector::Store, watcher};
 use prometheus_client::{
     encoding::{EncodeLabelSet, EncodeLabelValue},
-    metrics::{counter::Counter, family::Family},
+    metrics::{counter::Counter, family::Family, gauge::Gauge},
     registry::Registry,
 };
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    sync::atomic::AtomicU64,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 /// Metrics for tracking resource watch events.
 #[derive(Clone, Debug)]
 pub(super) struct ResourceWatchMetrics {
     watch_events: Family<EventLabels, Counter>,
     watch_errors: Family<ErrorLabels, Counter>,
+    store_objects: Family<StoreLabels, Gauge>,
+    store_last_event: Family<StoreLabels, Gauge<f64, AtomicU64>>,
+}
+
+/// An item produced by a watch, which may or may not have succeeded
+pub(crate) trait WatchItem {
+    /// Returns true if the item updates the state of a store, i.e. it is an `Apply` event or
+    /// completes an initial list.
+    fn is_update(&self) -> bool;
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
@@ -32,6 +45,14 @@ struct ErrorLabels {
     error: &'static str,
 }
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct StoreLabels {
+    kind: String,
+    group: String,
+    version: String,
+    name: String,
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelValue)]
 enum EventOp {
     Apply,
@@ -56,9 +77,25 @@ impl ResourceWatchMetrics {
             watch_errors.clone(),
         );
 
+        let store_objects = Family::default();
+        registry.register(
+            "store_objects",
+            "Number of objects in a cached watch's store",
+            store_objects.clone(),
+        );
+
+        let store_last_event = Family::default();
+        registry.register(
+            "store_last_event_timestamp_seconds",
+            "Time, in seconds since the Unix epoch, at which a cached watch last received an apply event or completed an initial list",
+            store_last_event.clone(),
+        );
+
         Self {
             watch_events,
             watch_errors,
+            store_objects,
+            store_last_event,
         }
     }
 }
@@ -66,16 +103,15 @@ impl ResourceWatchMetrics {
 impl ResourceWatchMetrics {
     pub(crate) fn instrument_watch<T, S: Stream<Item = watcher::Result<watcher::Event<T>>> + Send>(
         metrics: Option<Self>,
+        dt: &T::DynamicType,
         watch: S,
     ) -> impl Stream<Item = watcher::Result<watcher::Event<T>>> + Send
     where
         T: Resource + Send,
-        T::DynamicType: Default,
     {
-        let dt = Default::default();
-        let kind = T::kind(&dt).into_owned();
-        let group = T::group(&dt).into_owned();
-        let version = T::version(&dt).into_owned();
+        let kind = T::kind(dt).into_owned();
+        let group = T::group(dt).into_owned();
+        let version = T::version(dt).into_owned();
         let apply_labels = EventLabels {
             kind,
             group,
@@ -128,4 +164,65 @@ impl ResourceWatchMetrics {
             event
         })
     }
+
+    /// Updates a gauge with the number of objects in `store` as the cached watch is processed.
+    ///
+    /// The time of the last update to the store is also recorded so that watches that stall
+    /// without failing may be detected (e.g. with `time() - store_last_event_timestamp_seconds`).
+    ///
+    /// The `name` label must uniquely identify the store, since stores of the same kind would
+    /// otherwise update the same gauges.
+    pub(crate) fn instrument_store<T, S>(
+        metrics: Option<Self>,
+        name: &str,
+        dt: &T::DynamicType,
+        store: Store<T>,
+        watch: S,
+    ) -> impl Stream<Item = S::Item>
+    where
+        T: Resource + Clone + 'static,
+        T::DynamicType: Eq + std::hash::Hash + Clone,
+        S: Stream,
+        S::Item: WatchItem,
+    {
+        let gauges = metrics.map(|m| {
+            let labels = StoreLabels {
+                kind: T::kind(dt).into_owned(),
+                group: T::group(dt).into_owned(),
+                version: T::version(dt).into_owned(),
+                name: name.to_string(),
+            };
+            (
+                m.store_objects.get_or_create(&labels).clone(),
+                m.store_last_event.get_or_create(&labels).clone(),
+            )
+        });
+
+        watch.map(move |event| {
+            if let Some((objects, last_event)) = &gauges {
+                objects.set(store.len() as i64);
+                if event.is_update() {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default();
+                    last_event.set(now.as_secs_f64());
+                }
+            }
+            event
+        })
+    }
+}
+
+// === impl WatchItem ===
+
+impl<T> WatchItem for watcher::Event<T> {
+    fn is_update(&self) -> bool {
+        matches!(self, watcher::Event::Apply(_) | watcher::Event::InitDone)
+    }
+}
+
+impl<T> WatchItem for watcher::Result<watcher::Event<T>> {
+    fn is_update(&self) -> bool {
+        self.as_ref().is_ok_and(WatchItem::is_update)
+    }
 }
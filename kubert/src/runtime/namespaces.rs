@@ -0,0 +1,118 @@
+use kube_core::Resource;
+use kube_runtime::{
+    reflector::{store::Writer, ObjectRef},
+    watcher,
+};
+use std::{collections::HashSet, hash::Hash};
+
+/// Applies events from several namespaced watches to a single [`Writer`].
+///
+/// A reflector's writer replaces its entire store when a watch (re)lists, so events from
+/// independent namespaced watches cannot be applied to it directly. Instead, each namespace's
+/// initial list is buffered and applied as a set of updates that only touch objects in that
+/// namespace.
+pub(super) struct NamespacesWriter<T>
+where
+    T: Resource + Clone + 'static,
+    T::DynamicType: Clone + Eq + Hash,
+{
+    writer: Writer<T>,
+    namespaces: Vec<Namespace<T>>,
+    ready: bool,
+}
+
+struct Namespace<T> {
+    name: String,
+    init: Option<Vec<T>>,
+    initialized: bool,
+}
+
+// === impl NamespacesWriter ===
+
+impl<T> NamespacesWriter<T>
+where
+    T: Resource + Clone + 'static,
+    T::DynamicType: Clone + Default + Eq + Hash,
+{
+    pub(super) fn new(writer: Writer<T>, namespaces: Vec<String>) -> Self {
+        Self {
+            writer,
+            namespaces: namespaces
+                .into_iter()
+                .map(|name| Namespace {
+                    name,
+                    init: None,
+                    initialized: false,
+                })
+                .collect(),
+            ready: false,
+        }
+    }
+
+    /// Applies an event from the watch on the `idx`th namespace.
+    pub(super) fn apply(&mut self, idx: usize, event: &watcher::Event<T>) {
+        match event {
+            watcher::Event::Apply(_) | watcher::Event::Delete(_) => {
+                self.writer.apply_watcher_event(event);
+            }
+
+            watcher::Event::Init => {
+                self.namespaces[idx].init = Some(Vec::new());
+            }
+
+            watcher::Event::InitApply(obj) => {
+                if let Some(init) = self.namespaces[idx].init.as_mut() {
+                    init.push(obj.clone());
+                }
+            }
+
+            watcher::Event::InitDone => {
+                let ns = &mut self.namespaces[idx];
+                let objs = ns.init.take().unwrap_or_default();
+                ns.initialized = true;
+                let name = ns.name.clone();
+                self.replace_namespace(&name, objs);
+
+                if !self.ready && self.namespaces.iter().all(|ns| ns.initialized) {
+                    self.mark_ready();
+                }
+            }
+        }
+    }
+
+    /// Replaces all cached objects in the namespace with `objs`.
+    fn replace_namespace(&mut self, ns: &str, objs: Vec<T>) {
+        let current = objs.iter().map(ObjectRef::from_obj).collect::<HashSet<_>>();
+        let stale = self
+            .writer
+            .as_reader()
+            .state()
+            .into_iter()
+            .filter(|obj| obj.meta().namespace.as_deref() == Some(ns))
+            .filter(|obj| !current.contains(&ObjectRef::from_obj(&**obj)))
+            .collect::<Vec<_>>();
+
+        for obj in stale {
+            self.writer
+                .apply_watcher_event(&watcher::Event::Delete((*obj).clone()));
+        }
+        for obj in objs {
+            self.writer.apply_watcher_event(&watcher::Event::Apply(obj));
+        }
+    }
+
+    /// Releases the store's readiness once every namespace has been listed.
+    ///
+    /// The writer only becomes ready when it completes an initial list, so the store's current
+    /// contents are relisted into it.
+    fn mark_ready(&mut self) {
+        self.ready = true;
+        let objs = self.writer.as_reader().state();
+        self.writer.apply_watcher_event(&watcher::Event::Init);
+        for obj in objs {
+            self.writer
+                .apply_watcher_event(&watcher::Event::InitApply((*obj).clone()));
+        }
+        self.writer.apply_watcher_event(&watcher::Event::InitDone);
+    }
+}
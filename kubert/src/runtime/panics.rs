@@ -0,0 +1,84 @@
+use crate::admin::Readiness;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, Once, PoisonError, Weak,
+};
+
+/// Tracks whether a runtime has observed a panic
+///
+/// Panic hooks are global to the process, so a single hook is installed the first time a runtime
+/// registers. The hook marks each registered runtime as not ready. A runtime is unregistered once
+/// all of its clones of the registration are dropped.
+#[derive(Clone, Debug)]
+pub(super) struct Registration(Arc<State>);
+
+#[derive(Debug)]
+struct State {
+    ready: Readiness,
+    panicked: AtomicBool,
+}
+
+static RUNTIMES: Mutex<Vec<Weak<State>>> = Mutex::new(Vec::new());
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// Registers a runtime to be marked as not ready when any thread panics
+pub(super) fn register(ready: Readiness) -> Registration {
+    INSTALL_HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let mut runtimes = RUNTIMES.lock().unwrap_or_else(PoisonError::into_inner);
+            runtimes.retain(|state| match state.upgrade() {
+                Some(state) => {
+                    state.panicked.store(true, Ordering::Release);
+                    state.ready.set(false);
+                    true
+                }
+                None => false,
+            });
+            // The panic itself is reported by the previous hook.
+            if !runtimes.is_empty() {
+                tracing::error!("A thread panicked; marking the runtime as not ready");
+            }
+            drop(runtimes);
+            hook(info);
+        }));
+    });
+
+    let state = Arc::new(State {
+        ready,
+        panicked: AtomicBool::new(false),
+    });
+    let mut runtimes = RUNTIMES.lock().unwrap_or_else(PoisonError::into_inner);
+    runtimes.retain(|state| state.strong_count() > 0);
+    runtimes.push(Arc::downgrade(&state));
+    Registration(state)
+}
+
+// === impl Registration ===
+
+impl Registration {
+    /// Returns true if any thread has panicked since the runtime was registered
+    pub(super) fn panicked(&self) -> bool {
+        self.0.panicked.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_mark_runtime_not_ready() {
+        let ready = crate::admin::Builder::new(([127, 0, 0, 1], 0).into()).readiness();
+        ready.set(true);
+        let registration = register(ready.clone());
+        assert!(!registration.panicked());
+
+        std::thread::spawn(|| panic!("boom"))
+            .join()
+            .expect_err("thread must panic");
+        assert!(registration.panicked());
+        assert!(!ready.get());
+    }
+}
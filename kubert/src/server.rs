@@ -19,7 +19,10 @@
     allow(dead_code, unused_variables)
 )]
 
-use std::{convert::Infallible, net::SocketAddr, path::PathBuf, str::FromStr, sync::Arc};
+use std::{
+    borrow::Cow, collections::HashMap, convert::Infallible, future::Future, net::SocketAddr,
+    path::PathBuf, pin::Pin, str::FromStr, sync::Arc,
+};
 use thiserror::Error;
 use tokio::net::{TcpListener, TcpStream};
 use tower::Service;
@@ -31,6 +34,12 @@ mod tls_rustls;
 #[cfg(feature = "openssl-tls")]
 mod tls_openssl;
 
+#[cfg(feature = "prometheus-client")]
+mod metrics;
+
+#[cfg(feature = "prometheus-client")]
+pub use self::metrics::ServerMetrics;
+
 #[cfg(test)]
 mod tests;
 
@@ -43,6 +52,16 @@ pub struct ServerArgs {
     #[cfg_attr(feature = "clap", clap(long, default_value = "0.0.0.0:443"))]
     pub server_addr: SocketAddr,
 
+    /// The name of the network interface (e.g. `eth0`) to which the server's
+    /// socket is bound.
+    ///
+    /// The interface's address is resolved when the server is bound, so the
+    /// server follows the interface's address across restarts. An address in
+    /// the same family as `server_addr` is preferred, and `server_addr`'s port
+    /// is used. When no interface is set, the server binds to `server_addr`.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub server_interface: Option<String>,
+
     /// The path to the server's TLS key file.
     ///
     /// This should be a PEM-encoded file containing a single PKCS#8 or RSA
@@ -56,6 +75,45 @@ pub struct ServerArgs {
     /// certificate.
     #[cfg_attr(feature = "clap", clap(long))]
     pub server_tls_certs: Option<TlsCertPath>,
+
+    /// The maximum size, in bytes, of a request body.
+    ///
+    /// Requests with a larger `content-length` are rejected with a `413
+    /// Payload Too Large` response. When a limit is set, requests without a
+    /// `content-length` (e.g. chunked bodies) are rejected with a `411 Length
+    /// Required` response. By default, request bodies are not limited.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub server_max_body_size: Option<u64>,
+
+    /// The maximum size, in bytes, of a body inflated by the server's
+    /// decompression layer.
+    ///
+    /// When the "server-brotli" or "server-gzip" features are enabled, bodies
+    /// that inflate beyond this size are aborted instead of being fully
+    /// decompressed. By default, decompressed bodies are not limited.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub server_max_decompressed_body_size: Option<u64>,
+
+    /// Serve only HTTP/1.1, disabling HTTP/2.
+    ///
+    /// When set, only `http/1.1` is advertised via TLS ALPN.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub server_http1_only: bool,
+
+    /// The protocols advertised via TLS ALPN, in order of preference.
+    ///
+    /// By default, `h2` and `http/1.1` are advertised (or only `http/1.1` when
+    /// serving only HTTP/1.1).
+    #[cfg_attr(feature = "clap", clap(long = "server-alpn-protocol"))]
+    pub server_alpn_protocols: Vec<String>,
+
+    /// Log each request's method, path, response status, and latency.
+    ///
+    /// Access logs are emitted as `INFO`-level events in the connection's
+    /// span, so they include the client's address and are rendered as JSON
+    /// when JSON logging is configured.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub server_access_log: bool,
 }
 
 /// A running server
@@ -64,7 +122,8 @@ pub struct ServerArgs {
 pub struct Bound {
     local_addr: SocketAddr,
     tcp: tokio::net::TcpListener,
-    tls: Arc<TlsPaths>,
+    tls: Arc<TlsConfig>,
+    config: Arc<ConnConfig>,
 }
 
 /// A running server
@@ -74,6 +133,31 @@ pub struct SpawnedServer {
     local_addr: SocketAddr,
     task: tokio::task::JoinHandle<()>,
 }
+/// Configures how the server's HTTP connections are served
+///
+/// See [`Bound::with_conn_builder`].
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub type ConnBuilder = hyper_util::server::conn::auto::Builder<Executor>;
+
+/// Spawns the background tasks of the server's HTTP connections
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub struct Executor(());
+
+/// Spawns a server's accept loop and connection tasks
+///
+/// See [`Bound::spawn_on`]. This is implemented for [`tokio::runtime::Handle`] and for functions
+/// that spawn boxed tasks, e.g. to name or instrument the server's tasks.
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub trait Spawn: Clone + Send + Sync + 'static {
+    /// Spawns a task, returning a handle to it
+    fn spawn(&self, task: BoxTask) -> tokio::task::JoinHandle<()>;
+}
+
+/// A server task to be run by a [`Spawn`] implementation
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub type BoxTask = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
 /// Describes an error that occurred while initializing a server
 #[derive(Debug, Error)]
 #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
@@ -87,11 +171,11 @@ pub enum Error {
     #[error("--server-tls-certs must be set")]
     NoTlsCerts,
 
-    /// The configured TLS certificate path could not be read
+    /// The configured TLS certificates could not be read
     #[error("failed to read TLS certificates: {0}")]
     TlsCertsReadError(#[source] std::io::Error),
 
-    /// The configured TLS key path could not be read
+    /// The configured TLS key could not be read
     #[error("failed to read TLS key: {0}")]
     TlsKeyReadError(#[source] std::io::Error),
 
@@ -106,6 +190,10 @@ pub enum Error {
     /// An error occurred while reading a bound server's local address
     #[error("failed to get bound local address: {0}")]
     LocalAddr(#[source] std::io::Error),
+
+    /// A configured ALPN protocol was empty or longer than 255 bytes
+    #[error("invalid ALPN protocol: {0:?}")]
+    InvalidAlpnProtocol(String),
 }
 
 /// The path to the server's TLS private key
@@ -123,6 +211,80 @@ struct TlsPaths {
     certs: TlsCertPath,
 }
 
+/// PEM-encoded TLS credentials provided in memory
+#[derive(Clone)]
+struct TlsPem {
+    key: Arc<[u8]>,
+    certs: Arc<[u8]>,
+}
+
+/// Where the server's TLS credentials are loaded from
+#[derive(Clone, Debug)]
+enum TlsCredentials {
+    Paths(TlsPaths),
+    Pem(TlsPem),
+}
+
+/// The server's TLS credentials, including any selected by SNI
+#[derive(Clone, Debug)]
+struct TlsConfig {
+    /// Served to clients that do not request one of the `sni` hostnames.
+    default: TlsCredentials,
+    /// Credentials keyed by lowercase hostname. These are loaded once, when they are added.
+    sni: Arc<HashMap<String, SniCredentials>>,
+}
+
+/// Credentials served to clients that request a specific hostname via SNI
+#[cfg(feature = "rustls-tls")]
+type SniCredentials = tls_rustls::SniCredentials;
+#[cfg(all(not(feature = "rustls-tls"), feature = "openssl-tls"))]
+type SniCredentials = tls_openssl::SniCredentials;
+#[cfg(not(any(feature = "rustls-tls", feature = "openssl-tls")))]
+type SniCredentials = Infallible;
+
+/// Configures how each connection is served
+#[derive(Clone, Debug)]
+struct ConnConfig {
+    max_body_size: Option<u64>,
+    #[cfg_attr(
+        not(any(feature = "server-brotli", feature = "server-gzip")),
+        allow(dead_code)
+    )]
+    max_decompressed_body_size: Option<u64>,
+    http1_only: bool,
+    alpn_protocols: Vec<Vec<u8>>,
+    access_log: bool,
+    configure: Option<ConfigureConn>,
+    #[cfg(feature = "prometheus-client")]
+    metrics: Option<ServerMetrics>,
+}
+
+/// Customizes the [`ConnBuilder`] used to serve each connection
+#[derive(Clone)]
+struct ConfigureConn(Arc<dyn Fn(&mut ConnBuilder) + Send + Sync + 'static>);
+
+/// Rejects requests with bodies that exceed a maximum size
+#[derive(Clone, Debug)]
+struct LimitBody<S> {
+    inner: S,
+    max_body_size: Option<u64>,
+}
+
+/// Aborts bodies that exceed a maximum size once decompressed
+#[cfg(any(feature = "server-brotli", feature = "server-gzip"))]
+#[derive(Clone, Debug)]
+struct LimitDecompressed<S> {
+    inner: S,
+    max_body_size: Option<u64>,
+}
+
+/// Logs the outcome of each request, when enabled
+#[derive(Clone, Debug)]
+struct AccessLog<S> {
+    inner: S,
+    enabled: bool,
+}
+
 // === impl ServerArgs ===
 
 impl ServerArgs {
@@ -137,38 +299,254 @@ impl ServerArgs {
     /// [tls-features]: crate#tls-features
     /// [tls-doc]: crate::server#tls-feature-flags
     pub async fn bind(self) -> Result<Bound, Error> {
+        self.bind_with(None, None).await
+    }
+
+    /// Attempts to load credentials and use the provided, already-bound, listener
+    ///
+    /// This is useful when the socket is provided externally (e.g. by systemd socket activation)
+    /// or in tests. The configured `server_addr` is ignored.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if neither of [the "rustls-tls" or "openssl-tls" Cargo
+    /// features][tls-features] are enabled. See [the module-level
+    /// documentation][tls-doc] for details.
+    ///
+    /// [tls-features]: crate#tls-features
+    /// [tls-doc]: crate::server#tls-feature-flags
+    pub async fn bind_listener(self, listener: TcpListener) -> Result<Bound, Error> {
+        self.bind_with(Some(listener), None).await
+    }
+
+    /// Attempts to bind the server socket using in-memory, PEM-encoded TLS credentials
+    ///
+    /// This is useful when credentials are provided via environment variables or fetched at
+    /// runtime rather than mounted as files. The configured `server_tls_key` and
+    /// `server_tls_certs` paths are ignored. Because the credentials are not read from the
+    /// filesystem, they are not reloaded to support certificate rotation.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if neither of [the "rustls-tls" or "openssl-tls" Cargo
+    /// features][tls-features] are enabled. See [the module-level
+    /// documentation][tls-doc] for details.
+    ///
+    /// [tls-features]: crate#tls-features
+    /// [tls-doc]: crate::server#tls-feature-flags
+    pub async fn bind_pem(
+        self,
+        key: impl Into<Vec<u8>>,
+        certs: impl Into<Vec<u8>>,
+    ) -> Result<Bound, Error> {
+        self.bind_with(None, Some(TlsPem::new(key, certs))).await
+    }
+
+    /// Uses the provided, already-bound, listener with in-memory, PEM-encoded TLS credentials
+    ///
+    /// See [`ServerArgs::bind_listener`] and [`ServerArgs::bind_pem`] for details.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if neither of [the "rustls-tls" or "openssl-tls" Cargo
+    /// features][tls-features] are enabled. See [the module-level
+    /// documentation][tls-doc] for details.
+    ///
+    /// [tls-features]: crate#tls-features
+    /// [tls-doc]: crate::server#tls-feature-flags
+    pub async fn bind_listener_pem(
+        self,
+        listener: TcpListener,
+        key: impl Into<Vec<u8>>,
+        certs: impl Into<Vec<u8>>,
+    ) -> Result<Bound, Error> {
+        self.bind_with(Some(listener), Some(TlsPem::new(key, certs)))
+            .await
+    }
+
+    /// Loads credentials and binds the server socket, unless a listener is provided
+    ///
+    /// Credentials are read from the configured paths unless PEM-encoded credentials are
+    /// provided.
+    async fn bind_with(
+        self,
+        listener: Option<TcpListener>,
+        pem: Option<TlsPem>,
+    ) -> Result<Bound, Error> {
+        let server_addr = self.server_addr;
+        let interface = self.server_interface.clone();
+        let (tls, config) = self.load(pem).await?;
+
+        let tcp = match listener {
+            Some(listener) => listener,
+            None => {
+                let res = match crate::accept::bind_addr(server_addr, interface.as_deref()) {
+                    Ok(addr) => TcpListener::bind(addr).await,
+                    Err(error) => Err(error),
+                };
+                res.map_err(|e| Error::Bind(server_addr, e))?
+            }
+        };
+        Bound::new(tcp, tls, config)
+    }
+
+    async fn load(self, pem: Option<TlsPem>) -> Result<(Arc<TlsConfig>, Arc<ConnConfig>), Error> {
+        let alpn_protocols = if !self.server_alpn_protocols.is_empty() {
+            self.server_alpn_protocols
+                .into_iter()
+                .map(|p| {
+                    if p.is_empty() || p.len() > 255 {
+                        return Err(Error::InvalidAlpnProtocol(p));
+                    }
+                    Ok(p.into_bytes())
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        } else if self.server_http1_only {
+            vec![b"http/1.1".to_vec()]
+        } else {
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        };
+        let config = Arc::new(ConnConfig {
+            max_body_size: self.server_max_body_size,
+            max_decompressed_body_size: self.server_max_decompressed_body_size,
+            http1_only: self.server_http1_only,
+            alpn_protocols,
+            access_log: self.server_access_log,
+            configure: None,
+            #[cfg(feature = "prometheus-client")]
+            metrics: None,
+        });
+
         let tls = {
-            let key = self.server_tls_key.ok_or(Error::NoTlsKey)?;
-            let certs = self.server_tls_certs.ok_or(Error::NoTlsCerts)?;
-            // Ensure the TLS key and certificate files load properly before binding the socket and
+            let default = match pem {
+                Some(pem) => TlsCredentials::Pem(pem),
+                None => {
+                    let key = self.server_tls_key.ok_or(Error::NoTlsKey)?;
+                    let certs = self.server_tls_certs.ok_or(Error::NoTlsCerts)?;
+                    TlsCredentials::Paths(TlsPaths { key, certs })
+                }
+            };
+            let tls = TlsConfig {
+                default,
+                sni: Default::default(),
+            };
+            // Ensure the TLS key and certificates load properly before binding the socket and
             // spawning the server.
 
             #[cfg(all(not(feature = "rustls-tls"), feature = "openssl-tls"))]
-            let _ = tls_openssl::load_tls(&key, &certs).await?;
+            let _ = tls_openssl::load_tls(&tls.default, &tls.sni, &config.alpn_protocols).await?;
             #[cfg(feature = "rustls-tls")]
-            let _ = tls_rustls::load_tls(&key, &certs).await?;
+            let _ = tls_rustls::load_tls(&tls.default, &tls.sni, &config.alpn_protocols).await?;
 
-            Arc::new(TlsPaths { key, certs })
+            Arc::new(tls)
         };
 
-        let tcp = TcpListener::bind(&self.server_addr)
-            .await
-            .map_err(|e| Error::Bind(self.server_addr, e))?;
+        Ok((tls, config))
+    }
+}
+
+// === impl Bound ===
+
+impl Bound {
+    fn new(tcp: TcpListener, tls: Arc<TlsConfig>, config: Arc<ConnConfig>) -> Result<Self, Error> {
         let local_addr = tcp.local_addr().map_err(Error::LocalAddr)?;
-        Ok(Bound {
+        Ok(Self {
             local_addr,
             tcp,
             tls,
+            config,
         })
     }
-}
 
-impl Bound {
     /// Returns the bound local address of the server
     pub fn local_addr(&self) -> SocketAddr {
         self.local_addr
     }
 
+    /// Customizes how each connection is served, e.g. to tune HTTP/2 settings
+    ///
+    /// The provided function is invoked with the [`ConnBuilder`] for each connection after the
+    /// server's defaults (e.g. the HTTP/1 header read timeout) have been applied, so the defaults
+    /// may be overridden.
+    ///
+    /// ```
+    /// fn tune(server: kubert::server::Bound) -> kubert::server::Bound {
+    ///     server.with_conn_builder(|builder| {
+    ///         builder
+    ///             .http2()
+    ///             .max_concurrent_streams(16)
+    ///             .initial_stream_window_size(1024 * 1024);
+    ///     })
+    /// }
+    /// ```
+    pub fn with_conn_builder(
+        mut self,
+        configure: impl Fn(&mut ConnBuilder) + Send + Sync + 'static,
+    ) -> Self {
+        Arc::make_mut(&mut self.config).configure = Some(ConfigureConn(Arc::new(configure)));
+        self
+    }
+
+    /// Serves the TLS credentials at the given paths to clients that request `hostname` via SNI
+    ///
+    /// Hostnames are matched exactly, ignoring case. Clients that do not send SNI, or that request
+    /// a hostname without its own credentials, are served the server's default credentials.
+    ///
+    /// Unlike the default credentials, the files are read once, when this method is called, and
+    /// are not reloaded for each connection. An error is returned if the credentials cannot be
+    /// loaded or if the key does not match the certificate.
+    #[cfg(any(feature = "rustls-tls", feature = "openssl-tls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "rustls-tls", feature = "openssl-tls"))))]
+    pub async fn with_sni_credentials(
+        self,
+        hostname: impl AsRef<str>,
+        key: TlsKeyPath,
+        certs: TlsCertPath,
+    ) -> Result<Self, Error> {
+        self.with_sni(hostname, TlsCredentials::Paths(TlsPaths { key, certs }))
+            .await
+    }
+
+    /// Serves in-memory, PEM-encoded TLS credentials to clients that request `hostname` via SNI
+    ///
+    /// See [`Bound::with_sni_credentials`] for details.
+    #[cfg(any(feature = "rustls-tls", feature = "openssl-tls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "rustls-tls", feature = "openssl-tls"))))]
+    pub async fn with_sni_pem(
+        self,
+        hostname: impl AsRef<str>,
+        key: impl Into<Vec<u8>>,
+        certs: impl Into<Vec<u8>>,
+    ) -> Result<Self, Error> {
+        self.with_sni(hostname, TlsCredentials::Pem(TlsPem::new(key, certs)))
+            .await
+    }
+
+    #[cfg(any(feature = "rustls-tls", feature = "openssl-tls"))]
+    async fn with_sni(
+        mut self,
+        hostname: impl AsRef<str>,
+        creds: TlsCredentials,
+    ) -> Result<Self, Error> {
+        #[cfg(all(not(feature = "rustls-tls"), feature = "openssl-tls"))]
+        let creds = tls_openssl::load_sni_credentials(&creds, &self.config.alpn_protocols).await?;
+        #[cfg(feature = "rustls-tls")]
+        let creds = tls_rustls::load_sni_credentials(&creds).await?;
+
+        let hostname = hostname.as_ref().to_ascii_lowercase();
+        let tls = Arc::make_mut(&mut self.tls);
+        Arc::make_mut(&mut tls.sni).insert(hostname, creds);
+        Ok(self)
+    }
+
+    /// Records connection metrics with the given [`ServerMetrics`]
+    #[cfg(feature = "prometheus-client")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "prometheus-client")))]
+    pub fn with_metrics(mut self, metrics: ServerMetrics) -> Self {
+        Arc::make_mut(&mut self.config).metrics = Some(metrics);
+        self
+    }
+
     /// Bind an HTTPS server to the configured address with the provided service
     ///
     /// The server terminates gracefully when the provided `drain` handle is signaled.
@@ -178,6 +556,28 @@ impl Bound {
     /// internet or to clients that open many short-lived connections. It is primarily intended for
     /// kubernetes admission controllers.
     pub fn spawn<S, B>(self, service: S, drain: drain::Watch) -> SpawnedServer
+    where
+        S: Service<hyper::Request<hyper::body::Incoming>, Response = hyper::Response<B>>
+            + Clone
+            + Send
+            + 'static,
+        S::Error: std::error::Error + Send + Sync,
+        S::Future: Send,
+        B: hyper::body::Body + Send + 'static,
+        B::Data: Send,
+        B::Error: std::error::Error + Send + Sync,
+    {
+        self.spawn_on(service, drain, tokio::runtime::Handle::current())
+    }
+
+    /// Bind an HTTPS server to the configured address with the provided service, spawning the
+    /// server with the provided [`Spawn`] implementation (e.g. a [`tokio::runtime::Handle`])
+    ///
+    /// The server's accept loop and all connection tasks are spawned with `spawn`. The listener
+    /// remains registered with the runtime on which it was bound, which must continue running.
+    ///
+    /// See [`Bound::spawn`] for details.
+    pub fn spawn_on<S, B>(self, service: S, drain: drain::Watch, spawn: impl Spawn) -> SpawnedServer
     where
         S: Service<hyper::Request<hyper::body::Incoming>, Response = hyper::Response<B>>
             + Clone
@@ -193,12 +593,13 @@ impl Bound {
             local_addr,
             tcp,
             tls,
+            config,
         } = self;
 
-        let task = tokio::spawn(
-            accept_loop(tcp, drain, service, tls)
+        let task = spawn.spawn(Box::pin(
+            accept_loop(tcp, drain, service, tls, config, spawn.clone())
                 .instrument(info_span!("server", port = %local_addr.port())),
-        );
+        ));
 
         SpawnedServer { local_addr, task }
     }
@@ -223,8 +624,14 @@ impl SpawnedServer {
     }
 }
 
-async fn accept_loop<S, B>(tcp: TcpListener, drain: drain::Watch, service: S, tls: Arc<TlsPaths>)
-where
+async fn accept_loop<S, B>(
+    tcp: TcpListener,
+    drain: drain::Watch,
+    service: S,
+    tls: Arc<TlsConfig>,
+    config: Arc<ConnConfig>,
+    spawn: impl Spawn,
+) where
     S: Service<hyper::Request<hyper::body::Incoming>, Response = hyper::Response<B>>
         + Clone
         + Send
@@ -235,6 +642,14 @@ where
     B::Data: Send,
     B::Error: std::error::Error + Send + Sync,
 {
+    // Each connection holds a receiver so that the number of active connections may be observed
+    // and so that we can wait for all connections to complete when draining.
+    let (active, _) = tokio::sync::watch::channel(());
+
+    // Back off while accepting fails consecutively (e.g. when the process has run out of file
+    // descriptors) so that the accept loop does not spin.
+    let mut accept_errors = 0;
+
     tracing::debug!("listening");
     loop {
         tracing::trace!("accepting");
@@ -243,14 +658,24 @@ where
             biased;
 
             release = drain.clone().signaled() => {
+                info!(connections = active.receiver_count(), "Draining connections");
+                let start = std::time::Instant::now();
+                active.closed().await;
+                info!(elapsed = ?start.elapsed(), "Connections drained");
                 drop(release);
                 return;
             }
 
             res = tcp.accept() => match res {
-                Ok((socket, _)) => socket,
+                Ok((socket, _)) => {
+                    accept_errors = 0;
+                    socket
+                }
                 Err(error) => {
-                    error!(%error, "Failed to accept connection");
+                    let delay = crate::accept::error_delay(accept_errors);
+                    accept_errors += 1;
+                    error!(%error, ?delay, "Failed to accept connection");
+                    tokio::time::sleep(delay).await;
                     continue;
                 }
             },
@@ -269,18 +694,35 @@ where
             }
         };
 
-        tokio::spawn(
-            serve_conn(socket, drain.clone(), service.clone(), tls.clone()).instrument(info_span!(
+        spawn.spawn(Box::pin(
+            serve_conn(
+                socket,
+                active.subscribe(),
+                drain.clone(),
+                service.clone(),
+                tls.clone(),
+                config.clone(),
+            )
+            .instrument(info_span!(
                 "conn",
                 client.ip = %client_addr.ip(),
                 client.port = %client_addr.port(),
+                tls.version = tracing::field::Empty,
+                tls.sni = tracing::field::Empty,
+                tls.alpn = tracing::field::Empty,
             )),
-        );
+        ));
     }
 }
 
-async fn serve_conn<S, B>(socket: TcpStream, drain: drain::Watch, service: S, tls: Arc<TlsPaths>)
-where
+async fn serve_conn<S, B>(
+    socket: TcpStream,
+    active: tokio::sync::watch::Receiver<()>,
+    drain: drain::Watch,
+    service: S,
+    tls: Arc<TlsConfig>,
+    config: Arc<ConnConfig>,
+) where
     S: Service<hyper::Request<hyper::body::Incoming>, Response = hyper::Response<B>>
         + Clone
         + Send
@@ -294,13 +736,12 @@ where
     tracing::debug!("accepted TCP connection");
 
     let socket = {
-        let TlsPaths { ref key, ref certs } = &*tls;
         // Reload the TLS credentials for each connection.
 
         #[cfg(all(not(feature = "rustls-tls"), feature = "openssl-tls"))]
-        let res = tls_openssl::load_tls(key, certs).await;
+        let res = tls_openssl::load_tls(&tls.default, &tls.sni, &config.alpn_protocols).await;
         #[cfg(feature = "rustls-tls")]
-        let res = tls_rustls::load_tls(key, certs).await;
+        let res = tls_rustls::load_tls(&tls.default, &tls.sni, &config.alpn_protocols).await;
         #[cfg(not(any(feature = "rustls-tls", feature = "openssl-tls")))]
         let res = {
             enum Accept {}
@@ -336,35 +777,56 @@ where
         };
         tracing::trace!("TLS handshake completed");
 
+        // Record the negotiated session parameters on the connection's span.
+        #[cfg(all(not(feature = "rustls-tls"), feature = "openssl-tls"))]
+        tls_openssl::record_session(&socket, &tracing::Span::current());
+        #[cfg(feature = "rustls-tls")]
+        tls_rustls::record_session(&socket, &tracing::Span::current());
+
+        #[cfg(feature = "prometheus-client")]
+        if let Some(metrics) = config.metrics.as_ref() {
+            #[cfg(all(not(feature = "rustls-tls"), feature = "openssl-tls"))]
+            metrics.tls_connection(tls_openssl::tls_version(&socket));
+            #[cfg(feature = "rustls-tls")]
+            metrics.tls_connection(tls_rustls::tls_version(&socket));
+        }
+
         socket
     };
 
-    #[derive(Copy, Clone, Debug)]
-    struct Executor;
-    impl<F> hyper::rt::Executor<F> for Executor
-    where
-        F: std::future::Future + Send + 'static,
-        F::Output: Send + 'static,
-    {
-        fn execute(&self, fut: F) {
-            tokio::spawn(fut.in_current_span());
-        }
-    }
+    let service = LimitBody {
+        inner: service,
+        max_body_size: config.max_body_size,
+    };
 
     #[cfg(any(feature = "server-brotli", feature = "server-gzip"))]
-    let service = tower_http::decompression::Decompression::new(
-        tower_http::compression::Compression::new(service),
-    );
+    let service = LimitDecompressed {
+        inner: tower_http::decompression::Decompression::new(
+            tower_http::compression::Compression::new(service),
+        ),
+        max_body_size: config.max_decompressed_body_size,
+    };
+
+    let service = AccessLog {
+        inner: service,
+        enabled: config.access_log,
+    };
 
     // Serve the HTTP connection and wait for the drain signal. If a drain is
     // signaled, tell the HTTP connection to terminate gracefully when in-flight
     // requests have completed.
-    let mut builder = hyper_util::server::conn::auto::Builder::new(Executor);
+    let mut builder = ConnBuilder::new(Executor(()));
+    if config.http1_only {
+        builder = builder.http1_only();
+    }
     // Prevent port scanners, etc, from holding connections open.
     builder
         .http1()
         .header_read_timeout(std::time::Duration::from_secs(2))
         .timer(hyper_util::rt::TokioTimer::default());
+    if let Some(ConfigureConn(configure)) = config.configure.as_ref() {
+        configure(&mut builder);
+    }
     let graceful = hyper_util::server::graceful::GracefulShutdown::new();
     let conn = graceful.watch(
         builder
@@ -380,6 +842,7 @@ where
                 Ok(()) => debug!("Connection closed"),
                 Err(error) => info!(%error, "Connection lost"),
             }
+            drop(active);
         }
         .in_current_span(),
     );
@@ -388,6 +851,232 @@ where
     latch.release_after(graceful.shutdown()).await;
 }
 
+// === impl Executor ===
+
+impl<F> hyper::rt::Executor<F> for Executor
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    fn execute(&self, fut: F) {
+        tokio::spawn(fut.in_current_span());
+    }
+}
+
+// === impl Spawn ===
+
+impl Spawn for tokio::runtime::Handle {
+    fn spawn(&self, task: BoxTask) -> tokio::task::JoinHandle<()> {
+        tokio::runtime::Handle::spawn(self, task)
+    }
+}
+
+impl<F> Spawn for F
+where
+    F: Fn(BoxTask) -> tokio::task::JoinHandle<()> + Clone + Send + Sync + 'static,
+{
+    fn spawn(&self, task: BoxTask) -> tokio::task::JoinHandle<()> {
+        (self)(task)
+    }
+}
+
+// === impl ConfigureConn ===
+
+impl std::fmt::Debug for ConfigureConn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigureConn").finish_non_exhaustive()
+    }
+}
+
+// === impl LimitBody ===
+
+impl<S, ReqB, B> Service<hyper::Request<ReqB>> for LimitBody<S>
+where
+    S: Service<hyper::Request<ReqB>, Response = hyper::Response<B>>,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+    ReqB: hyper::body::Body,
+    B: hyper::body::Body + Send + 'static,
+{
+    type Response = hyper::Response<http_body_util::Either<B, http_body_util::Empty<B::Data>>>;
+    type Error = S::Error;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, S::Error>> + Send + 'static>,
+    >;
+
+    #[inline]
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::Request<ReqB>) -> Self::Future {
+        if let Some(max) = self.max_body_size {
+            // Hyper fails bodies that exceed their `content-length`, so the
+            // body's size hint bounds how much of it may be read.
+            let hint = hyper::body::Body::size_hint(req.body());
+            if hint.lower() > max || hint.upper().is_some_and(|len| len > max) {
+                info!(content_length = ?hint.exact(), max_body_size = max, "Request body too large");
+                return Box::pin(std::future::ready(Ok(empty_response(
+                    hyper::StatusCode::PAYLOAD_TOO_LARGE,
+                ))));
+            }
+            if hint.upper().is_none() {
+                info!(max_body_size = max, "Request body length unknown");
+                return Box::pin(std::future::ready(Ok(empty_response(
+                    hyper::StatusCode::LENGTH_REQUIRED,
+                ))));
+            }
+        }
+
+        let rsp = self.inner.call(req);
+        Box::pin(async move { Ok(rsp.await?.map(http_body_util::Either::Left)) })
+    }
+}
+
+fn empty_response<B: hyper::body::Body>(
+    status: hyper::StatusCode,
+) -> hyper::Response<http_body_util::Either<B, http_body_util::Empty<B::Data>>> {
+    hyper::Response::builder()
+        .status(status)
+        .body(http_body_util::Either::Right(http_body_util::Empty::new()))
+        .expect("response must be valid")
+}
+
+// === impl LimitDecompressed ===
+
+#[cfg(any(feature = "server-brotli", feature = "server-gzip"))]
+impl<S, ReqB, B> Service<hyper::Request<ReqB>> for LimitDecompressed<S>
+where
+    S: Service<hyper::Request<ReqB>, Response = hyper::Response<B>>,
+    S::Future: Send + 'static,
+    B: hyper::body::Body,
+{
+    type Response = hyper::Response<http_body_util::Either<http_body_util::Limited<B>, B>>;
+    type Error = S::Error;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, S::Error>> + Send + 'static>,
+    >;
+
+    #[inline]
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::Request<ReqB>) -> Self::Future {
+        let max = self
+            .max_body_size
+            .map(|max| usize::try_from(max).unwrap_or(usize::MAX));
+        let rsp = self.inner.call(req);
+        Box::pin(async move {
+            Ok(rsp.await?.map(|body| match max {
+                Some(max) => http_body_util::Either::Left(http_body_util::Limited::new(body, max)),
+                None => http_body_util::Either::Right(body),
+            }))
+        })
+    }
+}
+
+// === impl AccessLog ===
+
+impl<S, ReqB, B> Service<hyper::Request<ReqB>> for AccessLog<S>
+where
+    S: Service<hyper::Request<ReqB>, Response = hyper::Response<B>>,
+    S::Error: std::fmt::Display + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, S::Error>> + Send + 'static>,
+    >;
+
+    #[inline]
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::Request<ReqB>) -> Self::Future {
+        if !self.enabled {
+            return Box::pin(self.inner.call(req));
+        }
+
+        let method = req.method().clone();
+        let path = req.uri().path().to_string();
+        let start = tokio::time::Instant::now();
+        let rsp = self.inner.call(req);
+        Box::pin(async move {
+            let res = rsp.await;
+            let latency = start.elapsed();
+            match &res {
+                Ok(rsp) => info!(
+                    %method,
+                    %path,
+                    status = rsp.status().as_u16(),
+                    latency_ms = latency.as_secs_f64() * 1000.0,
+                    "Request completed"
+                ),
+                Err(error) => info!(
+                    %method,
+                    %path,
+                    %error,
+                    latency_ms = latency.as_secs_f64() * 1000.0,
+                    "Request failed"
+                ),
+            }
+            res
+        })
+    }
+}
+
+// === impl TlsPem ===
+
+impl TlsPem {
+    fn new(key: impl Into<Vec<u8>>, certs: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into().into(),
+            certs: certs.into().into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for TlsPem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Avoid logging the private key.
+        f.debug_struct("TlsPem").finish_non_exhaustive()
+    }
+}
+
+// === impl TlsCredentials ===
+
+impl TlsCredentials {
+    /// Returns the PEM-encoded private key and certificates, reading them from
+    /// the filesystem if necessary.
+    async fn read(&self) -> Result<(Cow<'_, [u8]>, Cow<'_, [u8]>), Error> {
+        match self {
+            Self::Paths(TlsPaths {
+                key: TlsKeyPath(kp),
+                certs: TlsCertPath(cp),
+            }) => {
+                let key = tokio::fs::read(kp).await.map_err(Error::TlsKeyReadError)?;
+                let certs = tokio::fs::read(cp)
+                    .await
+                    .map_err(Error::TlsCertsReadError)?;
+                Ok((Cow::Owned(key), Cow::Owned(certs)))
+            }
+            Self::Pem(TlsPem { key, certs }) => Ok((Cow::Borrowed(key), Cow::Borrowed(certs))),
+        }
+    }
+}
+
 // === impl TlsCertPath ===
 
 impl FromStr for TlsCertPath {
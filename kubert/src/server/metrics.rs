@@ -0,0 +1,42 @@
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{counter::Counter, family::Family},
+    registry::Registry,
+};
+
+/// Metrics for tracking server connections
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "server", feature = "prometheus-client")))
+)]
+pub struct ServerMetrics {
+    tls_connections: Family<TlsLabels, Counter>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct TlsLabels {
+    tls_version: &'static str,
+}
+
+impl ServerMetrics {
+    /// Creates a new set of metrics and registers them.
+    pub fn register(registry: &mut Registry) -> Self {
+        let tls_connections = Family::default();
+        registry.register(
+            "tls_connections",
+            "Count of TLS connections accepted by the server by negotiated protocol version",
+            tls_connections.clone(),
+        );
+
+        Self { tls_connections }
+    }
+
+    pub(super) fn tls_connection(&self, tls_version: Option<&'static str>) {
+        self.tls_connections
+            .get_or_create(&TlsLabels {
+                tls_version: tls_version.unwrap_or("unknown"),
+            })
+            .inc();
+    }
+}
@@ -30,11 +30,27 @@ fn gen_keys() -> (TempDir, TlsPaths) {
     (dir, TlsPaths { key, certs })
 }
 
+/// Server arguments without TLS credentials, bound to an ephemeral loopback port
+fn test_args() -> ServerArgs {
+    ServerArgs {
+        server_addr: ([127, 0, 0, 1], 0).into(),
+        server_interface: None,
+        server_tls_key: None,
+        server_tls_certs: None,
+        server_max_body_size: None,
+        server_max_decompressed_body_size: None,
+        server_http1_only: false,
+        server_alpn_protocols: vec![],
+        server_access_log: false,
+    }
+}
+
 #[cfg(feature = "rustls-tls")]
 #[tokio::test]
 async fn load_tls_rustls() {
-    let (_tempdir, TlsPaths { key, certs }) = gen_keys();
-    match super::tls_rustls::load_tls(&key, &certs).await {
+    let (_tempdir, paths) = gen_keys();
+    let default = TlsCredentials::Paths(paths);
+    match super::tls_rustls::load_tls(&default, &Default::default(), &[b"h2".to_vec()]).await {
         Ok(_) => println!("load_tls: success!"),
         Err(error) => panic!("load_tls failed! {error}"),
     }
@@ -43,9 +59,405 @@ async fn load_tls_rustls() {
 #[cfg(feature = "openssl-tls")]
 #[tokio::test]
 async fn load_tls_openssl() {
-    let (_tempdir, TlsPaths { key, certs }) = gen_keys();
-    match super::tls_openssl::load_tls(&key, &certs).await {
+    let (_tempdir, paths) = gen_keys();
+    let default = TlsCredentials::Paths(paths);
+    match super::tls_openssl::load_tls(&default, &Default::default(), &[b"h2".to_vec()]).await {
         Ok(_) => println!("load_tls: success!"),
         Err(error) => panic!("load_tls failed! {error}"),
     }
 }
+
+/// Generates PEM-encoded credentials for `hostname`.
+fn gen_pem(hostname: &str) -> TlsCredentials {
+    let cert = rcgen::generate_simple_self_signed(vec![hostname.to_string()])
+        .expect("failed to generate certs");
+    TlsCredentials::Pem(TlsPem::new(cert.key_pair.serialize_pem(), cert.cert.pem()))
+}
+
+/// Generates PEM-encoded credentials whose key does not match the certificate.
+fn gen_mismatched_pem() -> TlsCredentials {
+    let TlsCredentials::Pem(TlsPem { key, .. }) = gen_pem("kubert.test.example.com") else {
+        unreachable!()
+    };
+    let TlsCredentials::Pem(TlsPem { certs, .. }) = gen_pem("kubert.test.example.com") else {
+        unreachable!()
+    };
+    TlsCredentials::Pem(TlsPem { key, certs })
+}
+
+#[cfg(feature = "rustls-tls")]
+#[tokio::test]
+async fn load_tls_rustls_sni() {
+    use super::tls_rustls::{load_sni_credentials, load_tls};
+
+    let other = load_sni_credentials(&gen_pem("other.test.example.com"))
+        .await
+        .expect("failed to load SNI credentials");
+    let sni = Arc::new(HashMap::from([(
+        "other.test.example.com".to_string(),
+        other,
+    )]));
+    let default = gen_pem("kubert.test.example.com");
+    if let Err(error) = load_tls(&default, &sni, &[b"h2".to_vec()]).await {
+        panic!("load_tls failed! {error}");
+    }
+
+    match load_sni_credentials(&gen_mismatched_pem()).await {
+        Err(Error::InvalidTlsCredentials(_)) => {}
+        res => panic!("unexpected result: {res:?}"),
+    }
+}
+
+#[cfg(feature = "openssl-tls")]
+#[tokio::test]
+async fn load_tls_openssl_sni() {
+    use super::tls_openssl::{load_sni_credentials, load_tls};
+
+    let alpn = [b"h2".to_vec()];
+    let other = load_sni_credentials(&gen_pem("other.test.example.com"), &alpn)
+        .await
+        .expect("failed to load SNI credentials");
+    let sni = Arc::new(HashMap::from([(
+        "other.test.example.com".to_string(),
+        other,
+    )]));
+    let default = gen_pem("kubert.test.example.com");
+    if let Err(error) = load_tls(&default, &sni, &alpn).await {
+        panic!("load_tls failed! {error}");
+    }
+
+    match load_sni_credentials(&gen_mismatched_pem(), &alpn).await {
+        Err(Error::InvalidTlsCredentials(_)) => {}
+        res => panic!("unexpected result: {res:?}"),
+    }
+}
+
+/// Returns a service that responds to each request with an empty `200 OK`
+/// response.
+fn ok_service<B>() -> impl Service<
+    hyper::Request<B>,
+    Response = hyper::Response<String>,
+    Error = Infallible,
+    Future = impl Send,
+> + Clone {
+    tower::service_fn(|_: hyper::Request<B>| async move {
+        Ok::<_, Infallible>(hyper::Response::new(String::new()))
+    })
+}
+
+#[tokio::test]
+async fn limits_body_size() {
+    use tower::ServiceExt;
+
+    let svc = LimitBody {
+        inner: ok_service(),
+        max_body_size: Some(4),
+    };
+    let req = |body: &str| {
+        hyper::Request::builder()
+            .header(hyper::header::CONTENT_LENGTH, body.len())
+            .body(body.to_string())
+            .expect("request must be valid")
+    };
+
+    let rsp = svc.clone().oneshot(req("abcd")).await.expect("infallible");
+    assert_eq!(rsp.status(), hyper::StatusCode::OK);
+
+    let rsp = svc.oneshot(req("abcde")).await.expect("infallible");
+    assert_eq!(rsp.status(), hyper::StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn requires_body_length_when_limited() {
+    use tower::ServiceExt;
+
+    // Streamed bodies do not have a content-length.
+    let req = || {
+        let frames = ["ab", "cd"]
+            .map(|c| Ok::<_, Infallible>(hyper::body::Frame::data(hyper::body::Bytes::from(c))));
+        hyper::Request::builder()
+            .header(hyper::header::TRANSFER_ENCODING, "chunked")
+            .body(http_body_util::StreamBody::new(tokio_stream::iter(frames)))
+            .expect("request must be valid")
+    };
+
+    let svc = LimitBody {
+        inner: ok_service(),
+        max_body_size: Some(4),
+    };
+    let rsp = svc.oneshot(req()).await.expect("infallible");
+    assert_eq!(rsp.status(), hyper::StatusCode::LENGTH_REQUIRED);
+
+    let svc = LimitBody {
+        inner: ok_service(),
+        max_body_size: None,
+    };
+    let rsp = svc.oneshot(req()).await.expect("infallible");
+    assert_eq!(rsp.status(), hyper::StatusCode::OK);
+}
+
+#[cfg(feature = "server-gzip")]
+#[tokio::test]
+async fn limits_decompressed_body_size() {
+    use http_body_util::BodyExt;
+    use std::io::Write;
+    use tower::ServiceExt;
+
+    // Returns a gzip-encoded response that inflates to `len` bytes.
+    let gzip_service = tower::service_fn(|len: usize| async move {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        gz.write_all(&vec![b'a'; len])
+            .expect("body must be compressed");
+        let body = gz.finish().expect("body must be compressed");
+        let rsp = hyper::Response::builder()
+            .header(hyper::header::CONTENT_ENCODING, "gzip")
+            .body(http_body_util::Full::new(hyper::body::Bytes::from(body)))
+            .expect("response must be valid");
+        Ok::<_, Infallible>(rsp)
+    });
+    let svc = |max_body_size| LimitDecompressed {
+        inner: tower_http::decompression::Decompression::new(
+            tower::ServiceBuilder::new()
+                .map_request(|req: hyper::Request<usize>| req.into_body())
+                .service(gzip_service),
+        ),
+        max_body_size,
+    };
+    let req = |len: usize| hyper::Request::new(len);
+
+    let rsp = svc(Some(1024))
+        .oneshot(req(1024))
+        .await
+        .expect("infallible");
+    let body = rsp.into_body().collect().await.expect("body must be read");
+    assert_eq!(body.to_bytes().len(), 1024);
+
+    // A small compressed body that inflates far beyond the limit.
+    let rsp = svc(Some(1024))
+        .oneshot(req(16 * 1024 * 1024))
+        .await
+        .expect("infallible");
+    let error = rsp
+        .into_body()
+        .collect()
+        .await
+        .expect_err("body must exceed the limit");
+    assert!(error.is::<http_body_util::LengthLimitError>());
+
+    // Without a limit, bodies are fully decompressed.
+    let rsp = svc(None)
+        .oneshot(req(16 * 1024 * 1024))
+        .await
+        .expect("infallible");
+    let body = rsp.into_body().collect().await.expect("body must be read");
+    assert_eq!(body.to_bytes().len(), 16 * 1024 * 1024);
+}
+
+#[tokio::test]
+async fn access_log_preserves_responses() {
+    use tower::ServiceExt;
+
+    for enabled in [false, true] {
+        let svc = AccessLog {
+            inner: tower::service_fn(|req: hyper::Request<String>| async move {
+                let rsp = hyper::Response::builder()
+                    .status(hyper::StatusCode::ACCEPTED)
+                    .body(req.into_body())
+                    .expect("response must be valid");
+                Ok::<_, Infallible>(rsp)
+            }),
+            enabled,
+        };
+        let req = hyper::Request::builder()
+            .method(hyper::Method::POST)
+            .uri("/validate")
+            .body(String::from("ok"))
+            .expect("request must be valid");
+        let rsp = svc.oneshot(req).await.expect("infallible");
+        assert_eq!(rsp.status(), hyper::StatusCode::ACCEPTED);
+        assert_eq!(rsp.into_body(), "ok");
+    }
+}
+
+#[cfg(feature = "openssl-tls")]
+#[test]
+fn select_alpn_protocol_openssl() {
+    use super::tls_openssl::select_alpn_protocol;
+
+    let client = b"\x08http/1.1\x02h2";
+    let h2 = [b"h2".to_vec(), b"http/1.1".to_vec()];
+    assert_eq!(select_alpn_protocol(&h2, client), Some(&b"h2"[..]));
+    let http1 = [b"http/1.1".to_vec()];
+    assert_eq!(select_alpn_protocol(&http1, client), Some(&b"http/1.1"[..]));
+    assert_eq!(select_alpn_protocol(&http1, b"\x02h2"), None);
+    // Malformed client lists are ignored.
+    assert_eq!(select_alpn_protocol(&h2, b"\x09h2"), None);
+}
+
+#[tokio::test]
+async fn rejects_invalid_alpn_protocols() {
+    let (_tempdir, TlsPaths { key, certs }) = gen_keys();
+    let args = ServerArgs {
+        server_tls_key: Some(key),
+        server_tls_certs: Some(certs),
+        server_alpn_protocols: vec!["h2".to_string(), "".to_string()],
+        ..test_args()
+    };
+    match args.bind().await {
+        Err(Error::InvalidAlpnProtocol(p)) => assert_eq!(p, ""),
+        res => panic!("unexpected result: {res:?}"),
+    }
+}
+
+#[tokio::test]
+async fn binds_listener() {
+    let (_tempdir, TlsPaths { key, certs }) = gen_keys();
+    let args = ServerArgs {
+        server_addr: ([127, 0, 0, 1], 1).into(),
+        server_tls_key: Some(key),
+        server_tls_certs: Some(certs),
+        ..test_args()
+    };
+    let listener = TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .expect("failed to bind listener");
+    let addr = listener
+        .local_addr()
+        .expect("listener must have an address");
+    let bound = args
+        .bind_listener(listener)
+        .await
+        .expect("failed to adopt listener");
+    assert_eq!(bound.local_addr(), addr);
+}
+
+#[tokio::test]
+async fn fails_to_bind_unknown_interface() {
+    let (_tempdir, TlsPaths { key, certs }) = gen_keys();
+    let args = ServerArgs {
+        server_interface: Some("kubert-test0".to_string()),
+        server_tls_key: Some(key),
+        server_tls_certs: Some(certs),
+        ..test_args()
+    };
+    match args.bind().await {
+        Err(Error::Bind(addr, _)) => assert_eq!(addr, ([127, 0, 0, 1], 0).into()),
+        res => panic!("unexpected result: {res:?}"),
+    }
+}
+
+#[tokio::test]
+async fn binds_listener_with_pem() {
+    let cert = rcgen::generate_simple_self_signed(vec!["kubert.test.example.com".to_string()])
+        .expect("failed to generate certs");
+    let args = ServerArgs {
+        server_addr: ([127, 0, 0, 1], 1).into(),
+        ..test_args()
+    };
+    let listener = TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .expect("failed to bind listener");
+    let addr = listener
+        .local_addr()
+        .expect("listener must have an address");
+    let bound = args
+        .bind_listener_pem(listener, cert.key_pair.serialize_pem(), cert.cert.pem())
+        .await
+        .expect("failed to load PEM credentials");
+    assert_eq!(bound.local_addr(), addr);
+}
+
+#[cfg(any(feature = "rustls-tls", feature = "openssl-tls"))]
+#[tokio::test]
+async fn validates_sni_credentials() {
+    let (_tempdir, TlsPaths { key, certs }) = gen_keys();
+    let bind = || async {
+        let args = ServerArgs {
+            server_tls_key: Some(key.clone()),
+            server_tls_certs: Some(certs.clone()),
+            ..test_args()
+        };
+        args.bind().await.expect("failed to bind server")
+    };
+
+    let cert = rcgen::generate_simple_self_signed(vec!["other.test.example.com".to_string()])
+        .expect("failed to generate certs");
+    let bound = bind()
+        .await
+        .with_sni_pem(
+            "Other.Test.Example.com",
+            cert.key_pair.serialize_pem(),
+            cert.cert.pem(),
+        )
+        .await
+        .expect("failed to load SNI credentials");
+    assert_eq!(bound.tls.sni.len(), 1);
+    assert!(bound.tls.sni.contains_key("other.test.example.com"));
+
+    // The key must match the certificate.
+    let other = rcgen::generate_simple_self_signed(vec!["other.test.example.com".to_string()])
+        .expect("failed to generate certs");
+    match bind()
+        .await
+        .with_sni_pem(
+            "other.test.example.com",
+            cert.key_pair.serialize_pem(),
+            other.cert.pem(),
+        )
+        .await
+    {
+        Err(Error::InvalidTlsCredentials(_)) => {}
+        res => panic!("unexpected result: {:?}", res.map(|_| ())),
+    }
+
+    let missing = TlsPaths {
+        key: TlsKeyPath("/nonexistent/key.pem".into()),
+        certs: TlsCertPath("/nonexistent/certs.pem".into()),
+    };
+    match bind()
+        .await
+        .with_sni_credentials("missing.test.example.com", missing.key, missing.certs)
+        .await
+    {
+        Err(Error::TlsKeyReadError(_)) => {}
+        res => panic!("unexpected result: {:?}", res.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn spawns_tasks_with_spawn() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let (_tempdir, TlsPaths { key, certs }) = gen_keys();
+    let args = ServerArgs {
+        server_tls_key: Some(key),
+        server_tls_certs: Some(certs),
+        ..test_args()
+    };
+    let bound = args.bind().await.expect("failed to bind");
+
+    let spawned = Arc::new(AtomicUsize::new(0));
+    let spawn = {
+        let spawned = spawned.clone();
+        move |task: BoxTask| {
+            spawned.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(task)
+        }
+    };
+    let (_signal, drain) = drain::channel();
+    let server = bound.spawn_on(ok_service::<hyper::body::Incoming>(), drain, spawn);
+    assert_eq!(spawned.load(Ordering::SeqCst), 1);
+
+    // Each accepted connection is spawned with the same implementation.
+    let _conn = TcpStream::connect(server.local_addr())
+        .await
+        .expect("failed to connect");
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while spawned.load(Ordering::SeqCst) < 2 {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("connection task must be spawned");
+    server.abort();
+}
@@ -1,18 +1,19 @@
 #![cfg_attr(feature = "rustls-tls", allow(dead_code))]
 
 use super::*;
-use once_cell::sync::Lazy;
 use openssl::{
     error::ErrorStack,
     pkey::{PKey, Private},
     ssl::{self, Ssl},
     x509::X509,
 };
-use std::pin::Pin;
+use std::{collections::HashMap, pin::Pin};
 use tokio_openssl::SslStream;
 
 pub(in crate::server) type TlsAcceptor = ssl::SslAcceptor;
 
+pub(in crate::server) type SniCredentials = ssl::SslContext;
+
 #[derive(Debug, thiserror::Error)]
 pub(in crate::server) enum AcceptError {
     #[error("failed to construct SSL session from acceptor context: {0}")]
@@ -35,16 +36,74 @@ pub(in crate::server) async fn accept(
     Ok(stream)
 }
 
+/// Records the negotiated TLS version, SNI, and ALPN protocol on the connection's span.
+pub(in crate::server) fn record_session(stream: &SslStream<TcpStream>, span: &tracing::Span) {
+    let ssl = stream.ssl();
+    if let Some(version) = tls_version(stream) {
+        span.record("tls.version", version);
+    }
+    if let Some(sni) = ssl.servername(ssl::NameType::HOST_NAME) {
+        span.record("tls.sni", sni);
+    }
+    if let Some(alpn) = ssl.selected_alpn_protocol() {
+        span.record("tls.alpn", String::from_utf8_lossy(alpn).as_ref());
+    }
+}
+
+/// Returns the negotiated TLS version (e.g. `TLSv1.3`).
+pub(in crate::server) fn tls_version(stream: &SslStream<TcpStream>) -> Option<&'static str> {
+    Some(stream.ssl().version_str())
+}
+
 pub(in crate::server) async fn load_tls(
-    pk: &TlsKeyPath,
-    crts: &TlsCertPath,
+    default: &TlsCredentials,
+    sni: &Arc<HashMap<String, SniCredentials>>,
+    alpn_protocols: &[Vec<u8>],
 ) -> Result<TlsAcceptor, Error> {
-    let key = load_private_key(pk).await.map_err(Error::TlsKeyReadError)?;
-    let certs = load_certs(crts).await.map_err(Error::TlsCertsReadError)?;
-    configure(key, certs).map_err(|error| Error::InvalidTlsCredentials(Box::new(error)))
+    let (key, certs) = load_credentials(default).await?;
+    let mut conn = configure(key, certs, alpn_protocols)
+        .map_err(|error| Error::InvalidTlsCredentials(Box::new(error)))?;
+    if !sni.is_empty() {
+        let by_name = sni.clone();
+        // Switch to the context for the client's SNI hostname, if there is one. Otherwise, the
+        // default credentials are used.
+        conn.set_servername_callback(move |ssl, _| {
+            let ctx = ssl
+                .servername(ssl::NameType::HOST_NAME)
+                .and_then(|name| by_name.get(&name.to_ascii_lowercase()));
+            if let Some(ctx) = ctx {
+                ssl.set_ssl_context(ctx)
+                    .map_err(|_| ssl::SniError::ALERT_FATAL)?;
+            }
+            Ok(())
+        });
+    }
+    Ok(conn.build())
 }
 
-fn configure(key: PKey<Private>, certs: Vec<X509>) -> Result<TlsAcceptor, ErrorStack> {
+/// Loads credentials to be served to clients that request a specific hostname via SNI.
+pub(in crate::server) async fn load_sni_credentials(
+    creds: &TlsCredentials,
+    alpn_protocols: &[Vec<u8>],
+) -> Result<SniCredentials, Error> {
+    let (key, certs) = load_credentials(creds).await?;
+    configure(key, certs, alpn_protocols)
+        .map(|conn| conn.build().into_context())
+        .map_err(|error| Error::InvalidTlsCredentials(Box::new(error)))
+}
+
+async fn load_credentials(creds: &TlsCredentials) -> Result<(PKey<Private>, Vec<X509>), Error> {
+    let (key, certs) = creds.read().await?;
+    let key = parse_private_key(&key).map_err(Error::TlsKeyReadError)?;
+    let certs = parse_certs(&certs).map_err(Error::TlsCertsReadError)?;
+    Ok((key, certs))
+}
+
+fn configure(
+    key: PKey<Private>,
+    certs: Vec<X509>,
+    alpn_protocols: &[Vec<u8>],
+) -> Result<ssl::SslAcceptorBuilder, ErrorStack> {
     // mozilla_intermediate_v5 is the only variant that enables TLSv1.3, so we use that.
     let mut conn = {
         let method = ssl::SslMethod::tls_server();
@@ -55,57 +114,54 @@ fn configure(key: PKey<Private>, certs: Vec<X509>) -> Result<TlsAcceptor, ErrorS
     conn.set_verify(ssl::SslVerifyMode::NONE);
     conn.set_private_key(&key)?;
     conn.set_certificate(&certs[0])?;
+    conn.check_private_key()?;
 
     for c in certs.iter().skip(1) {
         conn.add_extra_chain_cert(c.to_owned())?;
     }
 
-    conn.set_alpn_protos(&ALPN_PROTOCOLS)?;
+    // Select the most-preferred server protocol that is supported by the client.
+    let protocols = alpn_protocols.to_vec();
+    conn.set_alpn_select_callback(move |_, client| {
+        select_alpn_protocol(&protocols, client).ok_or(ssl::AlpnError::NOACK)
+    });
 
-    Ok(conn.build())
+    Ok(conn)
 }
 
-/// ALPN protocols encoded as length-prefixed strings.
+/// Returns the first of the server's protocols that is included in the client's
+/// list of ALPN protocols.
 ///
-/// `openssl` requires that the list of protocols be encoded in the wire format.
-static ALPN_PROTOCOLS: Lazy<Vec<u8>> = Lazy::new(|| {
-    let protocols: &[&[u8]] = &[b"h2", b"http/1.1"];
-    // Allocate a buffer to hold the encoded protocols.
-    let mut bytes = {
-        // One additional byte for each protocol's length prefix.
-        let cap = protocols.len() + protocols.iter().map(|p| p.len()).sum::<usize>();
-        Vec::with_capacity(cap)
-    };
-
-    // Encode each protocol as a length-prefixed string.
-    for p in protocols {
-        if p.is_empty() {
-            continue;
+/// `openssl` provides the client's list of protocols in the wire format, as
+/// length-prefixed strings. The returned protocol references the client's list.
+pub(in crate::server) fn select_alpn_protocol<'c>(
+    server: &[Vec<u8>],
+    client: &'c [u8],
+) -> Option<&'c [u8]> {
+    server.iter().find_map(|p| {
+        let mut rest = client;
+        while let Some((&len, tail)) = rest.split_first() {
+            let (proto, tail) = tail.split_at_checked(len as usize)?;
+            if proto == p.as_slice() {
+                return Some(proto);
+            }
+            rest = tail;
         }
-        // Since we only call this with "h2" and "http/1.1", this assertion
-        // should never be hit.
-        debug_assert!(p.len() <= 255, "ALPN protocols must be less than 256 bytes");
-        bytes.push(p.len() as u8);
-        bytes.extend_from_slice(p);
-    }
-
-    bytes
-});
+        None
+    })
+}
 
-async fn load_certs(TlsCertPath(cp): &TlsCertPath) -> std::io::Result<Vec<X509>> {
-    let pem = tokio::fs::read(cp).await?;
-    let certs = X509::stack_from_pem(&pem)?;
+fn parse_certs(pem: &[u8]) -> std::io::Result<Vec<X509>> {
+    let certs = X509::stack_from_pem(pem)?;
     Ok(certs)
 }
 
-async fn load_private_key(TlsKeyPath(kp): &TlsKeyPath) -> std::io::Result<PKey<Private>> {
-    let pem = tokio::fs::read(kp).await?;
-
+fn parse_private_key(pem: &[u8]) -> std::io::Result<PKey<Private>> {
     // Load and return a single private key. The keyfile should be
     // PEM-encoded.
     // TODO(eliza): Potentially, we may want to support both PEM-encoded and
     // DER-encoded keyfiles, and decide whether to use
     // `PKey::private_key_from_pem` or `PKey::private_key_from_pkcs8` based
     // on the filename extension.
-    Ok(PKey::private_key_from_pem(&pem)?)
+    Ok(PKey::private_key_from_pem(pem)?)
 }
@@ -1,29 +1,97 @@
 use super::*;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tokio_rustls::{
     rustls::{
         self,
+        crypto::CryptoProvider,
         pki_types::{CertificateDer, PrivateKeyDer},
+        server::{ClientHello, ResolvesServerCert},
+        sign::CertifiedKey,
     },
     server::TlsStream,
     TlsAcceptor,
 };
 
+pub(in crate::server) type SniCredentials = Arc<CertifiedKey>;
+
+/// Selects a certificate by the client's SNI hostname, falling back to a default.
+#[derive(Debug)]
+struct SniResolver {
+    default: Arc<CertifiedKey>,
+    by_name: Arc<HashMap<String, SniCredentials>>,
+}
+
 pub(in crate::server) async fn load_tls(
-    pk: &TlsKeyPath,
-    crts: &TlsCertPath,
+    default: &TlsCredentials,
+    sni: &Arc<HashMap<String, SniCredentials>>,
+    alpn_protocols: &[Vec<u8>],
 ) -> Result<TlsAcceptor, Error> {
-    let key = load_private_key(pk).await.map_err(Error::TlsKeyReadError)?;
-    let certs = load_certs(crts).await.map_err(Error::TlsCertsReadError)?;
-    let mut cfg = rustls::ServerConfig::builder()
-        .with_no_client_auth()
-        .with_single_cert(certs, key)
-        .map_err(|err| Error::InvalidTlsCredentials(Box::new(err)))?;
-    cfg.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    let builder = rustls::ServerConfig::builder().with_no_client_auth();
+    let mut cfg = if sni.is_empty() {
+        let (key, certs) = load_credentials(default).await?;
+        builder
+            .with_single_cert(certs, key)
+            .map_err(|err| Error::InvalidTlsCredentials(Box::new(err)))?
+    } else {
+        let provider = builder.crypto_provider().clone();
+        let default = load_certified_key(default, &provider).await?;
+        builder.with_cert_resolver(Arc::new(SniResolver {
+            default,
+            by_name: sni.clone(),
+        }))
+    };
+    cfg.alpn_protocols = alpn_protocols.to_vec();
 
     Ok(TlsAcceptor::from(Arc::new(cfg)))
 }
 
+async fn load_credentials(
+    creds: &TlsCredentials,
+) -> Result<(PrivateKeyDer<'static>, Vec<CertificateDer<'static>>), Error> {
+    let (key, certs) = creds.read().await?;
+    let key = parse_private_key(&key).map_err(Error::TlsKeyReadError)?;
+    let certs = parse_certs(&certs).map_err(Error::TlsCertsReadError)?;
+    Ok((key, certs))
+}
+
+/// Loads credentials to be served to clients that request a specific hostname via SNI.
+pub(in crate::server) async fn load_sni_credentials(
+    creds: &TlsCredentials,
+) -> Result<SniCredentials, Error> {
+    let builder = rustls::ServerConfig::builder().with_no_client_auth();
+    load_certified_key(creds, builder.crypto_provider()).await
+}
+
+async fn load_certified_key(
+    creds: &TlsCredentials,
+    provider: &CryptoProvider,
+) -> Result<Arc<CertifiedKey>, Error> {
+    let (key, certs) = load_credentials(creds).await?;
+    let key = provider
+        .key_provider
+        .load_private_key(key)
+        .map_err(|err| Error::InvalidTlsCredentials(Box::new(err)))?;
+    let key = CertifiedKey::new(certs, key);
+    match key.keys_match() {
+        // Some keys cannot be checked against their certificates.
+        Ok(()) | Err(rustls::Error::InconsistentKeys(rustls::InconsistentKeys::Unknown)) => {}
+        Err(err) => return Err(Error::InvalidTlsCredentials(Box::new(err))),
+    }
+    Ok(Arc::new(key))
+}
+
+// === impl SniResolver ===
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let key = hello
+            .server_name()
+            .and_then(|name| self.by_name.get(&name.to_ascii_lowercase()))
+            .unwrap_or(&self.default);
+        Some(key.clone())
+    }
+}
+
 pub(in crate::server) async fn accept(
     acceptor: &TlsAcceptor,
     sock: TcpStream,
@@ -31,23 +99,44 @@ pub(in crate::server) async fn accept(
     acceptor.accept(sock).await
 }
 
-async fn load_certs(
-    TlsCertPath(cp): &TlsCertPath,
-) -> std::io::Result<Vec<CertificateDer<'static>>> {
-    let pem = tokio::fs::read(cp).await?;
-    rustls_pemfile::certs(&mut pem.as_slice())
+/// Records the negotiated TLS version, SNI, and ALPN protocol on the connection's span.
+pub(in crate::server) fn record_session(stream: &TlsStream<TcpStream>, span: &tracing::Span) {
+    let (_, conn) = stream.get_ref();
+    if let Some(version) = tls_version(stream) {
+        span.record("tls.version", version);
+    }
+    if let Some(sni) = conn.server_name() {
+        span.record("tls.sni", sni);
+    }
+    if let Some(alpn) = conn.alpn_protocol() {
+        span.record("tls.alpn", String::from_utf8_lossy(alpn).as_ref());
+    }
+}
+
+/// Returns the negotiated TLS version, named as by OpenSSL (e.g. `TLSv1.3`).
+pub(in crate::server) fn tls_version(stream: &TlsStream<TcpStream>) -> Option<&'static str> {
+    let (_, conn) = stream.get_ref();
+    Some(match conn.protocol_version()? {
+        rustls::ProtocolVersion::TLSv1_0 => "TLSv1",
+        rustls::ProtocolVersion::TLSv1_1 => "TLSv1.1",
+        rustls::ProtocolVersion::TLSv1_2 => "TLSv1.2",
+        rustls::ProtocolVersion::TLSv1_3 => "TLSv1.3",
+        _ => "unknown",
+    })
+}
+
+fn parse_certs(mut pem: &[u8]) -> std::io::Result<Vec<CertificateDer<'static>>> {
+    rustls_pemfile::certs(&mut pem)
         .map(|res| res.map(CertificateDer::from))
         .collect()
 }
 
-async fn load_private_key(TlsKeyPath(kp): &TlsKeyPath) -> std::io::Result<PrivateKeyDer<'static>> {
-    let pem = tokio::fs::read(kp).await?;
-
-    let mut keys = rustls_pemfile::pkcs8_private_keys(&mut pem.as_slice())
+fn parse_private_key(pem: &[u8]) -> std::io::Result<PrivateKeyDer<'static>> {
+    let mut keys = rustls_pemfile::pkcs8_private_keys(&mut &*pem)
         .map(|res| res.map(PrivateKeyDer::from))
         .collect::<Result<Vec<_>, _>>()?;
     if keys.is_empty() {
-        keys = rustls_pemfile::rsa_private_keys(&mut pem.as_slice())
+        keys = rustls_pemfile::rsa_private_keys(&mut &*pem)
             .map(|res| res.map(PrivateKeyDer::from))
             .collect::<Result<Vec<_>, _>>()?;
     }
@@ -1,26 +1,65 @@
 //! Drives graceful shutdown when the process receives a signal.
+//!
+//! Shutdown may also be initiated programmatically with a [`Trigger`], e.g. when the process is
+//! embedded in an application that handles signals itself.
 
-#[cfg(feature = "runtime")]
 use std::{
     pin::Pin,
     task::{Context, Poll},
 };
-use tokio::signal::unix::{signal, Signal, SignalKind};
+use tokio::{
+    signal::unix::{signal, Signal, SignalKind},
+    sync::mpsc,
+    time::Instant,
+};
 use tracing::debug;
 
 #[cfg_attr(docsrs, doc(cfg(feature = "shutdown")))]
 pub use drain::Watch;
 
-/// Drives shutdown by watching signals
+/// Drives shutdown by watching signals and [`Trigger`]s
 #[derive(Debug)]
 #[must_use = "call `Shutdown::on_signal` to await a signal"]
 #[cfg_attr(docsrs, doc(cfg(feature = "shutdown")))]
 pub struct Shutdown {
-    interrupt: Signal,
-    terminate: Signal,
+    sources: Sources,
+    trigger: Trigger,
     tx: drain::Signal,
+    outcome: tokio::sync::watch::Sender<Option<Outcome>>,
 }
 
+/// Initiates shutdown without a signal
+///
+/// Triggering a shutdown behaves like receiving a signal: the first trigger initiates shutdown and,
+/// if another trigger is received while waiting for [`Watch`] instances to be dropped, the shutdown
+/// is aborted.
+#[derive(Clone, Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "shutdown")))]
+pub struct Trigger(mpsc::UnboundedSender<()>);
+
+/// The events that initiate (or abort) shutdown
+#[derive(Debug)]
+struct Sources {
+    signals: Option<(Signal, Signal)>,
+    triggered: mpsc::UnboundedReceiver<()>,
+}
+
+/// Describes how a shutdown completed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "shutdown")))]
+pub enum Outcome {
+    /// All [`Watch`] instances were released after shutdown was signaled.
+    Graceful,
+
+    /// Shutdown was forced by a second signal before all [`Watch`] instances were released.
+    Aborted,
+}
+
+/// Observes the [`Outcome`] of a [`Shutdown`]
+#[derive(Clone, Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "shutdown")))]
+pub struct OutcomeWatch(tokio::sync::watch::Receiver<Option<Outcome>>);
+
 /// Indicates whether shutdown completed gracefully or was forced by a second signal
 #[derive(Debug, thiserror::Error)]
 #[cfg_attr(docsrs, doc(cfg(feature = "shutdown")))]
@@ -33,6 +72,25 @@ pub struct Aborted(());
 #[error("failed to register signal handler: {0}")]
 pub struct RegisterError(#[from] std::io::Error);
 
+/// A [`tokio::time::Interval`] that stops ticking once shutdown is signaled
+///
+/// This allows periodic tasks to terminate cleanly, e.g.:
+///
+/// ```ignore
+/// let mut interval = Interval::new(shutdown_rx, tokio::time::interval(period));
+/// while let Some(_) = interval.tick().await {
+///     // ...
+/// }
+/// ```
+///
+/// `Interval` also implements `Stream`, so `StreamExt::next` may be used in place of
+/// [`Interval::tick`].
+#[cfg_attr(docsrs, doc(cfg(feature = "shutdown")))]
+pub struct Interval {
+    interval: tokio::time::Interval,
+    shutdown: Option<Pin<Box<dyn std::future::Future<Output = ()> + Send + Sync + 'static>>>,
+}
+
 #[cfg(feature = "runtime")]
 pin_project_lite::pin_project! {
     /// Indicates an error registering a signal handler
@@ -42,6 +100,7 @@ pin_project_lite::pin_project! {
         inner: T,
         #[pin]
         shutdown: Pin<Box<dyn std::future::Future<Output = ()> + Send + Sync + 'static>>,
+        name: Option<String>,
     }
 }
 
@@ -56,66 +115,225 @@ pin_project_lite::pin_project! {
 pub fn sigint_or_sigterm() -> Result<(Shutdown, Watch), RegisterError> {
     let interrupt = signal(SignalKind::interrupt())?;
     let terminate = signal(SignalKind::terminate())?;
+    Ok(channel(Some((interrupt, terminate))))
+}
+
+/// Creates a shutdown channel that does not handle signals
+///
+/// Shutdown is only initiated by a [`Trigger`] obtained from [`Shutdown::trigger`]. This is useful
+/// when the process is embedded in an application that handles signals itself.
+#[cfg_attr(docsrs, doc(cfg(feature = "shutdown")))]
+pub fn without_signals() -> (Shutdown, Watch) {
+    channel(None)
+}
 
+fn channel(signals: Option<(Signal, Signal)>) -> (Shutdown, Watch) {
     let (tx, rx) = drain::channel();
+    let (outcome, _) = tokio::sync::watch::channel(None);
+    let (trigger, triggered) = mpsc::unbounded_channel();
     let shutdown = Shutdown {
-        interrupt,
-        terminate,
+        sources: Sources { signals, triggered },
+        trigger: Trigger(trigger),
         tx,
+        outcome,
     };
-    Ok((shutdown, rx))
+    (shutdown, rx)
 }
 
 impl Shutdown {
+    /// Returns a handle that observes how this shutdown completes
+    pub fn outcome(&self) -> OutcomeWatch {
+        OutcomeWatch(self.outcome.subscribe())
+    }
+
+    /// Returns a handle that initiates shutdown without a signal
+    pub fn trigger(&self) -> Trigger {
+        self.trigger.clone()
+    }
+
     /// Watches for signals and drives shutdown
     ///
-    /// When a `SIGINT` or `SIGTERM` signal is received, the shutdown is initiated, notifying all
-    /// [`Watch`] instances. When all watches are dropped, the shutdown is completed.
+    /// When a `SIGINT` or `SIGTERM` signal is received (or a [`Trigger`] fires), the shutdown is
+    /// initiated, notifying all [`Watch`] instances. When all watches are dropped, the shutdown is
+    /// completed.
     ///
     /// If a second signal is received while waiting for watches to be dropped, this future
     /// completes immediately with an [`Aborted`] error.
     pub async fn signaled(self) -> Result<(), Aborted> {
+        self.signaled_with(std::future::ready(())).await
+    }
+
+    /// Watches for signals and drives shutdown, completing `before_drain` before [`Watch`]
+    /// instances are notified
+    ///
+    /// This may be used, for example, to stop advertising readiness and wait for load balancers
+    /// to stop sending new traffic before the process begins draining.
+    ///
+    /// If a second signal is received before `before_drain` completes, this future completes
+    /// immediately with an [`Aborted`] error.
+    pub async fn signaled_with(
+        self,
+        before_drain: impl std::future::Future<Output = ()>,
+    ) -> Result<(), Aborted> {
         let Self {
-            mut interrupt,
-            mut terminate,
+            mut sources,
             mut tx,
+            outcome,
+            ..
         } = self;
 
         tokio::select! {
-            _ = interrupt.recv() => {
-                debug!("Received SIGINT; draining");
-            },
-
-            _ = terminate.recv() => {
-                debug!("Received SIGTERM; draining");
-            }
+            _ = sources.recv() => {}
 
             _ = tx.closed() => {
                 debug!("All shutdown receivers dropped");
                 // Drain can't do anything if the receivers have been dropped
+                outcome.send_replace(Some(Outcome::Graceful));
                 return Ok(());
             }
         }
 
-        tokio::select! {
-            _ = tx.drain() => {
+        let res = tokio::select! {
+            _ = async move {
+                before_drain.await;
+                debug!("Draining");
+                tx.drain().await
+            } => {
                 debug!("Drained");
                 Ok(())
             },
 
-            _ = interrupt.recv() => {
-                debug!("Received SIGINT; aborting");
+            _ = sources.recv() => {
+                debug!("Aborting");
                 Err(Aborted(()))
             },
+        };
 
-            _ = terminate.recv() => {
-                debug!("Received SIGTERM; aborting");
-                Err(Aborted(()))
+        outcome.send_replace(Some(match res {
+            Ok(()) => Outcome::Graceful,
+            Err(_) => Outcome::Aborted,
+        }));
+        res
+    }
+}
+
+// === impl Trigger ===
+
+impl Trigger {
+    /// Initiates shutdown or, if shutdown has already been initiated, aborts it
+    pub fn shutdown(&self) {
+        // If the shutdown has completed, there's nothing to do.
+        let _ = self.0.send(());
+    }
+}
+
+// === impl Sources ===
+
+impl Sources {
+    /// Waits for a signal or trigger to be received
+    async fn recv(&mut self) {
+        let Self { signals, triggered } = self;
+        let signaled = async move {
+            let Some((interrupt, terminate)) = signals.as_mut() else {
+                return std::future::pending().await;
+            };
+            tokio::select! {
+                _ = interrupt.recv() => debug!("Received SIGINT"),
+                _ = terminate.recv() => debug!("Received SIGTERM"),
             }
+        };
+
+        tokio::select! {
+            _ = signaled => {}
+            Some(()) = triggered.recv() => debug!("Shutdown triggered"),
         }
     }
 }
 
+// === impl OutcomeWatch ===
+
+impl OutcomeWatch {
+    /// Returns the outcome of the shutdown, if it has completed
+    pub fn get(&self) -> Option<Outcome> {
+        *self.0.borrow()
+    }
+
+    /// Waits for the shutdown to complete
+    ///
+    /// Returns `None` if the [`Shutdown`] is dropped before it completes.
+    pub async fn completed(&mut self) -> Option<Outcome> {
+        match self.0.wait_for(Option::is_some).await {
+            Ok(outcome) => *outcome,
+            Err(_) => None,
+        }
+    }
+}
+
+// === impl Interval ===
+
+impl Interval {
+    /// Wraps `interval` so that it stops ticking when the shutdown `watch` fires
+    pub fn new(watch: Watch, interval: tokio::time::Interval) -> Self {
+        let shutdown = Box::pin(async move {
+            let _ = watch.signaled().await;
+        });
+        Self {
+            interval,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// Completes when the next instant in the interval has been reached
+    ///
+    /// Returns `None` once shutdown has been signaled.
+    pub async fn tick(&mut self) -> Option<Instant> {
+        std::future::poll_fn(|cx| self.poll_tick(cx)).await
+    }
+
+    /// Polls for the next instant in the interval to be reached
+    ///
+    /// Returns `Poll::Ready(None)` once shutdown has been signaled.
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Option<Instant>> {
+        // Once shutdown has fired, the interval never ticks again.
+        let Some(shutdown) = self.shutdown.as_mut() else {
+            return Poll::Ready(None);
+        };
+        if shutdown.as_mut().poll(cx).is_ready() {
+            self.shutdown = None;
+            return Poll::Ready(None);
+        }
+
+        self.interval.poll_tick(cx).map(Some)
+    }
+
+    /// Returns a reference to the inner [`tokio::time::Interval`]
+    pub fn get_ref(&self) -> &tokio::time::Interval {
+        &self.interval
+    }
+
+    /// Returns a mutable reference to the inner [`tokio::time::Interval`]
+    pub fn get_mut(&mut self) -> &mut tokio::time::Interval {
+        &mut self.interval
+    }
+}
+
+impl futures_core::Stream for Interval {
+    type Item = Instant;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Instant>> {
+        self.get_mut().poll_tick(cx)
+    }
+}
+
+impl std::fmt::Debug for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interval")
+            .field("interval", &self.interval)
+            .field("signaled", &self.shutdown.is_none())
+            .finish()
+    }
+}
+
 #[cfg(feature = "runtime")]
 impl<T> CancelOnShutdown<T> {
     /// Wraps a `Future` or `Stream` that completes when the shutdown watch fires.
@@ -128,7 +346,18 @@ impl<T> CancelOnShutdown<T> {
         let shutdown = Box::pin(async move {
             let _ = watch.signaled().await;
         });
-        Self { inner, shutdown }
+        Self {
+            inner,
+            shutdown,
+            name: None,
+        }
+    }
+
+    /// Names the wrapped stream so that a debug log is emitted when it ends, indicating whether
+    /// it completed or was cancelled by shutdown.
+    pub(crate) fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
     }
 }
 
@@ -161,12 +390,18 @@ impl<S: futures_core::Stream> futures_core::Stream for CancelOnShutdown<S> {
 
         // Process items from the stream until it is pending (or the stream ends).
         if let Poll::Ready(next) = this.inner.poll_next(cx) {
+            if let (None, Some(name)) = (&next, this.name.as_ref()) {
+                debug!(%name, "Stream completed");
+            }
             return Poll::Ready(next);
         }
 
         // If the stream is pending, register interest in the shutdown watch and end the stream if
         // it has fired.
         if this.shutdown.as_mut().poll(cx).is_ready() {
+            if let Some(name) = this.name.as_ref() {
+                debug!(%name, "Stream cancelled by shutdown");
+            }
             return Poll::Ready(None);
         }
 
@@ -176,7 +411,7 @@ impl<S: futures_core::Stream> futures_core::Stream for CancelOnShutdown<S> {
 
 #[cfg(all(test, feature = "runtime"))]
 mod test {
-    use super::CancelOnShutdown;
+    use super::{CancelOnShutdown, Interval, Outcome};
     use tokio_stream::wrappers::ReceiverStream;
     use tokio_test::{assert_pending, assert_ready, assert_ready_eq, task};
 
@@ -221,4 +456,51 @@ mod test {
         assert_ready!(rx.poll());
         assert_ready!(drain.poll());
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn interval_stops_on_shutdown() {
+        let (shutdown_tx, shutdown_rx) = drain::channel();
+
+        let period = tokio::time::Duration::from_secs(1);
+        let mut interval = Interval::new(shutdown_rx, tokio::time::interval(period));
+        assert!(interval.tick().await.is_some());
+        assert!(interval.tick().await.is_some());
+
+        let mut drain = task::spawn(shutdown_tx.drain());
+        assert_pending!(drain.poll());
+        assert!(interval.tick().await.is_none());
+        assert!(interval.tick().await.is_none());
+        assert_ready!(drain.poll());
+    }
+
+    #[tokio::test]
+    async fn trigger_drives_shutdown() {
+        let (shutdown, shutdown_rx) = super::without_signals();
+        let trigger = shutdown.trigger();
+        let mut outcome = shutdown.outcome();
+        let mut signaled = task::spawn(shutdown.signaled());
+        assert_pending!(signaled.poll());
+
+        let mut released = task::spawn(shutdown_rx.signaled());
+        assert_pending!(released.poll());
+        trigger.shutdown();
+        assert_pending!(signaled.poll());
+        drop(assert_ready!(released.poll()));
+
+        assert!(assert_ready!(signaled.poll()).is_ok());
+        assert_eq!(outcome.completed().await, Some(Outcome::Graceful));
+    }
+
+    #[tokio::test]
+    async fn second_trigger_aborts() {
+        let (shutdown, shutdown_rx) = super::without_signals();
+        let trigger = shutdown.trigger();
+        let mut signaled = task::spawn(shutdown.signaled());
+
+        trigger.shutdown();
+        assert_pending!(signaled.poll());
+        trigger.shutdown();
+        assert!(assert_ready!(signaled.poll()).is_err());
+        drop(shutdown_rx);
+    }
 }